@@ -1,4 +1,4 @@
-use libusb::{DeviceHandleSyncApi, TransferType, Direction, DeviceDescriptor};
+use libusb::{DeviceHandleSyncApi, TransferType, Direction, DeviceDescriptor, EndpointLocation};
 
 use std::slice;
 use std::str::FromStr;
@@ -7,14 +7,6 @@ use std::process::exit;
 use ::*;
 
 
-#[derive(Debug)]
-struct Endpoint {
-    config: u8,
-    iface: u8,
-    setting: u8,
-    address: u8
-}
-
 pub fn main(context: &Context) {
     let args: Vec<String> = std::env::args().collect();
     if args.len() < 3 {
@@ -59,65 +51,51 @@ fn read_device(device: &mut Device, device_desc: &DeviceDescriptor, handle: &mut
     let languages = try!(handle.read_languages(timeout));
     println!("Active configuration: {}", try!(handle.active_configuration()));
     println!("Languages: {:?}", languages);
-    if languages.len() > 0 {
-        let language = languages[0];
+    if let Some(language) = libusb::choose_language(&["en-US"], &languages) {
+        println!("Reading strings in {}", language);
         println!("Manufacturer: {:?}", handle.read_manufacturer_string(language, device_desc, timeout).ok());
         println!("Product: {:?}", handle.read_product_string(language, device_desc, timeout).ok());
         println!("Serial Number: {:?}", handle.read_serial_number_string(language, device_desc, timeout).ok());
     }
-    match find_readable_endpoint(device, device_desc, TransferType::Interrupt) {
-        Some(endpoint) => read_endpoint(handle, endpoint, TransferType::Interrupt),
+    match try!(find_readable_endpoint(device, TransferType::Interrupt)) {
+        Some(endpoint) => read_endpoint(handle, endpoint),
         None => println!("No readable interrupt endpoint")
     }
-    match find_readable_endpoint(device, device_desc, TransferType::Bulk) {
-        Some(endpoint) => read_endpoint(handle, endpoint, TransferType::Bulk),
+    match try!(find_readable_endpoint(device, TransferType::Bulk)) {
+        Some(endpoint) => read_endpoint(handle, endpoint),
         None => println!("No readable bulk endpoint")
     }
     Ok(())
 }
 
-fn find_readable_endpoint(device: &mut Device, device_desc: &DeviceDescriptor, transfer_type: TransferType) -> Option<Endpoint> {
-    for n in 0..device_desc.num_configurations() {
-        let config_desc = match device.config_descriptor(n) {
-            Ok(c) => c,
-            Err(_) => continue
-        };
-        for interface in config_desc.interfaces() {
-            for interface_desc in interface.descriptors() {
-                for endpoint_desc in interface_desc.endpoint_descriptors() {
-                    if endpoint_desc.direction() == Direction::In && endpoint_desc.transfer_type() == transfer_type {
-                        return Some(Endpoint {
-                            config: config_desc.number(),
-                            iface: interface_desc.interface_number(),
-                            setting: interface_desc.setting_number(),
-                            address: endpoint_desc.address()
-                        });
-                    }
-                }
-            }
-        }
-    }
-    None
+fn find_readable_endpoint(device: &mut Device, transfer_type: TransferType) -> libusb::Result<Option<EndpointLocation>> {
+    device.find_endpoints()
+        .transfer_type(transfer_type)
+        .direction(Direction::In)
+        .first()
 }
 
-fn read_endpoint(handle: &mut DeviceHandle, endpoint: Endpoint, transfer_type: TransferType) {
+fn read_endpoint(handle: &mut DeviceHandle, endpoint: EndpointLocation) {
     println!("Reading from endpoint: {:?}", endpoint);
-    let has_kernel_driver = match handle.kernel_driver_active(endpoint.iface) {
-        Ok(true) => {
-            handle.detach_kernel_driver(endpoint.iface).ok();
-            true
-        },
-        _ => false
-    };
-    println!(" - kernel driver? {}", has_kernel_driver);
+
+    // setting the configuration fails while a kernel driver has an interface claimed, even if
+    // the configuration is already active
+    if handle.active_configuration().ok() != Some(endpoint.config()) {
+        if let Err(err) = handle.set_active_configuration(endpoint.config()) {
+            println!("could not configure endpoint: {}", err);
+            return;
+        }
+    }
+
     match configure_endpoint(handle, &endpoint) {
-        Ok(_) => {
+        Ok(iface) => {
+            println!(" - kernel driver? {}", iface.kernel_driver_detached());
             let mut vec = Vec::<u8>::with_capacity(256);
             let mut buf = unsafe { slice::from_raw_parts_mut((&mut vec[..]).as_mut_ptr(), vec.capacity()) };
             let timeout = Duration::from_secs(1);
-            match transfer_type {
+            match endpoint.transfer_type() {
                 TransferType::Interrupt => {
-                    match handle.read_interrupt(endpoint.address, buf, timeout) {
+                    match iface.read_interrupt(endpoint.address(), buf, timeout) {
                         Ok(len) => {
                             unsafe { vec.set_len(len) };
                             println!(" - read: {:?}", vec);
@@ -126,7 +104,7 @@ fn read_endpoint(handle: &mut DeviceHandle, endpoint: Endpoint, transfer_type: T
                     }
                 },
                 TransferType::Bulk => {
-                    match handle.read_bulk(endpoint.address, buf, timeout) {
+                    match iface.read_bulk(endpoint.address(), buf, timeout) {
                         Ok(len) => {
                             unsafe { vec.set_len(len) };
                             println!(" - read: {:?}", vec);
@@ -139,14 +117,10 @@ fn read_endpoint(handle: &mut DeviceHandle, endpoint: Endpoint, transfer_type: T
         },
         Err(err) => println!("could not configure endpoint: {}", err)
     }
-    if has_kernel_driver {
-        handle.attach_kernel_driver(endpoint.iface).ok();
-    }
 }
 
-fn configure_endpoint<'a>(handle: &'a mut DeviceHandle, endpoint: &Endpoint) -> libusb::Result<()> {
-    try!(handle.set_active_configuration(endpoint.config));
-    try!(handle.claim_interface(endpoint.iface));
-    try!(handle.set_alternate_setting(endpoint.iface, endpoint.setting));
-    Ok(())
+fn configure_endpoint<'dh, 'ctx>(handle: &'dh DeviceHandle<'ctx>, endpoint: &EndpointLocation) -> libusb::Result<ClaimedInterface<'dh, 'ctx>> {
+    let iface = try!(handle.claim_detaching_kernel_driver(endpoint.interface()));
+    try!(iface.set_alternate_setting(endpoint.setting()));
+    Ok(iface)
 }
@@ -0,0 +1,419 @@
+use std::fmt;
+use std::time::Duration;
+
+use config_descriptor::ConfigDescriptor;
+use interface_descriptor::InterfaceDescriptor;
+use endpoint_descriptor::EndpointDescriptor;
+use fields::{Speed, TransferType, speed_name, transfer_type_name};
+use error::Error;
+
+/// The periodic bandwidth that an endpoint reserves on the bus.
+#[derive(Debug,PartialEq,Eq,Clone,Copy,Hash)]
+pub struct EndpointBandwidth {
+    interface: u8,
+    setting: u8,
+    address: u8,
+    transfer_type: TransferType,
+    bytes_per_interval: u32,
+    overhead_per_interval: u32,
+    period: Duration,
+}
+
+impl EndpointBandwidth {
+    /// Returns the bandwidth reserved by `endpoint`, or `None` if it isn't a periodic endpoint.
+    fn new(setting: &InterfaceDescriptor, endpoint: &EndpointDescriptor, speed: Speed) -> Option<Self> {
+        let interval = match endpoint.service_interval(speed) {
+            Some(interval) => interval,
+            None => return None,
+        };
+
+        let transfer_type = endpoint.transfer_type();
+        let packet_size = (endpoint.max_packet_size() & 0x07FF) as u32;
+        let bytes_per_interval = endpoint.max_bytes_per_interval();
+
+        let transactions = match packet_size {
+            0 => 0,
+            n => (bytes_per_interval + n - 1) / n,
+        };
+
+        Some(EndpointBandwidth {
+            interface: setting.interface_number(),
+            setting: setting.setting_number(),
+            address: endpoint.address(),
+            transfer_type: transfer_type,
+            bytes_per_interval: bytes_per_interval,
+            overhead_per_interval: transactions * transaction_overhead(speed, transfer_type),
+            period: period(transfer_type, speed, interval),
+        })
+    }
+
+    /// Returns the number of the interface that the endpoint belongs to.
+    pub fn interface_number(&self) -> u8 {
+        self.interface
+    }
+
+    /// Returns the number of the alternate setting that the endpoint belongs to.
+    pub fn setting_number(&self) -> u8 {
+        self.setting
+    }
+
+    /// Returns the endpoint's address.
+    pub fn address(&self) -> u8 {
+        self.address
+    }
+
+    /// Returns the endpoint's transfer type.
+    pub fn transfer_type(&self) -> TransferType {
+        self.transfer_type
+    }
+
+    /// Returns the maximum number of data bytes that the endpoint transfers in a service interval.
+    pub fn bytes_per_interval(&self) -> u32 {
+        self.bytes_per_interval
+    }
+
+    /// Returns the protocol overhead of the endpoint's transactions in a service interval, in
+    /// bytes.
+    ///
+    /// This is the overhead listed by the USB 2.0 specification in tables 5-4 to 5-9 (tokens,
+    /// handshakes, CRCs and inter-packet delays). It's zero at SuperSpeed, where the overhead isn't
+    /// modelled.
+    pub fn overhead_per_interval(&self) -> u32 {
+        self.overhead_per_interval
+    }
+
+    /// Returns the period at which the host reserves bandwidth for the endpoint.
+    ///
+    /// This is the endpoint's service interval, except for full- and low-speed interrupt
+    /// endpoints: hosts poll those at the largest power-of-two number of frames that doesn't
+    /// exceed `bInterval`.
+    pub fn period(&self) -> Duration {
+        self.period
+    }
+
+    /// Returns the bandwidth reserved by the endpoint, including protocol overhead, in bytes per
+    /// second.
+    pub fn bytes_per_second(&self) -> u64 {
+        let bytes = (self.bytes_per_interval + self.overhead_per_interval) as u64;
+
+        bytes * 1_000_000_000 / nanos(self.period)
+    }
+}
+
+impl fmt::Display for EndpointBandwidth {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(fmt, "interface {}, alternate setting {}, endpoint 0x{:02x} ({}): {} bytes every {}, {} bytes/s",
+               self.interface,
+               self.setting,
+               self.address,
+               transfer_type_name(self.transfer_type),
+               self.bytes_per_interval,
+               format_period(self.period),
+               self.bytes_per_second())
+    }
+}
+
+
+/// The periodic bandwidth needed by a combination of alternate settings.
+///
+/// Isochronous and interrupt endpoints reserve bandwidth on the bus when their alternate setting
+/// is selected. Hosts refuse to select a setting, usually with `Error::NoMem` or `Error::Io`,
+/// when the endpoints of all devices on the bus would need more than the share of the bus that
+/// USB reserves for periodic transfers: 90% of a frame at full and low speed, 80% of a microframe
+/// at high speed and 90% of the bus at SuperSpeed.
+///
+/// The calculation covers a single device and assumes that the host spreads endpoints evenly
+/// over frames, so a combination that fits can still fail when other devices share the bus.
+///
+/// ## Example
+///
+/// ```
+/// let bytes = [
+///     0x09, 0x02, 0x22, 0x00, 0x01, 0x01, 0x00, 0x80, 0x32,
+///     0x09, 0x04, 0x00, 0x00, 0x00, 0x0E, 0x02, 0x00, 0x00,
+///     0x09, 0x04, 0x00, 0x01, 0x01, 0x0E, 0x02, 0x00, 0x00,
+///     0x07, 0x05, 0x81, 0x05, 0x00, 0x14, 0x01,
+/// ];
+///
+/// let config = libusb::ConfigDescriptor::from_bytes(&bytes).unwrap();
+/// let usage = libusb::BandwidthUsage::for_config(&config, &[(0, 1)], libusb::Speed::High).unwrap();
+///
+/// if !usage.fits() {
+///     println!("{}", usage);
+/// }
+/// ```
+#[derive(Debug,PartialEq,Eq,Clone)]
+pub struct BandwidthUsage {
+    speed: Speed,
+    endpoints: Vec<EndpointBandwidth>,
+}
+
+impl BandwidthUsage {
+    /// Creates an empty calculation for a device operating at `speed`.
+    pub fn new(speed: Speed) -> Self {
+        BandwidthUsage {
+            speed: speed,
+            endpoints: Vec::new(),
+        }
+    }
+
+    /// Calculates the bandwidth needed by a configuration with the given alternate settings.
+    ///
+    /// `settings` holds pairs of interface and alternate setting numbers. Interfaces that aren't
+    /// listed use alternate setting 0, which is selected when the configuration is set.
+    ///
+    /// ## Errors
+    ///
+    /// Returns `Error::NotFound` if the configuration doesn't have one of the interfaces or
+    /// alternate settings.
+    pub fn for_config(config: &ConfigDescriptor, settings: &[(u8, u8)], speed: Speed) -> ::Result<Self> {
+        let mut usage = BandwidthUsage::new(speed);
+
+        for &(interface, _) in settings {
+            if !config.interfaces().any(|i| i.number() == interface) {
+                return Err(Error::NotFound);
+            }
+        }
+
+        for interface in config.interfaces() {
+            let setting_number = settings.iter()
+                .find(|&&(i, _)| i == interface.number())
+                .map_or(0, |&(_, setting)| setting);
+
+            match interface.descriptors().find(|d| d.setting_number() == setting_number) {
+                Some(setting) => usage.add_setting(&setting),
+                None => return Err(Error::NotFound),
+            }
+        }
+
+        Ok(usage)
+    }
+
+    /// Adds the periodic endpoints of an alternate setting.
+    pub fn add_setting(&mut self, setting: &InterfaceDescriptor) {
+        let speed = self.speed;
+
+        self.endpoints.extend(setting.endpoint_descriptors().filter_map(|endpoint| {
+            EndpointBandwidth::new(setting, &endpoint, speed)
+        }));
+    }
+
+    /// Returns the device speed used for the calculation.
+    pub fn speed(&self) -> Speed {
+        self.speed
+    }
+
+    /// Returns the periodic endpoints that reserve bandwidth.
+    pub fn endpoints(&self) -> &[EndpointBandwidth] {
+        &self.endpoints
+    }
+
+    /// Returns the total bandwidth reserved by the periodic endpoints, in bytes per second.
+    pub fn bytes_per_second(&self) -> u64 {
+        self.endpoints.iter().map(|e| e.bytes_per_second()).sum()
+    }
+
+    /// Returns the bus bandwidth available to periodic transfers, in bytes per second.
+    ///
+    /// Returns `None` if the speed is unknown.
+    pub fn limit(&self) -> Option<u64> {
+        match self.speed {
+            Speed::Unknown => None,
+            Speed::Low     => Some(168_750),
+            Speed::Full    => Some(1_350_000),
+            Speed::High    => Some(48_000_000),
+            Speed::Super   => Some(450_000_000),
+        }
+    }
+
+    /// Returns `true` if the periodic endpoints fit in the bus bandwidth available to periodic
+    /// transfers.
+    ///
+    /// Endpoints of devices at an unknown speed have no service interval and reserve nothing, so
+    /// such a calculation always fits.
+    pub fn fits(&self) -> bool {
+        self.limit().map_or(true, |limit| self.bytes_per_second() <= limit)
+    }
+}
+
+impl fmt::Display for BandwidthUsage {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        let total = self.bytes_per_second();
+
+        match self.limit() {
+            Some(limit) => {
+                let permille = total * 1000 / limit;
+
+                write!(fmt, "periodic bandwidth at {}: {} of {} bytes/s ({}.{}%)",
+                       speed_name(self.speed), total, limit, permille / 10, permille % 10)?;
+            },
+            None => {
+                write!(fmt, "periodic bandwidth at {}: {} bytes/s", speed_name(self.speed), total)?;
+            },
+        }
+
+        for endpoint in &self.endpoints {
+            write!(fmt, "\n  {}", endpoint)?;
+        }
+
+        Ok(())
+    }
+}
+
+
+/// Returns the protocol overhead of a single transaction, in bytes at the bus speed.
+fn transaction_overhead(speed: Speed, transfer_type: TransferType) -> u32 {
+    match (speed, transfer_type) {
+        (Speed::Full, TransferType::Isochronous) => 9,
+        (Speed::Full, TransferType::Interrupt)   => 13,
+        (Speed::Low,  TransferType::Interrupt)   => 13,
+        (Speed::High, TransferType::Isochronous) => 38,
+        (Speed::High, TransferType::Interrupt)   => 55,
+        _ => 0,
+    }
+}
+
+fn period(transfer_type: TransferType, speed: Speed, interval: Duration) -> Duration {
+    match (transfer_type, speed) {
+        (TransferType::Interrupt, Speed::Low) |
+        (TransferType::Interrupt, Speed::Full) => {
+            let frames = nanos(interval) / 1_000_000;
+            let mut period = 1;
+
+            while period * 2 <= frames {
+                period *= 2;
+            }
+
+            Duration::from_millis(period)
+        },
+        _ => interval,
+    }
+}
+
+fn nanos(duration: Duration) -> u64 {
+    duration.as_secs() * 1_000_000_000 + duration.subsec_nanos() as u64
+}
+
+fn format_period(period: Duration) -> String {
+    let nanos = nanos(period);
+
+    if nanos % 1_000_000 == 0 {
+        format!("{}ms", nanos / 1_000_000)
+    }
+    else {
+        format!("{}us", nanos / 1_000)
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+    use descriptor_builder::{ConfigDescriptorBuilder, InterfaceDescriptorBuilder, EndpointDescriptorBuilder};
+    use config_descriptor::ConfigDescriptor;
+    use test_helpers::build_config;
+    use fields::{Speed, TransferType};
+    use error::Error;
+    use super::*;
+
+    /// A streaming interface with an idle alternate setting 0 and a high-bandwidth isochronous
+    /// alternate setting 1, next to an interrupt interface.
+    fn camera() -> ConfigDescriptor {
+        build_config(ConfigDescriptorBuilder::new(1)
+            .interface(InterfaceDescriptorBuilder::new(0)
+                .endpoint(EndpointDescriptorBuilder::new(0x83, TransferType::Interrupt).max_packet_size(16).interval(4)))
+            .interface(InterfaceDescriptorBuilder::new(1))
+            .interface(InterfaceDescriptorBuilder::new(1).setting_number(1)
+                .endpoint(EndpointDescriptorBuilder::new(0x81, TransferType::Isochronous).max_packet_size(0x1400).interval(1))))
+    }
+
+    #[test]
+    fn it_counts_only_periodic_endpoints_of_selected_settings() {
+        let usage = BandwidthUsage::for_config(&camera(), &[], Speed::High).unwrap();
+
+        assert_eq!(vec![0x83], usage.endpoints().iter().map(|e| e.address()).collect::<Vec<_>>());
+        assert_eq!((16 + 55) * 1000, usage.bytes_per_second());
+        assert!(usage.fits());
+    }
+
+    #[test]
+    fn it_includes_high_bandwidth_transactions_and_overhead() {
+        let usage = BandwidthUsage::for_config(&camera(), &[(1, 1)], Speed::High).unwrap();
+        let endpoint = usage.endpoints()[1];
+
+        assert_eq!((1, 1, 0x81), (endpoint.interface_number(), endpoint.setting_number(), endpoint.address()));
+        assert_eq!(3072, endpoint.bytes_per_interval());
+        assert_eq!(3 * 38, endpoint.overhead_per_interval());
+        assert_eq!(Duration::new(0, 125_000), endpoint.period());
+        assert_eq!((3072 + 114) * 8000, endpoint.bytes_per_second());
+        assert_eq!(71_000 + 25_488_000, usage.bytes_per_second());
+        assert!(usage.fits());
+    }
+
+    #[test]
+    fn it_reports_settings_that_exceed_the_bus_limit() {
+        let config = build_config(ConfigDescriptorBuilder::new(1)
+            .interface(InterfaceDescriptorBuilder::new(0)
+                .endpoint(EndpointDescriptorBuilder::new(0x81, TransferType::Isochronous).max_packet_size(1023).interval(1)))
+            .interface(InterfaceDescriptorBuilder::new(1)
+                .endpoint(EndpointDescriptorBuilder::new(0x02, TransferType::Isochronous).max_packet_size(512).interval(1))));
+
+        let usage = BandwidthUsage::for_config(&config, &[], Speed::Full).unwrap();
+
+        assert_eq!((1023 + 9 + 512 + 9) * 1000, usage.bytes_per_second());
+        assert_eq!(Some(1_350_000), usage.limit());
+        assert!(!usage.fits());
+        assert_eq!("periodic bandwidth at full speed: 1553000 of 1350000 bytes/s (115.0%)\n  \
+                    interface 0, alternate setting 0, endpoint 0x81 (isochronous): 1023 bytes every 1ms, 1032000 bytes/s\n  \
+                    interface 1, alternate setting 0, endpoint 0x02 (isochronous): 512 bytes every 1ms, 521000 bytes/s",
+                   usage.to_string());
+    }
+
+    #[test]
+    fn it_reserves_full_speed_interrupt_bandwidth_at_power_of_two_periods() {
+        let config = build_config(ConfigDescriptorBuilder::new(1)
+            .interface(InterfaceDescriptorBuilder::new(0)
+                .endpoint(EndpointDescriptorBuilder::new(0x81, TransferType::Interrupt).max_packet_size(8).interval(10))));
+
+        let usage = BandwidthUsage::for_config(&config, &[], Speed::Full).unwrap();
+
+        assert_eq!(Duration::from_millis(8), usage.endpoints()[0].period());
+        assert_eq!((8 + 13) * 125, usage.bytes_per_second());
+    }
+
+    #[test]
+    fn it_uses_companion_bytes_per_interval_at_super_speed() {
+        let config = build_config(ConfigDescriptorBuilder::new(1)
+            .interface(InterfaceDescriptorBuilder::new(0)
+                .endpoint(EndpointDescriptorBuilder::new(0x81, TransferType::Isochronous).max_packet_size(1024).interval(4)
+                    .ss_companion(3, 0, 4096))));
+
+        let usage = BandwidthUsage::for_config(&config, &[], Speed::Super).unwrap();
+
+        assert_eq!(Duration::from_millis(1), usage.endpoints()[0].period());
+        assert_eq!(0, usage.endpoints()[0].overhead_per_interval());
+        assert_eq!(4_096_000, usage.bytes_per_second());
+    }
+
+    #[test]
+    fn it_reserves_nothing_at_unknown_speed() {
+        let usage = BandwidthUsage::for_config(&camera(), &[(1, 1)], Speed::Unknown).unwrap();
+
+        assert!(usage.endpoints().is_empty());
+        assert_eq!(None, usage.limit());
+        assert!(usage.fits());
+    }
+
+    #[test]
+    fn it_rejects_unknown_interfaces_and_settings() {
+        match BandwidthUsage::for_config(&camera(), &[(2, 0)], Speed::High) {
+            Err(Error::NotFound) => (),
+            other => panic!("unexpected result: {:?}", other),
+        }
+
+        match BandwidthUsage::for_config(&camera(), &[(1, 2)], Speed::High) {
+            Err(Error::NotFound) => (),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+}
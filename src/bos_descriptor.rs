@@ -0,0 +1,977 @@
+use std::fmt;
+use std::str;
+
+use libusb::*;
+
+use fields::{Speed, Version};
+use descriptor_parser::{check_header, read_u16};
+
+
+const CAP_USB_2_0_EXTENSION: u8 = 0x02;
+const CAP_SUPERSPEED_USB: u8 = 0x03;
+const CAP_CONTAINER_ID: u8 = 0x04;
+const CAP_PLATFORM: u8 = 0x05;
+const CAP_SUPERSPEED_PLUS: u8 = 0x0A;
+const CAP_BILLBOARD: u8 = 0x0D;
+
+/// Describes a device's Binary Object Store (BOS).
+///
+/// The BOS descriptor is the root of a set of device capability descriptors. Devices that report
+/// USB version 2.01 or later publish it to advertise features such as link power management,
+/// SuperSpeed operation and platform-specific capabilities.
+#[derive(Debug,PartialEq,Eq,Clone,Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct BosDescriptor {
+    capabilities: Vec<DeviceCapability>,
+}
+
+impl BosDescriptor {
+    /// Parses a BOS descriptor and its device capabilities from their raw bytes.
+    ///
+    /// `bytes` must hold the whole BOS as returned by a `GET_DESCRIPTOR` request, `wTotalLength`
+    /// bytes in all. Bytes after `wTotalLength` are ignored. The capabilities are taken from the
+    /// descriptors that are present, not from the `bNumDeviceCaps` field.
+    ///
+    /// ## Errors
+    ///
+    /// * `Custom` if the bytes are malformed, with a message that describes the problem.
+    pub fn from_bytes(bytes: &[u8]) -> ::Result<Self> {
+        let header_length = check_header(bytes, LIBUSB_DT_BOS, 5, "BOS")?;
+        let total_length = read_u16(bytes, 2) as usize;
+
+        if total_length < header_length {
+            return Err(format!("BOS descriptor has wTotalLength {}, which is shorter than its bLength {}", total_length, header_length).into());
+        }
+
+        if total_length > bytes.len() {
+            return Err(format!("BOS descriptor has wTotalLength {}, but only {} bytes are available", total_length, bytes.len()).into());
+        }
+
+        let bytes = &bytes[..total_length];
+        let mut capabilities = Vec::new();
+        let mut offset = header_length;
+
+        while offset < bytes.len() {
+            let capability = parse_capability(&bytes[offset..])
+                .map_err(|e| ::Error::Custom(format!("device capability at offset {}: {}", offset, e)))?;
+
+            offset += bytes[offset] as usize;
+            capabilities.push(capability);
+        }
+
+        Ok(BosDescriptor { capabilities: capabilities })
+    }
+
+    /// Returns the device's capabilities in the order in which they appear in the BOS.
+    pub fn capabilities(&self) -> &[DeviceCapability] {
+        &self.capabilities
+    }
+
+    /// Returns the number of device capabilities.
+    pub fn num_capabilities(&self) -> u8 {
+        self.capabilities.len() as u8
+    }
+
+    /// Returns the USB 2.0 extension capability, if the device has one.
+    pub fn usb_2_0_extension(&self) -> Option<&Usb2ExtensionCapability> {
+        self.capabilities.iter().filter_map(|capability| match *capability {
+            DeviceCapability::Usb2Extension(ref c) => Some(c),
+            _ => None,
+        }).next()
+    }
+
+    /// Returns the SuperSpeed USB capability, if the device has one.
+    pub fn superspeed(&self) -> Option<&SuperSpeedCapability> {
+        self.capabilities.iter().filter_map(|capability| match *capability {
+            DeviceCapability::SuperSpeed(ref c) => Some(c),
+            _ => None,
+        }).next()
+    }
+
+    /// Returns the SuperSpeedPlus USB capability, if the device has one.
+    pub fn superspeed_plus(&self) -> Option<&SuperSpeedPlusCapability> {
+        self.capabilities.iter().filter_map(|capability| match *capability {
+            DeviceCapability::SuperSpeedPlus(ref c) => Some(c),
+            _ => None,
+        }).next()
+    }
+
+    /// Returns the device's container ID, if it has one.
+    pub fn container_id(&self) -> Option<Uuid> {
+        self.capabilities.iter().filter_map(|capability| match *capability {
+            DeviceCapability::ContainerId(uuid) => Some(uuid),
+            _ => None,
+        }).next()
+    }
+
+    /// Returns the platform capability with the given UUID, if the device has one.
+    pub fn platform_capability(&self, uuid: Uuid) -> Option<&PlatformCapability> {
+        self.capabilities.iter().filter_map(|capability| match *capability {
+            DeviceCapability::Platform(ref c) if c.uuid == uuid => Some(c),
+            _ => None,
+        }).next()
+    }
+}
+
+/// A device capability from the BOS.
+#[derive(Debug,PartialEq,Eq,Clone,Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum DeviceCapability {
+    /// The USB 2.0 extension capability, which describes link power management support.
+    Usb2Extension(Usb2ExtensionCapability),
+
+    /// The SuperSpeed USB capability.
+    SuperSpeed(SuperSpeedCapability),
+
+    /// The SuperSpeedPlus USB capability.
+    SuperSpeedPlus(SuperSpeedPlusCapability),
+
+    /// The container ID, which is the same for every function of a multi-function device.
+    ContainerId(Uuid),
+
+    /// A platform or vendor specific capability, such as WebUSB or Microsoft OS 2.0 descriptors.
+    Platform(PlatformCapability),
+
+    /// The Billboard capability of a USB Type-C device that failed to enter an alternate mode.
+    Billboard(BillboardCapability),
+
+    /// A capability that this crate doesn't parse.
+    Unknown(UnknownCapability),
+}
+
+impl DeviceCapability {
+    /// Returns the capability's type code (`bDevCapabilityType`).
+    pub fn capability_type(&self) -> u8 {
+        match *self {
+            DeviceCapability::Usb2Extension(_)  => CAP_USB_2_0_EXTENSION,
+            DeviceCapability::SuperSpeed(_)     => CAP_SUPERSPEED_USB,
+            DeviceCapability::SuperSpeedPlus(_) => CAP_SUPERSPEED_PLUS,
+            DeviceCapability::ContainerId(_)    => CAP_CONTAINER_ID,
+            DeviceCapability::Platform(_)       => CAP_PLATFORM,
+            DeviceCapability::Billboard(_)      => CAP_BILLBOARD,
+            DeviceCapability::Unknown(ref c)    => c.capability_type(),
+        }
+    }
+}
+
+/// The USB 2.0 extension capability.
+#[derive(Debug,PartialEq,Eq,Clone,Copy,Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Usb2ExtensionCapability {
+    attributes: u32,
+}
+
+impl Usb2ExtensionCapability {
+    /// Indicates if the device supports the link power management (LPM) protocol.
+    pub fn lpm_supported(&self) -> bool {
+        self.attributes & 0x0000_0002 != 0
+    }
+
+    /// Indicates if the device supports BESL and alternate HIRD definitions for LPM.
+    pub fn besl_supported(&self) -> bool {
+        self.attributes & 0x0000_0004 != 0
+    }
+
+    /// Returns the device's recommended baseline BESL value, if it has one.
+    pub fn baseline_besl(&self) -> Option<u8> {
+        match self.attributes & 0x0000_0008 {
+            0 => None,
+            _ => Some((self.attributes >> 8) as u8 & 0x0F),
+        }
+    }
+
+    /// Returns the device's recommended deep BESL value, if it has one.
+    pub fn deep_besl(&self) -> Option<u8> {
+        match self.attributes & 0x0000_0010 {
+            0 => None,
+            _ => Some((self.attributes >> 12) as u8 & 0x0F),
+        }
+    }
+
+    /// Returns the raw `bmAttributes` field.
+    pub fn attributes(&self) -> u32 {
+        self.attributes
+    }
+}
+
+/// The SuperSpeed USB capability.
+#[derive(Debug,PartialEq,Eq,Clone,Copy,Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SuperSpeedCapability {
+    attributes: u8,
+    speeds_supported: u16,
+    functionality_support: u8,
+    u1_exit_latency: u8,
+    u2_exit_latency: u16,
+}
+
+impl SuperSpeedCapability {
+    /// Indicates if the device can generate latency tolerance messages (LTM).
+    pub fn ltm_capable(&self) -> bool {
+        self.attributes & 0x02 != 0
+    }
+
+    /// Indicates if the device supports operating at the given speed.
+    pub fn supports_speed(&self, speed: Speed) -> bool {
+        let bit = match speed {
+            Speed::Low     => 0x0001,
+            Speed::Full    => 0x0002,
+            Speed::High    => 0x0004,
+            Speed::Super   => 0x0008,
+            Speed::Unknown => return false,
+        };
+
+        self.speeds_supported & bit != 0
+    }
+
+    /// Returns the raw `wSpeedsSupported` field.
+    pub fn speeds_supported(&self) -> u16 {
+        self.speeds_supported
+    }
+
+    /// Returns the lowest speed at which all of the device's functionality is available.
+    pub fn functionality_support(&self) -> Speed {
+        match self.functionality_support {
+            0 => Speed::Low,
+            1 => Speed::Full,
+            2 => Speed::High,
+            3 => Speed::Super,
+            _ => Speed::Unknown,
+        }
+    }
+
+    /// Returns the U1 device exit latency in microseconds.
+    pub fn u1_exit_latency(&self) -> u8 {
+        self.u1_exit_latency
+    }
+
+    /// Returns the U2 device exit latency in microseconds.
+    pub fn u2_exit_latency(&self) -> u16 {
+        self.u2_exit_latency
+    }
+}
+
+/// The SuperSpeedPlus USB capability.
+#[derive(Debug,PartialEq,Eq,Clone,Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SuperSpeedPlusCapability {
+    attributes: u32,
+    functionality_support: u16,
+    sublink_speeds: Vec<SublinkSpeed>,
+}
+
+impl SuperSpeedPlusCapability {
+    /// Returns the number of sublink speed IDs that the device supports.
+    pub fn num_sublink_speed_ids(&self) -> u8 {
+        ((self.attributes >> 5) & 0x0F) as u8 + 1
+    }
+
+    /// Returns the ID of the lowest sublink speed at which all of the device's functionality is
+    /// available.
+    pub fn min_sublink_speed_id(&self) -> u8 {
+        (self.functionality_support & 0x0F) as u8
+    }
+
+    /// Returns the minimum number of receive lanes that the device needs to be fully functional.
+    pub fn min_rx_lanes(&self) -> u8 {
+        ((self.functionality_support >> 8) & 0x0F) as u8
+    }
+
+    /// Returns the minimum number of transmit lanes that the device needs to be fully functional.
+    pub fn min_tx_lanes(&self) -> u8 {
+        ((self.functionality_support >> 12) & 0x0F) as u8
+    }
+
+    /// Returns the sublink speeds that the device supports.
+    pub fn sublink_speeds(&self) -> &[SublinkSpeed] {
+        &self.sublink_speeds
+    }
+
+    /// Returns the raw `bmAttributes` field.
+    pub fn attributes(&self) -> u32 {
+        self.attributes
+    }
+}
+
+/// A sublink speed attribute of the SuperSpeedPlus USB capability.
+#[derive(Debug,PartialEq,Eq,Clone,Copy,Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SublinkSpeed {
+    attributes: u32,
+}
+
+impl SublinkSpeed {
+    /// Returns the sublink speed ID. Symmetric links have a receive and a transmit attribute with
+    /// the same ID.
+    pub fn id(&self) -> u8 {
+        (self.attributes & 0x0F) as u8
+    }
+
+    /// Returns the lane speed in bits per second.
+    pub fn lane_speed(&self) -> u64 {
+        let mantissa = (self.attributes >> 16) as u64;
+
+        match (self.attributes >> 4) & 0x03 {
+            0 => mantissa,
+            1 => mantissa * 1_000,
+            2 => mantissa * 1_000_000,
+            _ => mantissa * 1_000_000_000,
+        }
+    }
+
+    /// Indicates if the sublink is symmetric.
+    pub fn symmetric(&self) -> bool {
+        self.attributes & 0x40 == 0
+    }
+
+    /// Indicates if the attribute describes the transmit direction of an asymmetric sublink.
+    pub fn transmit(&self) -> bool {
+        self.attributes & 0x80 != 0
+    }
+
+    /// Returns the link protocol, 0 for SuperSpeed and 1 for SuperSpeedPlus.
+    pub fn protocol(&self) -> u8 {
+        ((self.attributes >> 14) & 0x03) as u8
+    }
+
+    /// Returns the raw `bmSublinkSpeedAttr` field.
+    pub fn attributes(&self) -> u32 {
+        self.attributes
+    }
+}
+
+/// A universally unique identifier, as used in container ID and platform capabilities.
+///
+/// The bytes are kept in the order in which they appear in the descriptor, where the first three
+/// fields are little endian. They are formatted in the usual `xxxxxxxx-xxxx-xxxx-xxxx-xxxxxxxxxxxx`
+/// form.
+#[derive(PartialEq,Eq,Clone,Copy,Hash)]
+pub struct Uuid {
+    bytes: [u8; 16],
+}
+
+impl Uuid {
+    /// Creates a UUID from the 16 bytes that hold it in a descriptor.
+    pub fn from_bytes(bytes: [u8; 16]) -> Self {
+        Uuid { bytes: bytes }
+    }
+
+    /// Returns the UUID's bytes in descriptor order.
+    pub fn as_bytes(&self) -> &[u8; 16] {
+        &self.bytes
+    }
+}
+
+impl fmt::Display for Uuid {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        let b = &self.bytes;
+
+        write!(fmt, "{:02x}{:02x}{:02x}{:02x}-{:02x}{:02x}-{:02x}{:02x}-{:02x}{:02x}-",
+               b[3], b[2], b[1], b[0], b[5], b[4], b[7], b[6], b[8], b[9])?;
+
+        for byte in &b[10..] {
+            write!(fmt, "{:02x}", byte)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Debug for Uuid {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(fmt, "Uuid({})", self)
+    }
+}
+
+impl str::FromStr for Uuid {
+    type Err = ::Error;
+
+    /// Parses a UUID in the `xxxxxxxx-xxxx-xxxx-xxxx-xxxxxxxxxxxx` form that `Display` produces.
+    fn from_str(s: &str) -> ::Result<Self> {
+        let groups: Vec<&str> = s.split('-').collect();
+        let lengths: Vec<usize> = groups.iter().map(|g| g.len()).collect();
+
+        let digits: String = groups.concat();
+
+        if lengths != [8, 4, 4, 4, 12] || !digits.chars().all(|c| c.is_digit(16)) {
+            return Err(format!("invalid UUID: {:?}", s).into());
+        }
+
+        let mut bytes = [0u8; 16];
+
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&digits[2*i..2*i + 2], 16).unwrap();
+        }
+
+        // the first three fields are little endian in descriptor order
+        bytes[0..4].reverse();
+        bytes[4..6].reverse();
+        bytes[6..8].reverse();
+
+        Ok(Uuid { bytes: bytes })
+    }
+}
+
+#[cfg(feature = "serde")]
+impl ::serde::Serialize for Uuid {
+    fn serialize<S: ::serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> ::serde::Deserialize<'de> for Uuid {
+    fn deserialize<D: ::serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(::serde::de::Error::custom)
+    }
+}
+
+/// A platform capability, identified by a UUID.
+#[derive(Debug,PartialEq,Eq,Clone,Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct PlatformCapability {
+    uuid: Uuid,
+    data: Vec<u8>,
+}
+
+impl PlatformCapability {
+    /// Returns the UUID that identifies the platform capability.
+    pub fn uuid(&self) -> Uuid {
+        self.uuid
+    }
+
+    /// Returns the capability specific data that follows the UUID.
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+}
+
+/// The Billboard capability.
+#[derive(Debug,PartialEq,Eq,Clone,Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct BillboardCapability {
+    additional_info_url_string_index: u8,
+    preferred_mode: u8,
+    vconn_power: u16,
+    version: u16,
+    additional_failure_info: u8,
+    modes: Vec<AlternateMode>,
+}
+
+impl BillboardCapability {
+    /// Returns the index of the string descriptor that holds a URL with more information.
+    pub fn additional_info_url_string_index(&self) -> Option<u8> {
+        match self.additional_info_url_string_index {
+            0 => None,
+            n => Some(n),
+        }
+    }
+
+    /// Returns the index of the preferred alternate mode in [`modes`](#method.modes).
+    pub fn preferred_mode(&self) -> u8 {
+        self.preferred_mode
+    }
+
+    /// Returns the raw `VCONNPower` field.
+    pub fn vconn_power(&self) -> u16 {
+        self.vconn_power
+    }
+
+    /// Returns the version of the Billboard specification that the device complies with.
+    pub fn version(&self) -> Version {
+        Version::from_bcd(self.version)
+    }
+
+    /// Returns the raw `bAdditionalFailureInfo` field.
+    pub fn additional_failure_info(&self) -> u8 {
+        self.additional_failure_info
+    }
+
+    /// Returns the alternate modes that the device supports.
+    pub fn modes(&self) -> &[AlternateMode] {
+        &self.modes
+    }
+}
+
+/// An alternate mode listed in the Billboard capability.
+#[derive(Debug,PartialEq,Eq,Clone,Copy,Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct AlternateMode {
+    svid: u16,
+    mode: u8,
+    string_index: u8,
+    state: AlternateModeState,
+}
+
+impl AlternateMode {
+    /// Returns the standard or vendor ID (SVID) of the alternate mode.
+    pub fn svid(&self) -> u16 {
+        self.svid
+    }
+
+    /// Returns the index of the mode within the SVID.
+    pub fn mode(&self) -> u8 {
+        self.mode
+    }
+
+    /// Returns the index of the string descriptor that describes the alternate mode.
+    pub fn description_string_index(&self) -> Option<u8> {
+        match self.string_index {
+            0 => None,
+            n => Some(n),
+        }
+    }
+
+    /// Returns the outcome of entering the alternate mode.
+    pub fn state(&self) -> AlternateModeState {
+        self.state
+    }
+}
+
+/// The outcome of entering an alternate mode, as reported in the Billboard capability.
+#[derive(Debug,PartialEq,Eq,Clone,Copy,Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum AlternateModeState {
+    /// An unspecified error occurred.
+    Error,
+
+    /// The device didn't attempt to enter the alternate mode.
+    NotAttempted,
+
+    /// The device attempted to enter the alternate mode, but failed.
+    Unsuccessful,
+
+    /// The device entered the alternate mode.
+    Successful,
+}
+
+/// A device capability that this crate doesn't parse.
+#[derive(Debug,PartialEq,Eq,Clone,Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct UnknownCapability {
+    bytes: Vec<u8>,
+}
+
+impl UnknownCapability {
+    /// Returns the capability's type code (`bDevCapabilityType`).
+    pub fn capability_type(&self) -> u8 {
+        self.bytes[2]
+    }
+
+    /// Returns all of the capability descriptor's bytes, including its header.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+
+fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+    read_u16(bytes, offset) as u32 | (read_u16(bytes, offset + 2) as u32) << 16
+}
+
+fn read_uuid(bytes: &[u8], offset: usize) -> Uuid {
+    let mut uuid = [0u8; 16];
+    uuid.copy_from_slice(&bytes[offset..offset + 16]);
+    Uuid::from_bytes(uuid)
+}
+
+fn check_capability_length(bytes: &[u8], min_length: usize, name: &str) -> ::Result<()> {
+    if (bytes[0] as usize) < min_length {
+        return Err(format!("{} capability has bLength {}, expected at least {}", name, bytes[0], min_length).into());
+    }
+
+    Ok(())
+}
+
+/// Parses the device capability descriptor at the start of `bytes`.
+fn parse_capability(bytes: &[u8]) -> ::Result<DeviceCapability> {
+    let length = check_header(bytes, LIBUSB_DT_DEVICE_CAPABILITY, 3, "device capability")?;
+    let bytes = &bytes[..length];
+
+    match bytes[2] {
+        CAP_USB_2_0_EXTENSION => {
+            check_capability_length(bytes, 7, "USB 2.0 extension")?;
+
+            Ok(DeviceCapability::Usb2Extension(Usb2ExtensionCapability {
+                attributes: read_u32(bytes, 3),
+            }))
+        },
+        CAP_SUPERSPEED_USB => {
+            check_capability_length(bytes, 10, "SuperSpeed USB")?;
+
+            Ok(DeviceCapability::SuperSpeed(SuperSpeedCapability {
+                attributes: bytes[3],
+                speeds_supported: read_u16(bytes, 4),
+                functionality_support: bytes[6],
+                u1_exit_latency: bytes[7],
+                u2_exit_latency: read_u16(bytes, 8),
+            }))
+        },
+        CAP_SUPERSPEED_PLUS => {
+            check_capability_length(bytes, 12, "SuperSpeedPlus USB")?;
+
+            let attributes = read_u32(bytes, 4);
+            let num_sublink_speeds = (attributes & 0x1F) as usize + 1;
+            check_capability_length(bytes, 12 + 4 * num_sublink_speeds, "SuperSpeedPlus USB")?;
+
+            Ok(DeviceCapability::SuperSpeedPlus(SuperSpeedPlusCapability {
+                attributes: attributes,
+                functionality_support: read_u16(bytes, 8),
+                sublink_speeds: (0..num_sublink_speeds).map(|i| {
+                    SublinkSpeed { attributes: read_u32(bytes, 12 + 4 * i) }
+                }).collect(),
+            }))
+        },
+        CAP_CONTAINER_ID => {
+            check_capability_length(bytes, 20, "container ID")?;
+
+            Ok(DeviceCapability::ContainerId(read_uuid(bytes, 4)))
+        },
+        CAP_PLATFORM => {
+            check_capability_length(bytes, 20, "platform")?;
+
+            Ok(DeviceCapability::Platform(PlatformCapability {
+                uuid: read_uuid(bytes, 4),
+                data: bytes[20..].to_vec(),
+            }))
+        },
+        CAP_BILLBOARD => {
+            check_capability_length(bytes, 44, "Billboard")?;
+
+            let num_modes = bytes[4] as usize;
+            check_capability_length(bytes, 44 + 4 * num_modes, "Billboard")?;
+
+            let configured = &bytes[8..40];
+
+            Ok(DeviceCapability::Billboard(BillboardCapability {
+                additional_info_url_string_index: bytes[3],
+                preferred_mode: bytes[5],
+                vconn_power: read_u16(bytes, 6),
+                version: read_u16(bytes, 40),
+                additional_failure_info: bytes[42],
+                modes: (0..num_modes).map(|i| {
+                    let mode = &bytes[44 + 4 * i..];
+
+                    AlternateMode {
+                        svid: read_u16(mode, 0),
+                        mode: mode[2],
+                        string_index: mode[3],
+                        state: match (configured[i / 4] >> (2 * (i % 4))) & 0x03 {
+                            0 => AlternateModeState::Error,
+                            1 => AlternateModeState::NotAttempted,
+                            2 => AlternateModeState::Unsuccessful,
+                            _ => AlternateModeState::Successful,
+                        },
+                    }
+                }).collect(),
+            }))
+        },
+        _ => Ok(DeviceCapability::Unknown(UnknownCapability { bytes: bytes.to_vec() })),
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use fields::{Speed, Version};
+    use super::{BosDescriptor, DeviceCapability, AlternateModeState, Uuid};
+
+    // A USB 3 hub: USB 2.0 extension, SuperSpeed USB and container ID capabilities.
+    const HUB: &'static [u8] = &[
+        0x05, 0x0F, 0x2A, 0x00, 0x03,
+        0x07, 0x10, 0x02, 0x06, 0x00, 0x00, 0x00,
+        0x0A, 0x10, 0x03, 0x00, 0x0E, 0x00, 0x01, 0x0A, 0xFF, 0x07,
+        0x14, 0x10, 0x04, 0x00, 0x33, 0x22, 0x11, 0x00, 0x55, 0x44, 0x77, 0x66,
+        0x88, 0x99, 0xAA, 0xBB, 0xCC, 0xDD, 0xEE, 0xFF,
+    ];
+
+    // A WebUSB platform capability followed by a capability this crate doesn't know.
+    const WEBUSB: &'static [u8] = &[
+        0x05, 0x0F, 0x21, 0x00, 0x02,
+        0x18, 0x10, 0x05, 0x00, 0x38, 0xB6, 0x08, 0x34, 0xA9, 0x09, 0xA0, 0x47,
+        0x8B, 0xFD, 0xA0, 0x76, 0x88, 0x15, 0xB6, 0x65, 0x00, 0x01, 0x01, 0x01,
+        0x04, 0x10, 0x42, 0xAB,
+    ];
+
+    fn error(result: ::Result<BosDescriptor>) -> String {
+        match result {
+            Err(::Error::Custom(message)) => message,
+            Err(e) => panic!("expected custom error, got {:?}", e),
+            Ok(bos) => panic!("expected error, got {:?}", bos),
+        }
+    }
+
+    fn with_total_length(bytes: &[u8], total_length: usize) -> Vec<u8> {
+        let mut bytes = bytes.to_vec();
+        bytes[2] = total_length as u8;
+        bytes[3] = (total_length >> 8) as u8;
+        bytes
+    }
+
+    fn capability(bytes: &[u8]) -> DeviceCapability {
+        let mut bos = vec![0x05, 0x0F, 0x00, 0x00, 0x01];
+        bos.extend_from_slice(bytes);
+        let total_length = bos.len();
+
+        BosDescriptor::from_bytes(&with_total_length(&bos, total_length)).unwrap().capabilities()[0].clone()
+    }
+
+    #[test]
+    fn it_parses_capabilities_in_order() {
+        let bos = BosDescriptor::from_bytes(HUB).unwrap();
+        let types: Vec<u8> = bos.capabilities().iter().map(|c| c.capability_type()).collect();
+
+        assert_eq!(3, bos.num_capabilities());
+        assert_eq!(vec![0x02, 0x03, 0x04], types);
+    }
+
+    #[test]
+    fn it_parses_usb_2_0_extension() {
+        let extension = *BosDescriptor::from_bytes(HUB).unwrap().usb_2_0_extension().unwrap();
+
+        assert!(extension.lpm_supported());
+        assert!(extension.besl_supported());
+        assert_eq!(None, extension.baseline_besl());
+        assert_eq!(None, extension.deep_besl());
+    }
+
+    #[test]
+    fn it_parses_recommended_besl_values() {
+        match capability(&[0x07, 0x10, 0x02, 0x1E, 0x42, 0x00, 0x00]) {
+            DeviceCapability::Usb2Extension(extension) => {
+                assert_eq!(Some(2), extension.baseline_besl());
+                assert_eq!(Some(4), extension.deep_besl());
+            },
+            c => panic!("expected USB 2.0 extension, got {:?}", c),
+        }
+    }
+
+    #[test]
+    fn it_parses_superspeed_capability() {
+        let superspeed = *BosDescriptor::from_bytes(HUB).unwrap().superspeed().unwrap();
+
+        assert!(!superspeed.ltm_capable());
+        assert!(!superspeed.supports_speed(Speed::Low));
+        assert!(superspeed.supports_speed(Speed::Full));
+        assert!(superspeed.supports_speed(Speed::High));
+        assert!(superspeed.supports_speed(Speed::Super));
+        assert!(!superspeed.supports_speed(Speed::Unknown));
+        assert_eq!(Speed::Full, superspeed.functionality_support());
+        assert_eq!(10, superspeed.u1_exit_latency());
+        assert_eq!(2047, superspeed.u2_exit_latency());
+    }
+
+    #[test]
+    fn it_parses_container_id() {
+        let uuid = BosDescriptor::from_bytes(HUB).unwrap().container_id().unwrap();
+
+        assert_eq!("00112233-4455-6677-8899-aabbccddeeff", uuid.to_string());
+        assert_eq!(0x33, uuid.as_bytes()[0]);
+    }
+
+    #[test]
+    fn it_parses_platform_capability() {
+        let bos = BosDescriptor::from_bytes(WEBUSB).unwrap();
+        let uuid = Uuid::from_bytes([0x38, 0xB6, 0x08, 0x34, 0xA9, 0x09, 0xA0, 0x47, 0x8B, 0xFD, 0xA0, 0x76, 0x88, 0x15, 0xB6, 0x65]);
+        let platform = bos.platform_capability(uuid).unwrap();
+
+        assert_eq!("3408b638-09a9-47a0-8bfd-a0768815b665", platform.uuid().to_string());
+        assert_eq!(&[0x00, 0x01, 0x01, 0x01], platform.data());
+        assert!(bos.platform_capability(Uuid::from_bytes([0; 16])).is_none());
+    }
+
+    #[test]
+    fn it_parses_uuid_from_string() {
+        let uuid: Uuid = "3408b638-09a9-47a0-8bfd-a0768815b665".parse().unwrap();
+
+        assert_eq!(&[0x38, 0xB6, 0x08, 0x34, 0xA9, 0x09, 0xA0, 0x47, 0x8B, 0xFD, 0xA0, 0x76, 0x88, 0x15, 0xB6, 0x65], uuid.as_bytes());
+        assert_eq!(uuid, "3408B638-09A9-47A0-8BFD-A0768815B665".parse().unwrap());
+    }
+
+    #[test]
+    fn it_rejects_malformed_uuid_strings() {
+        assert!("3408b638-09a9-47a0-8bfd".parse::<Uuid>().is_err());
+        assert!("3408b63809a947a08bfda0768815b665".parse::<Uuid>().is_err());
+        assert!("3408b638-09a9-47a0-8bfd-a0768815b66g".parse::<Uuid>().is_err());
+        assert!("3408b638-09a9-47a0-8bfd-a0768815b6+5".parse::<Uuid>().is_err());
+    }
+
+    #[test]
+    fn it_preserves_unknown_capabilities() {
+        let bos = BosDescriptor::from_bytes(WEBUSB).unwrap();
+
+        match bos.capabilities()[1] {
+            DeviceCapability::Unknown(ref c) => {
+                assert_eq!(0x42, c.capability_type());
+                assert_eq!(&WEBUSB[29..], c.as_bytes());
+            },
+            ref c => panic!("expected unknown capability, got {:?}", c),
+        }
+    }
+
+    #[test]
+    fn it_parses_superspeed_plus_capability() {
+        let bytes = [
+            0x1C, 0x10, 0x0A, 0x00, 0x23, 0x00, 0x00, 0x00, 0x00, 0x11, 0x00, 0x00,
+            0x30, 0x40, 0x0A, 0x00, 0xB0, 0x40, 0x0A, 0x00,
+            0x31, 0x40, 0x05, 0x00, 0xB1, 0x40, 0x05, 0x00,
+        ];
+
+        match capability(&bytes) {
+            DeviceCapability::SuperSpeedPlus(ssp) => {
+                let speeds: Vec<(u8, u64, bool)> = ssp.sublink_speeds().iter().map(|s| (s.id(), s.lane_speed(), s.transmit())).collect();
+
+                assert_eq!(2, ssp.num_sublink_speed_ids());
+                assert_eq!(0, ssp.min_sublink_speed_id());
+                assert_eq!(1, ssp.min_rx_lanes());
+                assert_eq!(1, ssp.min_tx_lanes());
+                assert!(ssp.sublink_speeds()[0].symmetric());
+                assert_eq!(1, ssp.sublink_speeds()[0].protocol());
+                assert_eq!(vec![(0, 10_000_000_000, false), (0, 10_000_000_000, true),
+                                (1, 5_000_000_000, false), (1, 5_000_000_000, true)], speeds);
+            },
+            c => panic!("expected SuperSpeedPlus capability, got {:?}", c),
+        }
+    }
+
+    #[test]
+    fn it_parses_billboard_capability() {
+        let mut bytes = vec![0x34, 0x10, 0x0D, 0x01, 0x02, 0x00, 0x00, 0x80];
+        bytes.extend_from_slice(&[0x0E]);
+        bytes.extend_from_slice(&[0x00; 31]);
+        bytes.extend_from_slice(&[0x10, 0x01, 0x00, 0x00]);
+        bytes.extend_from_slice(&[0x01, 0xFF, 0x01, 0x02, 0x8B, 0x21, 0x01, 0x00]);
+
+        match capability(&bytes) {
+            DeviceCapability::Billboard(billboard) => {
+                let modes: Vec<_> = billboard.modes().iter().map(|m| (m.svid(), m.mode(), m.description_string_index(), m.state())).collect();
+
+                assert_eq!(Some(1), billboard.additional_info_url_string_index());
+                assert_eq!(0, billboard.preferred_mode());
+                assert_eq!(0x8000, billboard.vconn_power());
+                assert_eq!(Version(1, 1, 0), billboard.version());
+                assert_eq!(vec![(0xFF01, 1, Some(2), AlternateModeState::Unsuccessful),
+                                (0x218B, 1, None, AlternateModeState::Successful)], modes);
+            },
+            c => panic!("expected Billboard capability, got {:?}", c),
+        }
+    }
+
+    #[test]
+    fn it_parses_bos_without_capabilities() {
+        let bos = BosDescriptor::from_bytes(&[0x05, 0x0F, 0x05, 0x00, 0x00]).unwrap();
+
+        assert!(bos.capabilities().is_empty());
+        assert!(bos.usb_2_0_extension().is_none());
+        assert!(bos.container_id().is_none());
+    }
+
+    #[test]
+    fn it_ignores_bytes_after_total_length() {
+        let mut bytes = HUB.to_vec();
+        bytes.extend_from_slice(&[0x00, 0xFF]);
+
+        assert_eq!(3, BosDescriptor::from_bytes(&bytes).unwrap().num_capabilities());
+    }
+
+    #[test]
+    fn it_counts_parsed_capabilities_instead_of_header_field() {
+        let mut bytes = HUB.to_vec();
+        bytes[4] = 7;
+
+        assert_eq!(3, BosDescriptor::from_bytes(&bytes).unwrap().num_capabilities());
+    }
+
+    #[test]
+    fn it_rejects_bos_of_wrong_type() {
+        let mut bytes = HUB.to_vec();
+        bytes[1] = 0x02;
+
+        assert!(error(BosDescriptor::from_bytes(&bytes)).contains("got type 0x02"));
+    }
+
+    #[test]
+    fn it_rejects_truncated_bos() {
+        assert!(error(BosDescriptor::from_bytes(&HUB[..HUB.len() - 1])).contains("wTotalLength 42"));
+    }
+
+    #[test]
+    fn it_rejects_total_length_shorter_than_header() {
+        assert!(error(BosDescriptor::from_bytes(&with_total_length(HUB, 4))).contains("wTotalLength 4"));
+    }
+
+    #[test]
+    fn it_rejects_capability_of_wrong_type() {
+        let mut bytes = HUB.to_vec();
+        bytes[6] = 0x04;
+
+        assert!(error(BosDescriptor::from_bytes(&bytes)).contains("device capability at offset 5"));
+    }
+
+    #[test]
+    fn it_rejects_short_known_capability() {
+        let mut bytes = vec![0x05, 0x0F, 0x0B, 0x00, 0x01];
+        bytes.extend_from_slice(&[0x06, 0x10, 0x02, 0x06, 0x00, 0x00]);
+
+        assert!(error(BosDescriptor::from_bytes(&bytes)).contains("USB 2.0 extension capability has bLength 6"));
+    }
+
+    #[test]
+    fn it_rejects_superspeed_plus_capability_with_missing_attributes() {
+        let mut bytes = vec![0x05, 0x0F, 0x1D, 0x00, 0x01];
+        bytes.extend_from_slice(&[
+            0x18, 0x10, 0x0A, 0x00, 0x23, 0x00, 0x00, 0x00, 0x00, 0x11, 0x00, 0x00,
+            0x30, 0x40, 0x0A, 0x00, 0xB0, 0x40, 0x0A, 0x00, 0x31, 0x40, 0x05, 0x00,
+        ]);
+
+        assert!(error(BosDescriptor::from_bytes(&bytes)).contains("expected at least 28"));
+    }
+
+    #[test]
+    fn it_handles_every_truncation_and_corruption() {
+        for fixture in &[HUB, WEBUSB] {
+            for len in 0..fixture.len() {
+                assert!(BosDescriptor::from_bytes(&fixture[..len]).is_err());
+
+                if len >= 5 {
+                    let _ = BosDescriptor::from_bytes(&with_total_length(&fixture[..len], len));
+                }
+            }
+
+            for offset in 0..fixture.len() {
+                for &value in &[0x00, 0x01, 0x02, 0x03, 0x0A, 0x0D, 0x10, 0xFF] {
+                    let mut bytes = fixture.to_vec();
+                    bytes[offset] = value;
+
+                    let _ = BosDescriptor::from_bytes(&bytes);
+                }
+            }
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn it_round_trips_bos_through_serde() {
+        use serde_json;
+
+        for bytes in &[HUB, WEBUSB] {
+            let bos = BosDescriptor::from_bytes(bytes).unwrap();
+            let json = serde_json::to_string(&bos).unwrap();
+
+            assert_eq!(bos, serde_json::from_str(&json).unwrap());
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn it_serializes_uuid_as_string() {
+        use serde_json;
+
+        let uuid = Uuid::from_bytes([0x38, 0xB6, 0x08, 0x34, 0xA9, 0x09, 0xA0, 0x47, 0x8B, 0xFD, 0xA0, 0x76, 0x88, 0x15, 0xB6, 0x65]);
+
+        assert_eq!("\"3408b638-09a9-47a0-8bfd-a0768815b665\"", serde_json::to_string(&uuid).unwrap());
+        assert_eq!(uuid, serde_json::from_str("\"3408b638-09a9-47a0-8bfd-a0768815b665\"").unwrap());
+        assert!(serde_json::from_str::<Uuid>("\"not a uuid\"").is_err());
+    }
+}
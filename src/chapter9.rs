@@ -0,0 +1,1060 @@
+use std::fmt;
+use std::time::Duration;
+
+use libusb::*;
+
+use io::IoType;
+use device_handle::DeviceHandle;
+use device_handle_sync_api::DeviceHandleSyncApi;
+use device_descriptor::DeviceDescriptor;
+use config_descriptor::ConfigDescriptor;
+use descriptor_parser::{self, read_u16};
+use descriptor_lint::FindingLocation;
+use fields::{Direction, Recipient, TransferType};
+use setup_packet::SetupPacket;
+use standard_request::Feature;
+use error::Error;
+
+
+/// A device that can be exercised by a [`Chapter9Test`](struct.Chapter9Test.html).
+///
+/// The provided methods send the standard requests as control transfers, so a simulated device
+/// only has to implement [`DeviceHandleSyncApi`](trait.DeviceHandleSyncApi.html). `DeviceHandle`
+/// overrides them with the corresponding `libusb` functions, which keep the operating system's
+/// view of the device up to date.
+pub trait Chapter9Device: DeviceHandleSyncApi {
+    /// Selects a configuration with a `SET_CONFIGURATION` request. Zero returns the device to the
+    /// address state.
+    fn set_configuration(&mut self, config: u8, timeout: Duration) -> ::Result<()> {
+        let setup = SetupPacket::builder()
+            .direction(Direction::Out)
+            .recipient(Recipient::Device)
+            .request(LIBUSB_REQUEST_SET_CONFIGURATION)
+            .value(config as u16)
+            .build();
+
+        self.control_out(setup, &[], timeout)?;
+        Ok(())
+    }
+
+    /// Selects an interface's alternate setting with a `SET_INTERFACE` request.
+    fn set_interface(&mut self, iface: u8, setting: u8, timeout: Duration) -> ::Result<()> {
+        let setup = SetupPacket::builder()
+            .direction(Direction::Out)
+            .recipient(Recipient::Interface)
+            .request(LIBUSB_REQUEST_SET_INTERFACE)
+            .value(setting as u16)
+            .index(iface as u16)
+            .build();
+
+        self.control_out(setup, &[], timeout)?;
+        Ok(())
+    }
+
+    /// Clears an endpoint's halt feature with a `CLEAR_FEATURE` request.
+    fn clear_endpoint_halt(&mut self, endpoint: u8, timeout: Duration) -> ::Result<()> {
+        self.clear_feature(Feature::EndpointHalt(endpoint), timeout)
+    }
+
+    /// Called when the test is done with an interface, before another configuration is selected.
+    fn finish_interface(&mut self, _iface: u8) -> ::Result<()> {
+        Ok(())
+    }
+}
+
+/// Interfaces are claimed before their alternate settings are selected and released when the test
+/// is done with them, so kernel drivers must be detached from the device's interfaces first.
+impl<'ctx, Io> Chapter9Device for DeviceHandle<'ctx, Io>
+    where Io: IoType<'ctx>,
+          DeviceHandle<'ctx, Io>: DeviceHandleSyncApi,
+{
+    fn set_configuration(&mut self, config: u8, _: Duration) -> ::Result<()> {
+        match config {
+            0 => self.unconfigure(),
+            n => self.set_active_configuration(n),
+        }
+    }
+
+    fn set_interface(&mut self, iface: u8, setting: u8, _: Duration) -> ::Result<()> {
+        self.claim_interface(iface)?;
+        self.set_alternate_setting(iface, setting)
+    }
+
+    fn clear_endpoint_halt(&mut self, endpoint: u8, _: Duration) -> ::Result<()> {
+        self.clear_halt(endpoint)
+    }
+
+    fn finish_interface(&mut self, iface: u8) -> ::Result<()> {
+        self.release_interface(iface)
+    }
+}
+
+/// A check made by a [`Chapter9Test`](struct.Chapter9Test.html).
+#[derive(Debug,PartialEq,Eq,Clone,Copy,Hash)]
+pub enum CheckKind {
+    /// `GET_DESCRIPTOR` returns a well-formed device descriptor, the same one for every request
+    /// length.
+    DeviceDescriptor,
+
+    /// `GET_DESCRIPTOR` returns a well-formed configuration descriptor set, the same one for every
+    /// request length.
+    ConfigDescriptor,
+
+    /// `GET_DESCRIPTOR` for a configuration that the device doesn't have is stalled.
+    InvalidConfigIndex,
+
+    /// `GET_STATUS` for the device succeeds and agrees with the configuration's attributes.
+    DeviceStatus,
+
+    /// `SET_CONFIGURATION` succeeds and `GET_CONFIGURATION` returns the configuration.
+    SetConfiguration,
+
+    /// `SET_CONFIGURATION` with zero returns the device to the address state.
+    Unconfigure,
+
+    /// `SET_INTERFACE` succeeds and `GET_INTERFACE` returns the alternate setting.
+    SetInterface,
+
+    /// `GET_STATUS` for the interface succeeds.
+    InterfaceStatus,
+
+    /// The endpoint is halted by `SET_FEATURE(ENDPOINT_HALT)`, recovered by
+    /// `CLEAR_FEATURE(ENDPOINT_HALT)`, and `GET_STATUS` reports its state.
+    EndpointHalt,
+
+    /// String descriptor zero is well-formed and lists at least one language.
+    Languages,
+
+    /// The string descriptor with the given index is well-formed in every language.
+    StringDescriptor(u8),
+}
+
+/// The outcome of a check.
+#[derive(Debug,PartialEq,Eq,Clone)]
+pub enum CheckOutcome {
+    /// The device behaved as required.
+    Passed,
+
+    /// The device didn't behave as required, for the given reason.
+    Failed(String),
+
+    /// The check doesn't apply to the device, for the given reason.
+    Skipped(String),
+}
+
+/// The result of a single check.
+#[derive(Debug,PartialEq,Eq,Clone)]
+pub struct Chapter9Check {
+    kind: CheckKind,
+    location: FindingLocation,
+    outcome: CheckOutcome,
+}
+
+impl Chapter9Check {
+    /// Returns what was checked.
+    pub fn kind(&self) -> CheckKind {
+        self.kind
+    }
+
+    /// Returns the part of the device that was checked.
+    pub fn location(&self) -> FindingLocation {
+        self.location
+    }
+
+    /// Returns the outcome of the check.
+    pub fn outcome(&self) -> &CheckOutcome {
+        &self.outcome
+    }
+}
+
+/// The results of a [`Chapter9Test`](struct.Chapter9Test.html), in the order in which the
+/// checks were made.
+#[derive(Debug,PartialEq,Eq,Clone)]
+pub struct Chapter9Report {
+    checks: Vec<Chapter9Check>,
+}
+
+impl Chapter9Report {
+    /// Returns the results of all checks.
+    pub fn checks(&self) -> &[Chapter9Check] {
+        &self.checks
+    }
+
+    /// Returns the results of the checks that failed.
+    pub fn failures(&self) -> Vec<&Chapter9Check> {
+        self.checks.iter().filter(|check| match check.outcome { CheckOutcome::Failed(_) => true, _ => false }).collect()
+    }
+
+    /// Indicates if no check failed.
+    pub fn passed(&self) -> bool {
+        self.failures().is_empty()
+    }
+}
+
+impl fmt::Display for CheckKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            CheckKind::DeviceDescriptor        => f.write_str("device descriptor"),
+            CheckKind::ConfigDescriptor        => f.write_str("configuration descriptor"),
+            CheckKind::InvalidConfigIndex      => f.write_str("invalid configuration index"),
+            CheckKind::DeviceStatus            => f.write_str("device status"),
+            CheckKind::SetConfiguration        => f.write_str("set configuration"),
+            CheckKind::Unconfigure             => f.write_str("unconfigure"),
+            CheckKind::SetInterface            => f.write_str("set interface"),
+            CheckKind::InterfaceStatus         => f.write_str("interface status"),
+            CheckKind::EndpointHalt            => f.write_str("endpoint halt"),
+            CheckKind::Languages               => f.write_str("languages"),
+            CheckKind::StringDescriptor(index) => write!(f, "string descriptor {}", index),
+        }
+    }
+}
+
+impl fmt::Display for Chapter9Check {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.outcome {
+            CheckOutcome::Passed => write!(f, "PASS {} ({})", self.kind, self.location),
+            CheckOutcome::Failed(ref reason) => write!(f, "FAIL {} ({}): {}", self.kind, self.location, reason),
+            CheckOutcome::Skipped(ref reason) => write!(f, "SKIP {} ({}): {}", self.kind, self.location, reason),
+        }
+    }
+}
+
+impl fmt::Display for Chapter9Report {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for check in &self.checks {
+            writeln!(f, "{}", check)?;
+        }
+
+        Ok(())
+    }
+}
+
+
+/// Checks that a device handles the standard requests of chapter 9 of the USB specification.
+///
+/// The test reads the device and configuration descriptors with several request lengths and
+/// compares them with each other and with the expected descriptors, selects every configuration
+/// and every alternate setting of its interfaces, halts and recovers every endpoint that has a
+/// halt feature, reads the status of the device and of its interfaces and reads every string
+/// descriptor that the descriptors refer to in every language. Finally the device is
+/// unconfigured and its original configuration is restored. The device is left unconfigured if
+/// its original configuration couldn't be read.
+///
+/// The test changes the device's state. It should only be run against devices that aren't in
+/// use.
+///
+/// ## Examples
+///
+/// ```no_run
+/// use std::time::Duration;
+/// use libusb::io::sync::Context;
+///
+/// let context = Context::new().unwrap();
+/// let mut handle = context.open_device_with_vid_pid(0x1209, 0x0001).unwrap();
+///
+/// let report = libusb::Chapter9Test::new(Duration::from_secs(1)).run(&mut handle);
+///
+/// print!("{}", report);
+/// assert!(report.passed());
+/// ```
+#[derive(Debug,Clone)]
+pub struct Chapter9Test {
+    timeout: Duration,
+    expected: Option<Vec<u8>>,
+}
+
+impl Chapter9Test {
+    /// Creates a test that uses `timeout` for every request.
+    pub fn new(timeout: Duration) -> Self {
+        Chapter9Test {
+            timeout: timeout,
+            expected: None,
+        }
+    }
+
+    /// Sets the descriptors that the device should return.
+    ///
+    /// `bytes` holds a device descriptor followed by the device's configurations, in the format
+    /// accepted by [`parse_descriptors`](fn.parse_descriptors.html). Without expected descriptors,
+    /// the descriptors are only compared with each other.
+    pub fn expected_descriptors(mut self, bytes: &[u8]) -> Self {
+        self.expected = Some(bytes.to_vec());
+        self
+    }
+
+    /// Runs the test against a device.
+    ///
+    /// Checks are reported in the order in which they're made. If the device descriptor can't be
+    /// read, no other checks are made. Configurations whose descriptors can't be read aren't
+    /// selected.
+    pub fn run<D: Chapter9Device>(&self, device: &mut D) -> Chapter9Report {
+        let mut run = Run {
+            device: device,
+            timeout: self.timeout,
+            checks: Vec::new(),
+        };
+
+        let expected = match self.expected {
+            Some(ref bytes) => match split_descriptors(bytes) {
+                Ok(expected) => Some(expected),
+                Err(e) => {
+                    run.record(CheckKind::DeviceDescriptor, FindingLocation::Device, Err(format!("the expected descriptors are malformed: {}", e)));
+                    return Chapter9Report { checks: run.checks };
+                },
+            },
+            None => None,
+        };
+
+        let initial_config = run.device.read_configuration(run.timeout).ok();
+
+        let device = match run.device_descriptor(expected.as_ref().map(|&(ref device, _)| &device[..])) {
+            Some(device) => device,
+            None => return Chapter9Report { checks: run.checks },
+        };
+
+        let mut configs = Vec::new();
+
+        for index in 0..device.num_configurations() {
+            let expected = expected.as_ref().and_then(|&(_, ref configs)| configs.get(index as usize)).map(|config| &config[..]);
+
+            if let Some(config) = run.config_descriptor(index, expected) {
+                configs.push(config);
+            }
+        }
+
+        run.invalid_config_index(device.num_configurations());
+
+        for config in &configs {
+            run.configuration(config);
+        }
+
+        run.unconfigure(&configs, initial_config);
+        run.strings(&device, &configs);
+
+        Chapter9Report { checks: run.checks }
+    }
+}
+
+
+struct Run<'a, D: Chapter9Device + 'a> {
+    device: &'a mut D,
+    timeout: Duration,
+    checks: Vec<Chapter9Check>,
+}
+
+impl<'a, D: Chapter9Device> Run<'a, D> {
+    fn record(&mut self, kind: CheckKind, location: FindingLocation, result: Result<(), String>) {
+        let outcome = match result {
+            Ok(()) => CheckOutcome::Passed,
+            Err(reason) => CheckOutcome::Failed(reason),
+        };
+
+        self.checks.push(Chapter9Check { kind: kind, location: location, outcome: outcome });
+    }
+
+    fn skip(&mut self, kind: CheckKind, location: FindingLocation, reason: &str) {
+        self.checks.push(Chapter9Check { kind: kind, location: location, outcome: CheckOutcome::Skipped(reason.to_owned()) });
+    }
+
+    fn read(&self, descriptor_type: u8, index: u8, language_id: u16, length: usize) -> Result<Vec<u8>, String> {
+        let mut buf = vec![0u8; length];
+
+        match self.device.read_descriptor(descriptor_type, index, language_id, &mut buf, self.timeout) {
+            Ok(len) => {
+                buf.truncate(len);
+                Ok(buf)
+            },
+            Err(e) => Err(format!("GET_DESCRIPTOR({:#04x}, {}) with wLength {} failed: {}", descriptor_type, index, length, e)),
+        }
+    }
+
+    /// Reads a descriptor again with a shorter and a longer request and compares the responses with
+    /// `bytes`.
+    fn check_lengths(&self, descriptor_type: u8, index: u8, language_id: u16, bytes: &[u8], short_length: usize) -> Result<(), String> {
+        let short = self.read(descriptor_type, index, language_id, short_length)?;
+        compare(&short, &bytes[..short_length], &format!("the response to a request with wLength {}", short_length))?;
+
+        let long = self.read(descriptor_type, index, language_id, bytes.len() + 16)?;
+        compare(&long, bytes, &format!("the response to a request with wLength {}", bytes.len() + 16))
+    }
+
+    fn device_descriptor(&mut self, expected: Option<&[u8]>) -> Option<DeviceDescriptor> {
+        let parsed = self.read(LIBUSB_DT_DEVICE, 0, 0, 18).and_then(|bytes| {
+            match descriptor_parser::parse_device(&bytes) {
+                Ok(device) => Ok((bytes, device)),
+                Err(e) => Err(e.to_string()),
+            }
+        });
+
+        let (bytes, device) = match parsed {
+            Ok(parsed) => parsed,
+            Err(reason) => {
+                self.record(CheckKind::DeviceDescriptor, FindingLocation::Device, Err(reason));
+                return None;
+            },
+        };
+
+        let result = self.check_lengths(LIBUSB_DT_DEVICE, 0, 0, &bytes, 8).and_then(|()| {
+            expected.map_or(Ok(()), |expected| compare(&bytes, expected, "the device descriptor"))
+        });
+
+        self.record(CheckKind::DeviceDescriptor, FindingLocation::Device, result);
+        Some(device)
+    }
+
+    fn config_descriptor(&mut self, index: u8, expected: Option<&[u8]>) -> Option<ConfigDescriptor> {
+        let header = match self.read(LIBUSB_DT_CONFIG, index, 0, 9) {
+            Ok(ref header) if header.len() < 9 => {
+                self.record(CheckKind::ConfigDescriptor, FindingLocation::Device,
+                            Err(format!("configuration descriptor {} is {} bytes long, expected at least 9", index, header.len())));
+                return None;
+            },
+            Ok(header) => header,
+            Err(reason) => {
+                self.record(CheckKind::ConfigDescriptor, FindingLocation::Device, Err(reason));
+                return None;
+            },
+        };
+
+        let location = FindingLocation::Configuration { config: header[5] };
+        let total_length = read_u16(&header, 2) as usize;
+
+        let parsed = self.read(LIBUSB_DT_CONFIG, index, 0, total_length).and_then(|bytes| {
+            if bytes.len() != total_length {
+                return Err(format!("GET_DESCRIPTOR returned {} bytes, but wTotalLength is {}", bytes.len(), total_length));
+            }
+
+            match descriptor_parser::parse_config(&bytes, LIBUSB_DT_CONFIG) {
+                Ok(config) => Ok((bytes, config)),
+                Err(e) => Err(e.to_string()),
+            }
+        });
+
+        let (bytes, config) = match parsed {
+            Ok(parsed) => parsed,
+            Err(reason) => {
+                self.record(CheckKind::ConfigDescriptor, location, Err(reason));
+                return None;
+            },
+        };
+
+        let result = self.check_lengths(LIBUSB_DT_CONFIG, index, 0, &bytes, 9).and_then(|()| {
+            expected.map_or(Ok(()), |expected| compare(&bytes, expected, "the configuration descriptor"))
+        });
+
+        self.record(CheckKind::ConfigDescriptor, location, result);
+        Some(config)
+    }
+
+    fn invalid_config_index(&mut self, num_configurations: u8) {
+        let mut buf = [0u8; 9];
+
+        let result = match self.device.read_descriptor(LIBUSB_DT_CONFIG, num_configurations, 0, &mut buf, self.timeout) {
+            Err(Error::Pipe) => Ok(()),
+            Ok(_) => Err(format!("GET_DESCRIPTOR for configuration index {} succeeded, but bNumConfigurations is {}", num_configurations, num_configurations)),
+            Err(e) => Err(format!("GET_DESCRIPTOR for configuration index {} failed with {}, expected a stall", num_configurations, e)),
+        };
+
+        self.record(CheckKind::InvalidConfigIndex, FindingLocation::Device, result);
+    }
+
+    fn configuration(&mut self, config: &ConfigDescriptor) {
+        let number = config.number();
+        let location = FindingLocation::Configuration { config: number };
+
+        let result = self.select_configuration(number);
+        let selected = result.is_ok();
+
+        self.record(CheckKind::SetConfiguration, location, result);
+
+        if !selected {
+            return;
+        }
+
+        let result = match self.device.read_device_status(self.timeout) {
+            Ok(ref status) if status.self_powered() && !config.self_powered() => {
+                Err(format!("the device reports that it's self-powered, but configuration {} is bus-powered", number))
+            },
+            Ok(_) => Ok(()),
+            Err(e) => Err(format!("GET_STATUS failed: {}", e)),
+        };
+
+        self.record(CheckKind::DeviceStatus, location, result);
+
+        for interface in config.interfaces() {
+            let iface = interface.number();
+            let location = FindingLocation::Interface { config: number, interface: iface };
+
+            let result = self.device.read_interface_status(iface, self.timeout).map(|_| ()).map_err(|e| format!("GET_STATUS failed: {}", e));
+            self.record(CheckKind::InterfaceStatus, location, result);
+
+            let mut num_settings = 0;
+
+            for setting in interface.descriptors() {
+                let location = FindingLocation::AlternateSetting { config: number, interface: iface, setting: setting.setting_number() };
+                let result = self.select_interface(iface, setting.setting_number());
+                let selected = result.is_ok();
+
+                num_settings += 1;
+                self.record(CheckKind::SetInterface, location, result);
+
+                if !selected {
+                    continue;
+                }
+
+                for endpoint in setting.endpoint_descriptors() {
+                    let location = FindingLocation::Endpoint {
+                        config: number,
+                        interface: iface,
+                        setting: setting.setting_number(),
+                        address: endpoint.address(),
+                    };
+
+                    if endpoint.transfer_type() == TransferType::Isochronous {
+                        self.skip(CheckKind::EndpointHalt, location, "isochronous endpoints have no halt feature");
+                    }
+                    else {
+                        let result = self.halt_endpoint(endpoint.address());
+                        self.record(CheckKind::EndpointHalt, location, result);
+                    }
+                }
+            }
+
+            if num_settings > 1 {
+                if let Err(e) = self.device.set_interface(iface, 0, self.timeout) {
+                    self.record(CheckKind::SetInterface, location, Err(format!("could not restore alternate setting 0: {}", e)));
+                }
+            }
+
+            if let Err(e) = self.device.finish_interface(iface) {
+                debug!("could not finish interface {}: {}", iface, e);
+            }
+        }
+    }
+
+    fn select_configuration(&mut self, config: u8) -> Result<(), String> {
+        self.device.set_configuration(config, self.timeout).map_err(|e| format!("SET_CONFIGURATION failed: {}", e))?;
+
+        match self.device.read_configuration(self.timeout) {
+            Ok(active) if active == config => Ok(()),
+            Ok(active) => Err(format!("GET_CONFIGURATION returned {}, expected {}", active, config)),
+            Err(e) => Err(format!("GET_CONFIGURATION failed: {}", e)),
+        }
+    }
+
+    fn select_interface(&mut self, iface: u8, setting: u8) -> Result<(), String> {
+        self.device.set_interface(iface, setting, self.timeout).map_err(|e| format!("SET_INTERFACE failed: {}", e))?;
+
+        match self.device.read_alternate_setting(iface, self.timeout) {
+            Ok(active) if active == setting => Ok(()),
+            Ok(active) => Err(format!("GET_INTERFACE returned {}, expected {}", active, setting)),
+            Err(e) => Err(format!("GET_INTERFACE failed: {}", e)),
+        }
+    }
+
+    fn halt_endpoint(&mut self, endpoint: u8) -> Result<(), String> {
+        let timeout = self.timeout;
+
+        if self.halted(endpoint)? {
+            return Err("the endpoint is halted after SET_INTERFACE".to_owned());
+        }
+
+        self.device.set_feature(Feature::EndpointHalt(endpoint), timeout).map_err(|e| format!("SET_FEATURE(ENDPOINT_HALT) failed: {}", e))?;
+
+        if !self.halted(endpoint)? {
+            return Err("GET_STATUS doesn't report the halt set by SET_FEATURE(ENDPOINT_HALT)".to_owned());
+        }
+
+        self.device.clear_endpoint_halt(endpoint, timeout).map_err(|e| format!("CLEAR_FEATURE(ENDPOINT_HALT) failed: {}", e))?;
+
+        if self.halted(endpoint)? {
+            return Err("the endpoint is still halted after CLEAR_FEATURE(ENDPOINT_HALT)".to_owned());
+        }
+
+        Ok(())
+    }
+
+    fn halted(&self, endpoint: u8) -> Result<bool, String> {
+        self.device.endpoint_halted(endpoint, self.timeout).map_err(|e| format!("GET_STATUS failed: {}", e))
+    }
+
+    /// Unconfigures the device and restores `initial_config`. The device is left unconfigured if
+    /// it was unconfigured initially or its initial configuration is unknown.
+    fn unconfigure(&mut self, configs: &[ConfigDescriptor], initial_config: Option<u8>) {
+        if configs.is_empty() {
+            self.skip(CheckKind::Unconfigure, FindingLocation::Device, "the device has no configurations");
+            return;
+        }
+
+        let result = self.select_configuration(0).and_then(|()| {
+            match initial_config {
+                Some(config) if config != 0 => {
+                    self.device.set_configuration(config, self.timeout).map_err(|e| format!("could not restore configuration {}: {}", config, e))
+                },
+                _ => Ok(()),
+            }
+        });
+
+        self.record(CheckKind::Unconfigure, FindingLocation::Device, result);
+    }
+
+    fn strings(&mut self, device: &DeviceDescriptor, configs: &[ConfigDescriptor]) {
+        let mut indices: Vec<u8> = Vec::new();
+
+        indices.extend(device.manufacturer_string_index());
+        indices.extend(device.product_string_index());
+        indices.extend(device.serial_number_string_index());
+
+        for config in configs {
+            indices.extend(config.description_string_index());
+
+            for interface in config.interfaces() {
+                for setting in interface.descriptors() {
+                    indices.extend(setting.description_string_index());
+                }
+            }
+        }
+
+        indices.sort();
+        indices.dedup();
+
+        if indices.is_empty() {
+            self.skip(CheckKind::Languages, FindingLocation::Device, "the descriptors don't refer to any strings");
+            return;
+        }
+
+        let languages = self.read(LIBUSB_DT_STRING, 0, 0, 255).and_then(|bytes| {
+            check_string(&bytes)?;
+
+            if bytes.len() < 4 {
+                return Err("string descriptor zero doesn't list any languages".to_owned());
+            }
+
+            Ok(bytes[2..].chunks(2).map(|id| read_u16(id, 0)).collect::<Vec<u16>>())
+        });
+
+        let languages = match languages {
+            Ok(languages) => {
+                self.record(CheckKind::Languages, FindingLocation::Device, Ok(()));
+                languages
+            },
+            Err(reason) => {
+                self.record(CheckKind::Languages, FindingLocation::Device, Err(reason));
+                return;
+            },
+        };
+
+        for index in indices {
+            let result = languages.iter().map(|&language_id| {
+                self.read(LIBUSB_DT_STRING, index, language_id, 255).and_then(|bytes| {
+                    check_string(&bytes)?;
+                    self.check_lengths(LIBUSB_DT_STRING, index, language_id, &bytes, 2)
+                }).map_err(|reason| format!("language {:#06x}: {}", language_id, reason))
+            }).collect::<Result<Vec<()>, String>>();
+
+            self.record(CheckKind::StringDescriptor(index), FindingLocation::Device, result.map(|_| ()));
+        }
+    }
+}
+
+/// Splits raw descriptors into the device descriptor and the configuration descriptor sets.
+fn split_descriptors(bytes: &[u8]) -> ::Result<(Vec<u8>, Vec<Vec<u8>>)> {
+    descriptor_parser::parse_descriptors(bytes)?;
+
+    let mut offset = bytes[0] as usize;
+    let mut configs = Vec::new();
+
+    while offset < bytes.len() {
+        let total_length = read_u16(bytes, offset + 2) as usize;
+
+        configs.push(bytes[offset..offset + total_length].to_vec());
+        offset += total_length;
+    }
+
+    Ok((bytes[..bytes[0] as usize].to_vec(), configs))
+}
+
+fn compare(actual: &[u8], expected: &[u8], name: &str) -> Result<(), String> {
+    if let Some(i) = actual.iter().zip(expected).position(|(a, e)| a != e) {
+        return Err(format!("{} differs at byte {}: got {:#04x}, expected {:#04x}", name, i, actual[i], expected[i]));
+    }
+
+    if actual.len() != expected.len() {
+        return Err(format!("{} is {} bytes long, expected {}", name, actual.len(), expected.len()));
+    }
+
+    Ok(())
+}
+
+fn check_string(bytes: &[u8]) -> Result<(), String> {
+    if bytes.len() < 2 || bytes[1] != LIBUSB_DT_STRING {
+        return Err("the response isn't a string descriptor".to_owned());
+    }
+
+    if bytes[0] as usize != bytes.len() {
+        return Err(format!("the string descriptor has bLength {}, but {} bytes were returned", bytes[0], bytes.len()));
+    }
+
+    if bytes.len() % 2 != 0 {
+        return Err(format!("the string descriptor has odd bLength {}", bytes[0]));
+    }
+
+    Ok(())
+}
+
+
+#[cfg(test)]
+mod test {
+    use std::cell::{Cell, RefCell};
+    use std::rc::Rc;
+    use std::time::Duration;
+    use libusb::*;
+    use error::Error;
+    use fields::TransferType;
+    use language;
+    use setup_packet::SetupPacket;
+    use descriptor_builder::{DeviceDescriptorBuilder, ConfigDescriptorBuilder, InterfaceDescriptorBuilder, EndpointDescriptorBuilder,
+                             string_descriptor, language_descriptor};
+    use descriptor_lint::FindingLocation;
+    use test_helpers::MockDevice;
+    use super::*;
+
+    impl Chapter9Device for MockDevice {}
+
+    const STRINGS: &'static [&'static str] = &["Acme", "Widget", "0001", "Default"];
+
+    #[derive(Default)]
+    struct State {
+        configuration: u8,
+        settings: Vec<(u8, u8)>,
+        halted: Vec<u8>,
+    }
+
+    fn timeout() -> Duration {
+        Duration::from_secs(1)
+    }
+
+    fn descriptors() -> DeviceDescriptorBuilder {
+        DeviceDescriptorBuilder::new(0x1209, 0x0001)
+            .manufacturer_string_index(1)
+            .product_string_index(2)
+            .serial_number_string_index(3)
+            .configuration(ConfigDescriptorBuilder::new(1)
+                .description_string_index(4)
+                .interface(InterfaceDescriptorBuilder::new(0)
+                    .class_code(0x03)
+                    .endpoint(EndpointDescriptorBuilder::new(0x81, TransferType::Interrupt).max_packet_size(8).interval(10)))
+                .interface(InterfaceDescriptorBuilder::new(1).class_code(0xFF))
+                .interface(InterfaceDescriptorBuilder::new(1)
+                    .setting_number(1)
+                    .class_code(0xFF)
+                    .endpoint(EndpointDescriptorBuilder::new(0x82, TransferType::Isochronous).max_packet_size(256).interval(1))
+                    .endpoint(EndpointDescriptorBuilder::new(0x02, TransferType::Bulk).max_packet_size(64))))
+    }
+
+    /// Returns a device that serves `builder`'s descriptors and keeps track of its configuration,
+    /// alternate settings and halted endpoints. Requests for which `fault` returns a response are
+    /// answered with that response instead.
+    fn simulated<F>(builder: DeviceDescriptorBuilder, fault: F) -> (MockDevice, Rc<RefCell<State>>)
+        where F: Fn(&SetupPacket, &State) -> Option<::Result<Vec<u8>>> + 'static,
+    {
+        let state = Rc::new(RefCell::new(State { configuration: 1, ..State::default() }));
+        let shared = state.clone();
+        let device = builder.device_descriptor();
+        let configs = builder.config_descriptors().to_vec();
+
+        let mock = MockDevice::new(move |setup, _| {
+            let mut state = shared.borrow_mut();
+
+            if let Some(response) = fault(setup, &state) {
+                return response;
+            }
+
+            let index = setup.index() as u8;
+
+            match (setup.raw_request_type(), setup.request()) {
+                (0x80, LIBUSB_REQUEST_GET_DESCRIPTOR) => {
+                    let descriptor_index = setup.value() as u8 as usize;
+
+                    match (setup.value() >> 8) as u8 {
+                        LIBUSB_DT_DEVICE => Ok(device.clone()),
+                        LIBUSB_DT_CONFIG => configs.get(descriptor_index).cloned().ok_or(Error::Pipe),
+                        LIBUSB_DT_STRING if descriptor_index == 0 => Ok(language_descriptor(&[language::from_lang_id(0x0409)])),
+                        LIBUSB_DT_STRING => STRINGS.get(descriptor_index - 1).map(|s| string_descriptor(s)).ok_or(Error::Pipe),
+                        _ => Err(Error::Pipe),
+                    }
+                },
+                (0x80, LIBUSB_REQUEST_GET_CONFIGURATION) => Ok(vec![state.configuration]),
+                (0x00, LIBUSB_REQUEST_SET_CONFIGURATION) => {
+                    state.configuration = setup.value() as u8;
+                    state.settings.clear();
+                    state.halted.clear();
+                    Ok(vec![])
+                },
+                (0x80, LIBUSB_REQUEST_GET_STATUS) |
+                (0x81, LIBUSB_REQUEST_GET_STATUS) => Ok(vec![0x00, 0x00]),
+                (0x82, LIBUSB_REQUEST_GET_STATUS) => Ok(vec![state.halted.contains(&index) as u8, 0x00]),
+                (0x02, LIBUSB_REQUEST_SET_FEATURE) => {
+                    state.halted.push(index);
+                    Ok(vec![])
+                },
+                (0x02, LIBUSB_REQUEST_CLEAR_FEATURE) => {
+                    state.halted.retain(|&endpoint| endpoint != index);
+                    Ok(vec![])
+                },
+                (0x81, LIBUSB_REQUEST_GET_INTERFACE) => {
+                    Ok(vec![state.settings.iter().find(|&&(iface, _)| iface == index).map_or(0, |&(_, setting)| setting)])
+                },
+                (0x01, LIBUSB_REQUEST_SET_INTERFACE) => {
+                    state.settings.retain(|&(iface, _)| iface != index);
+                    state.settings.push((index, setup.value() as u8));
+                    Ok(vec![])
+                },
+                _ => Err(Error::Pipe),
+            }
+        });
+
+        (mock, state)
+    }
+
+    fn no_fault(_: &SetupPacket, _: &State) -> Option<::Result<Vec<u8>>> {
+        None
+    }
+
+    fn summary(report: &Chapter9Report) -> Vec<(CheckKind, FindingLocation)> {
+        report.checks().iter().map(|check| (check.kind(), check.location())).collect()
+    }
+
+    fn failure_reasons(report: &Chapter9Report) -> Vec<String> {
+        report.failures().iter().map(|check| match *check.outcome() {
+            CheckOutcome::Failed(ref reason) => reason.clone(),
+            _ => unreachable!(),
+        }).collect()
+    }
+
+    #[test]
+    fn it_passes_a_compliant_device() {
+        let (mut device, state) = simulated(descriptors(), no_fault);
+        let report = Chapter9Test::new(timeout()).expected_descriptors(&descriptors().build()).run(&mut device);
+
+        assert!(report.passed(), "{}", report);
+
+        assert_eq!(vec![
+            (CheckKind::DeviceDescriptor, FindingLocation::Device),
+            (CheckKind::ConfigDescriptor, FindingLocation::Configuration { config: 1 }),
+            (CheckKind::InvalidConfigIndex, FindingLocation::Device),
+            (CheckKind::SetConfiguration, FindingLocation::Configuration { config: 1 }),
+            (CheckKind::DeviceStatus, FindingLocation::Configuration { config: 1 }),
+            (CheckKind::InterfaceStatus, FindingLocation::Interface { config: 1, interface: 0 }),
+            (CheckKind::SetInterface, FindingLocation::AlternateSetting { config: 1, interface: 0, setting: 0 }),
+            (CheckKind::EndpointHalt, FindingLocation::Endpoint { config: 1, interface: 0, setting: 0, address: 0x81 }),
+            (CheckKind::InterfaceStatus, FindingLocation::Interface { config: 1, interface: 1 }),
+            (CheckKind::SetInterface, FindingLocation::AlternateSetting { config: 1, interface: 1, setting: 0 }),
+            (CheckKind::SetInterface, FindingLocation::AlternateSetting { config: 1, interface: 1, setting: 1 }),
+            (CheckKind::EndpointHalt, FindingLocation::Endpoint { config: 1, interface: 1, setting: 1, address: 0x82 }),
+            (CheckKind::EndpointHalt, FindingLocation::Endpoint { config: 1, interface: 1, setting: 1, address: 0x02 }),
+            (CheckKind::Unconfigure, FindingLocation::Device),
+            (CheckKind::Languages, FindingLocation::Device),
+            (CheckKind::StringDescriptor(1), FindingLocation::Device),
+            (CheckKind::StringDescriptor(2), FindingLocation::Device),
+            (CheckKind::StringDescriptor(3), FindingLocation::Device),
+            (CheckKind::StringDescriptor(4), FindingLocation::Device),
+        ], summary(&report));
+
+        assert_eq!(&CheckOutcome::Skipped("isochronous endpoints have no halt feature".to_owned()), report.checks()[11].outcome());
+
+        assert_eq!(1, state.borrow().configuration);
+        assert!(state.borrow().halted.is_empty());
+    }
+
+    #[test]
+    fn it_leaves_initially_unconfigured_device_unconfigured() {
+        let (mut device, state) = simulated(descriptors(), no_fault);
+        state.borrow_mut().configuration = 0;
+
+        let report = Chapter9Test::new(timeout()).run(&mut device);
+
+        assert!(report.passed());
+        assert_eq!(0, state.borrow().configuration);
+    }
+
+    #[test]
+    fn it_does_not_restore_unknown_configuration() {
+        let unknown = Cell::new(true);
+
+        let (mut device, state) = simulated(descriptors(), move |setup, _| {
+            if setup.request() == LIBUSB_REQUEST_GET_CONFIGURATION && unknown.replace(false) {
+                Some(Err(Error::Pipe))
+            }
+            else {
+                None
+            }
+        });
+
+        let report = Chapter9Test::new(timeout()).run(&mut device);
+
+        assert!(report.passed());
+        assert_eq!(0, state.borrow().configuration);
+    }
+
+    #[test]
+    fn it_restores_alternate_settings() {
+        let (mut device, _) = simulated(descriptors(), no_fault);
+
+        Chapter9Test::new(timeout()).run(&mut device);
+
+        let set_interface = device.setups().into_iter().filter(|setup| setup.request() == LIBUSB_REQUEST_SET_INTERFACE).collect::<Vec<_>>();
+
+        assert_eq!(vec![
+            SetupPacket::new(0x01, LIBUSB_REQUEST_SET_INTERFACE, 0, 0, 0),
+            SetupPacket::new(0x01, LIBUSB_REQUEST_SET_INTERFACE, 0, 1, 0),
+            SetupPacket::new(0x01, LIBUSB_REQUEST_SET_INTERFACE, 1, 1, 0),
+            SetupPacket::new(0x01, LIBUSB_REQUEST_SET_INTERFACE, 0, 1, 0),
+        ], set_interface);
+    }
+
+    #[test]
+    fn it_reports_descriptors_that_differ_from_the_expected_ones() {
+        let (mut device, _) = simulated(descriptors(), no_fault);
+        let expected = DeviceDescriptorBuilder::new(0x1209, 0x0002)
+            .manufacturer_string_index(1)
+            .product_string_index(2)
+            .serial_number_string_index(3)
+            .configuration(ConfigDescriptorBuilder::new(1));
+
+        let report = Chapter9Test::new(timeout()).expected_descriptors(&expected.build()).run(&mut device);
+
+        assert_eq!(vec![
+            "the device descriptor differs at byte 10: got 0x01, expected 0x02".to_owned(),
+            "the configuration descriptor differs at byte 2: got 0x39, expected 0x09".to_owned(),
+        ], failure_reasons(&report));
+
+        assert_eq!(19, report.checks().len());
+    }
+
+    #[test]
+    fn it_reports_inconsistent_descriptor_reads() {
+        let full = descriptors().device_descriptor();
+        let (mut device, _) = simulated(descriptors(), move |setup, _| {
+            match (setup.request(), setup.value(), setup.length()) {
+                (LIBUSB_REQUEST_GET_DESCRIPTOR, 0x0100, 8) => Some(Ok(vec![0x12, 0x01, 0x10, 0x01, 0x00, 0x00, 0x00, 0x40])),
+                (LIBUSB_REQUEST_GET_DESCRIPTOR, 0x0100, 34) => Some(Ok(full.iter().cloned().chain(vec![0x00, 0x00]).collect())),
+                _ => None,
+            }
+        });
+
+        let report = Chapter9Test::new(timeout()).run(&mut device);
+
+        assert_eq!(vec!["the response to a request with wLength 8 differs at byte 2: got 0x10, expected 0x00".to_owned()],
+                   failure_reasons(&report));
+    }
+
+    #[test]
+    fn it_reports_endpoints_that_do_not_halt() {
+        let (mut device, _) = simulated(descriptors(), |setup, _| {
+            if setup.request() == LIBUSB_REQUEST_SET_FEATURE && setup.index() == 0x81 {
+                Some(Ok(vec![]))
+            }
+            else {
+                None
+            }
+        });
+
+        let report = Chapter9Test::new(timeout()).run(&mut device);
+
+        assert_eq!(vec!["FAIL endpoint halt (configuration 1, interface 0, alternate setting 0, endpoint 0x81): \
+                         GET_STATUS doesn't report the halt set by SET_FEATURE(ENDPOINT_HALT)".to_owned()],
+                   report.failures().iter().map(|check| check.to_string()).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn it_reports_alternate_settings_that_are_not_selected() {
+        let (mut device, _) = simulated(descriptors(), |setup, _| {
+            if setup.request() == LIBUSB_REQUEST_GET_INTERFACE { Some(Ok(vec![0])) } else { None }
+        });
+
+        let report = Chapter9Test::new(timeout()).run(&mut device);
+
+        assert_eq!(vec![(CheckKind::SetInterface, FindingLocation::AlternateSetting { config: 1, interface: 1, setting: 1 })],
+                   report.failures().iter().map(|check| (check.kind(), check.location())).collect::<Vec<_>>());
+        assert_eq!(vec!["GET_INTERFACE returned 0, expected 1".to_owned()], failure_reasons(&report));
+
+        assert!(!report.checks().iter().any(|check| match check.location() {
+            FindingLocation::Endpoint { setting: 1, .. } => true,
+            _ => false,
+        }));
+    }
+
+    #[test]
+    fn it_reports_configuration_state_errors() {
+        let (mut device, _) = simulated(descriptors(), |setup, _| {
+            match (setup.raw_request_type(), setup.request(), setup.value()) {
+                (0x80, LIBUSB_REQUEST_GET_DESCRIPTOR, 0x0201) => Some(Ok(vec![0x09, 0x02, 0x09, 0x00, 0x00, 0x02, 0x00, 0x80, 0x32])),
+                (0x80, LIBUSB_REQUEST_GET_STATUS, _) => Some(Ok(vec![0x01, 0x00])),
+                (0x00, LIBUSB_REQUEST_SET_CONFIGURATION, 0) => Some(Err(Error::Pipe)),
+                _ => None,
+            }
+        });
+
+        let report = Chapter9Test::new(timeout()).run(&mut device);
+
+        assert_eq!(vec![
+            "GET_DESCRIPTOR for configuration index 1 succeeded, but bNumConfigurations is 1".to_owned(),
+            "the device reports that it's self-powered, but configuration 1 is bus-powered".to_owned(),
+            "SET_CONFIGURATION failed: Pipe error".to_owned(),
+        ], failure_reasons(&report));
+    }
+
+    #[test]
+    fn it_reports_malformed_strings() {
+        let (mut device, _) = simulated(descriptors(), |setup, _| {
+            if setup.request() == LIBUSB_REQUEST_GET_DESCRIPTOR && setup.value() == 0x0303 {
+                Some(Ok(vec![0x05, 0x03, 0x30, 0x00, 0x31]))
+            }
+            else {
+                None
+            }
+        });
+
+        let report = Chapter9Test::new(timeout()).run(&mut device);
+
+        assert_eq!(vec![(CheckKind::StringDescriptor(3), FindingLocation::Device)],
+                   report.failures().iter().map(|check| (check.kind(), check.location())).collect::<Vec<_>>());
+        assert_eq!(vec!["language 0x0409: the string descriptor has odd bLength 5".to_owned()], failure_reasons(&report));
+    }
+
+    #[test]
+    fn it_stops_without_a_device_descriptor() {
+        let mut device = MockDevice::new(|_, _| Err(Error::Io));
+        let report = Chapter9Test::new(timeout()).run(&mut device);
+
+        assert!(!report.passed());
+        assert_eq!(vec!["GET_DESCRIPTOR(0x01, 0) with wLength 18 failed: Input/Output Error".to_owned()], failure_reasons(&report));
+        assert_eq!(1, report.checks().len());
+    }
+
+    #[test]
+    fn it_formats_report() {
+        let (mut device, _) = simulated(DeviceDescriptorBuilder::new(0x1209, 0x0001), no_fault);
+        let report = Chapter9Test::new(timeout()).run(&mut device);
+
+        assert_eq!("PASS device descriptor (device)\n\
+                    PASS invalid configuration index (device)\n\
+                    SKIP unconfigure (device): the device has no configurations\n\
+                    SKIP languages (device): the descriptors don't refer to any strings\n",
+                   report.to_string());
+    }
+
+    #[test]
+    fn it_sends_standard_requests_by_default() {
+        let mut device = MockDevice::new(|_, _| Ok(vec![]));
+
+        device.set_configuration(2, timeout()).unwrap();
+        device.set_interface(1, 3, timeout()).unwrap();
+        device.clear_endpoint_halt(0x81, timeout()).unwrap();
+
+        assert_eq!(vec![SetupPacket::new(0x00, LIBUSB_REQUEST_SET_CONFIGURATION, 2, 0, 0),
+                        SetupPacket::new(0x01, LIBUSB_REQUEST_SET_INTERFACE, 3, 1, 0),
+                        SetupPacket::new(0x02, LIBUSB_REQUEST_CLEAR_FEATURE, 0, 0x81, 0)], device.setups());
+    }
+}
@@ -0,0 +1,288 @@
+use std::mem;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use io::IoType;
+use device_handle::{self, DeviceHandle};
+use device_handle_sync_api::DeviceHandleSyncApi;
+use transfer_options::TransferOptions;
+use config_descriptor::ConfigDescriptor;
+use interface_descriptor::Interface;
+use endpoint_descriptor::EndpointDescriptor;
+use fields::Direction;
+use endpoint::{self, Endpoint, EndpointDirection, EndpointKind};
+
+
+/// How the kernel driver of a claimed interface was handled.
+#[doc(hidden)]
+#[derive(Debug,PartialEq,Eq,Clone,Copy)]
+pub enum KernelDriver {
+    /// No kernel driver was detached.
+    Untouched,
+
+    /// The kernel driver was detached with `libusb_detach_kernel_driver` and must be reattached.
+    Detached,
+
+    /// The kernel driver was detached by `libusb`'s auto-detach feature, which reattaches it when
+    /// the interface is released.
+    AutoDetached,
+}
+
+/// A claimed interface of an open device.
+///
+/// The interface is released when the guard goes out of scope. If the kernel driver was detached
+/// when the interface was claimed, it is reattached after the interface is released.
+///
+/// Alternate settings and I/O on the interface's endpoints can be done through the guard, which
+/// implements [`DeviceHandleSyncApi`](trait.DeviceHandleSyncApi.html) by forwarding to the device
+/// handle.
+///
+/// The guard keeps track of the interface's active setting, which is assumed to be the first
+/// setting when the interface is claimed and changes with
+/// [`set_alternate_setting`](#method.set_alternate_setting).
+pub struct ClaimedInterface<'dh, 'ctx: 'dh, Io>
+    where Io: IoType<'ctx>,
+{
+    handle: &'dh DeviceHandle<'ctx, Io>,
+    number: u8,
+    setting: AtomicUsize,
+    kernel_driver: KernelDriver,
+}
+
+impl<'dh, 'ctx, Io> Drop for ClaimedInterface<'dh, 'ctx, Io>
+    where Io: IoType<'ctx>,
+{
+    /// Releases the interface and reattaches the kernel driver.
+    fn drop(&mut self) {
+        if let Err(e) = device_handle::release_claimed(self.handle, self.number, self.kernel_driver) {
+            warn!("Could not release interface {}: {}", self.number, e);
+        }
+    }
+}
+
+impl<'dh, 'ctx, Io> ClaimedInterface<'dh, 'ctx, Io>
+    where Io: IoType<'ctx>,
+{
+    /// Returns the interface's number.
+    pub fn number(&self) -> u8 {
+        self.number
+    }
+
+    /// Returns the device handle that the interface was claimed on.
+    pub fn handle(&self) -> &'dh DeviceHandle<'ctx, Io> {
+        self.handle
+    }
+
+    /// Indicates if the guard detaches the interface's kernel driver and reattaches it on release.
+    pub fn kernel_driver_detached(&self) -> bool {
+        self.kernel_driver != KernelDriver::Untouched
+    }
+
+    /// Releases the interface, reporting any error.
+    ///
+    /// The interface is also released when the guard is dropped, but errors are only logged then.
+    pub fn release(self) -> ::Result<()> {
+        let res = device_handle::release_claimed(self.handle, self.number, self.kernel_driver);
+        mem::forget(self);
+        res
+    }
+
+    /// Sets the interface's active setting.
+    pub fn set_alternate_setting(&self, setting: u8) -> ::Result<()> {
+        device_handle::set_alternate_setting(self.handle, self.number, setting)?;
+        self.setting.store(setting as usize, Ordering::SeqCst);
+        Ok(())
+    }
+
+    /// Activates the alternate setting with the least periodic bandwidth that can transfer at least
+    /// `bytes_per_interval` bytes per service interval in `direction`.
+    ///
+    /// `interface` should describe the claimed interface. The setting is chosen with
+    /// [`Interface::setting_for_bandwidth`](struct.Interface.html#method.setting_for_bandwidth)
+    /// and its number is returned.
+    ///
+    /// ## Errors
+    ///
+    /// * `InvalidParam` if `interface` has a different number than the claimed interface.
+    /// * `NotFound` if no alternate setting provides enough bandwidth.
+    ///
+    /// ## Examples
+    ///
+    /// ```no_run
+    /// # use libusb::{ClaimedInterface, Direction, Interface};
+    /// # use libusb::io::sync::SyncIo;
+    /// # fn stream(iface: &ClaimedInterface<SyncIo>, interface: &Interface) -> libusb::Result<()> {
+    /// let setting = iface.set_alternate_setting_for_bandwidth(interface, Direction::In, 3072)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_alternate_setting_for_bandwidth(&self, interface: &Interface, direction: Direction, bytes_per_interval: u32) -> ::Result<u8> {
+        if interface.number() != self.number {
+            return Err(::Error::InvalidParam);
+        }
+
+        let setting = match interface.setting_for_bandwidth(direction, bytes_per_interval) {
+            Some(setting) => setting.setting_number(),
+            None => return Err(::Error::NotFound),
+        };
+
+        self.set_alternate_setting(setting)?;
+        Ok(setting)
+    }
+
+    /// Returns a typed handle to one of the interface's endpoints.
+    ///
+    /// `descriptor` must describe an endpoint of the interface's active setting in the device's
+    /// active configuration. The endpoint's direction and transfer type are given by `Dir` and
+    /// `Kind`.
+    ///
+    /// ## Errors
+    ///
+    /// * `InvalidParam` if the endpoint doesn't belong to the interface's active setting.
+    /// * `InvalidParam` if the descriptor's direction or transfer type doesn't match `Dir` or
+    ///   `Kind`.
+    /// * Any error of reading the active configuration descriptor.
+    ///
+    /// ## Examples
+    ///
+    /// ```no_run
+    /// # use libusb::{ClaimedInterface, EndpointDescriptor, In, Interrupt};
+    /// # use libusb::io::sync::SyncIo;
+    /// # fn read(iface: &ClaimedInterface<SyncIo>, descriptor: &EndpointDescriptor) -> libusb::Result<()> {
+    /// let endpoint = iface.endpoint::<In, Interrupt>(descriptor)?;
+    /// let mut buf = vec![0; endpoint.max_packet_size() as usize];
+    /// let len = endpoint.read(&mut buf, std::time::Duration::from_secs(1))?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn endpoint<'i, Dir, Kind>(&'i self, descriptor: &EndpointDescriptor) -> ::Result<Endpoint<'i, 'ctx, Io, Dir, Kind>>
+        where Dir: EndpointDirection,
+              Kind: EndpointKind,
+    {
+        let config = device_handle::active_config_descriptor(self.handle)?;
+        let setting = self.setting.load(Ordering::SeqCst) as u8;
+
+        if !has_endpoint(&config, self.number, setting, descriptor.address()) {
+            return Err(::Error::InvalidParam);
+        }
+
+        endpoint::from_descriptor(self.handle, descriptor)
+    }
+}
+
+impl<'dh, 'ctx, Io> DeviceHandleSyncApi for ClaimedInterface<'dh, 'ctx, Io>
+    where Io: IoType<'ctx>,
+          DeviceHandle<'ctx, Io>: DeviceHandleSyncApi,
+{
+    fn read_interrupt(&self, endpoint: u8, buf: &mut [u8], timeout: Duration) -> ::Result<usize> {
+        self.handle.read_interrupt(endpoint, buf, timeout)
+    }
+
+    fn write_interrupt(&self, endpoint: u8, buf: &[u8], timeout: Duration) -> ::Result<usize> {
+        self.handle.write_interrupt(endpoint, buf, timeout)
+    }
+
+    fn read_bulk(&self, endpoint: u8, buf: &mut [u8], timeout: Duration) -> ::Result<usize> {
+        self.handle.read_bulk(endpoint, buf, timeout)
+    }
+
+    fn write_bulk(&self, endpoint: u8, buf: &[u8], timeout: Duration) -> ::Result<usize> {
+        self.handle.write_bulk(endpoint, buf, timeout)
+    }
+
+    fn read_control(&self, request_type: u8, request: u8, value: u16, index: u16, buf: &mut [u8], timeout: Duration) -> ::Result<usize> {
+        self.handle.read_control(request_type, request, value, index, buf, timeout)
+    }
+
+    fn write_control(&self, request_type: u8, request: u8, value: u16, index: u16, buf: &[u8], timeout: Duration) -> ::Result<usize> {
+        self.handle.write_control(request_type, request, value, index, buf, timeout)
+    }
+
+    fn read_interrupt_with_options(&self, endpoint: u8, buf: &mut [u8], options: &TransferOptions) -> ::Result<usize> {
+        self.handle.read_interrupt_with_options(endpoint, buf, options)
+    }
+
+    fn write_interrupt_with_options(&self, endpoint: u8, buf: &[u8], options: &TransferOptions) -> ::Result<usize> {
+        self.handle.write_interrupt_with_options(endpoint, buf, options)
+    }
+
+    fn read_bulk_with_options(&self, endpoint: u8, buf: &mut [u8], options: &TransferOptions) -> ::Result<usize> {
+        self.handle.read_bulk_with_options(endpoint, buf, options)
+    }
+
+    fn write_bulk_with_options(&self, endpoint: u8, buf: &[u8], options: &TransferOptions) -> ::Result<usize> {
+        self.handle.write_bulk_with_options(endpoint, buf, options)
+    }
+
+    fn read_control_with_options(&self, request_type: u8, request: u8, value: u16, index: u16, buf: &mut [u8], options: &TransferOptions) -> ::Result<usize> {
+        self.handle.read_control_with_options(request_type, request, value, index, buf, options)
+    }
+
+    fn write_control_with_options(&self, request_type: u8, request: u8, value: u16, index: u16, buf: &[u8], options: &TransferOptions) -> ::Result<usize> {
+        self.handle.write_control_with_options(request_type, request, value, index, buf, options)
+    }
+
+    fn endpoint_halted(&self, endpoint: u8, timeout: Duration) -> ::Result<bool> {
+        self.handle.endpoint_halted(endpoint, timeout)
+    }
+}
+
+
+#[doc(hidden)]
+pub fn from_libusb<'dh, 'ctx, Io>(handle: &'dh DeviceHandle<'ctx, Io>, number: u8, kernel_driver: KernelDriver) -> ClaimedInterface<'dh, 'ctx, Io>
+    where Io: IoType<'ctx>,
+{
+    ClaimedInterface {
+        handle: handle,
+        number: number,
+        setting: AtomicUsize::new(0),
+        kernel_driver: kernel_driver,
+    }
+}
+
+/// Indicates if an alternate setting of an interface has an endpoint with the given address.
+fn has_endpoint(config: &ConfigDescriptor, iface: u8, setting: u8, address: u8) -> bool {
+    config.interfaces()
+        .filter(|interface| interface.number() == iface)
+        .flat_map(|interface| interface.descriptors())
+        .filter(|descriptor| descriptor.setting_number() == setting)
+        .any(|descriptor| descriptor.endpoint_descriptors().any(|endpoint| endpoint.address() == address))
+}
+
+
+
+#[cfg(test)]
+mod test {
+    use descriptor_builder::{ConfigDescriptorBuilder, InterfaceDescriptorBuilder, EndpointDescriptorBuilder};
+    use config_descriptor::ConfigDescriptor;
+    use fields::TransferType;
+    use test_helpers::build_config;
+    use super::has_endpoint;
+
+    fn config() -> ConfigDescriptor {
+        build_config(ConfigDescriptorBuilder::new(1)
+            .interface(InterfaceDescriptorBuilder::new(0)
+                .endpoint(EndpointDescriptorBuilder::new(0x81, TransferType::Interrupt)))
+            .interface(InterfaceDescriptorBuilder::new(1))
+            .interface(InterfaceDescriptorBuilder::new(1).setting_number(1)
+                .endpoint(EndpointDescriptorBuilder::new(0x82, TransferType::Bulk))))
+    }
+
+    #[test]
+    fn it_finds_endpoints_of_active_setting() {
+        assert!(has_endpoint(&config(), 0, 0, 0x81));
+        assert!(has_endpoint(&config(), 1, 1, 0x82));
+    }
+
+    #[test]
+    fn it_rejects_endpoints_of_other_settings() {
+        assert!(!has_endpoint(&config(), 1, 0, 0x82));
+        assert!(!has_endpoint(&config(), 2, 0, 0x82));
+    }
+
+    #[test]
+    fn it_rejects_endpoints_of_other_interfaces() {
+        assert!(!has_endpoint(&config(), 1, 1, 0x81));
+        assert!(!has_endpoint(&config(), 0, 0, 0x82));
+    }
+}
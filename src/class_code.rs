@@ -0,0 +1,396 @@
+use std::fmt;
+
+/// A class of devices or interfaces, identified by a class code assigned by the USB-IF.
+///
+/// The class code is found in the device descriptor, in interface descriptors and in interface
+/// association descriptors. Its meaning is refined by a sub class code and a protocol code, which
+/// together with the class code form a [`Class`](struct.Class.html).
+#[derive(Debug,PartialEq,Eq,Clone,Copy,Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum ClassCode {
+    /// The class is defined by each interface (device class `0x00`).
+    PerInterface,
+
+    /// Audio (`0x01`).
+    Audio,
+
+    /// Communications and CDC control (`0x02`).
+    Communications,
+
+    /// Human interface device (`0x03`).
+    Hid,
+
+    /// Physical interface device (`0x05`).
+    Physical,
+
+    /// Still imaging (`0x06`).
+    Image,
+
+    /// Printer (`0x07`).
+    Printer,
+
+    /// Mass storage (`0x08`).
+    MassStorage,
+
+    /// Hub (`0x09`).
+    Hub,
+
+    /// CDC data (`0x0A`).
+    CdcData,
+
+    /// Smart card (`0x0B`).
+    SmartCard,
+
+    /// Content security (`0x0D`).
+    ContentSecurity,
+
+    /// Video (`0x0E`).
+    Video,
+
+    /// Personal healthcare (`0x0F`).
+    PersonalHealthcare,
+
+    /// Audio/video devices (`0x10`).
+    AudioVideo,
+
+    /// Billboard (`0x11`).
+    Billboard,
+
+    /// USB Type-C bridge (`0x12`).
+    TypeCBridge,
+
+    /// Diagnostic device (`0xDC`).
+    Diagnostic,
+
+    /// Wireless controller (`0xE0`).
+    Wireless,
+
+    /// Miscellaneous (`0xEF`).
+    Miscellaneous,
+
+    /// Application specific (`0xFE`).
+    ApplicationSpecific,
+
+    /// Vendor specific (`0xFF`).
+    VendorSpecific,
+
+    /// A class code that isn't assigned.
+    Other(u8),
+}
+
+impl ClassCode {
+    /// Looks up a class code.
+    pub fn from_code(code: u8) -> Self {
+        match code {
+            0x00 => ClassCode::PerInterface,
+            0x01 => ClassCode::Audio,
+            0x02 => ClassCode::Communications,
+            0x03 => ClassCode::Hid,
+            0x05 => ClassCode::Physical,
+            0x06 => ClassCode::Image,
+            0x07 => ClassCode::Printer,
+            0x08 => ClassCode::MassStorage,
+            0x09 => ClassCode::Hub,
+            0x0A => ClassCode::CdcData,
+            0x0B => ClassCode::SmartCard,
+            0x0D => ClassCode::ContentSecurity,
+            0x0E => ClassCode::Video,
+            0x0F => ClassCode::PersonalHealthcare,
+            0x10 => ClassCode::AudioVideo,
+            0x11 => ClassCode::Billboard,
+            0x12 => ClassCode::TypeCBridge,
+            0xDC => ClassCode::Diagnostic,
+            0xE0 => ClassCode::Wireless,
+            0xEF => ClassCode::Miscellaneous,
+            0xFE => ClassCode::ApplicationSpecific,
+            0xFF => ClassCode::VendorSpecific,
+            n    => ClassCode::Other(n),
+        }
+    }
+
+    /// Returns the raw class code.
+    pub fn code(&self) -> u8 {
+        match *self {
+            ClassCode::PerInterface        => 0x00,
+            ClassCode::Audio               => 0x01,
+            ClassCode::Communications      => 0x02,
+            ClassCode::Hid                 => 0x03,
+            ClassCode::Physical            => 0x05,
+            ClassCode::Image               => 0x06,
+            ClassCode::Printer             => 0x07,
+            ClassCode::MassStorage         => 0x08,
+            ClassCode::Hub                 => 0x09,
+            ClassCode::CdcData             => 0x0A,
+            ClassCode::SmartCard           => 0x0B,
+            ClassCode::ContentSecurity     => 0x0D,
+            ClassCode::Video               => 0x0E,
+            ClassCode::PersonalHealthcare  => 0x0F,
+            ClassCode::AudioVideo          => 0x10,
+            ClassCode::Billboard           => 0x11,
+            ClassCode::TypeCBridge         => 0x12,
+            ClassCode::Diagnostic          => 0xDC,
+            ClassCode::Wireless            => 0xE0,
+            ClassCode::Miscellaneous       => 0xEF,
+            ClassCode::ApplicationSpecific => 0xFE,
+            ClassCode::VendorSpecific      => 0xFF,
+            ClassCode::Other(n)            => n,
+        }
+    }
+
+    /// Returns the class's name, or `None` for class codes that aren't assigned.
+    pub fn name(&self) -> Option<&'static str> {
+        match *self {
+            ClassCode::PerInterface        => Some("(Defined at Interface level)"),
+            ClassCode::Audio               => Some("Audio"),
+            ClassCode::Communications      => Some("Communications"),
+            ClassCode::Hid                 => Some("Human Interface Device"),
+            ClassCode::Physical            => Some("Physical Interface Device"),
+            ClassCode::Image               => Some("Imaging"),
+            ClassCode::Printer             => Some("Printer"),
+            ClassCode::MassStorage         => Some("Mass Storage"),
+            ClassCode::Hub                 => Some("Hub"),
+            ClassCode::CdcData             => Some("CDC Data"),
+            ClassCode::SmartCard           => Some("Chip/SmartCard"),
+            ClassCode::ContentSecurity     => Some("Content Security"),
+            ClassCode::Video               => Some("Video"),
+            ClassCode::PersonalHealthcare  => Some("Personal Healthcare"),
+            ClassCode::AudioVideo          => Some("Audio/Video"),
+            ClassCode::Billboard           => Some("Billboard"),
+            ClassCode::TypeCBridge         => Some("Type-C Bridge"),
+            ClassCode::Diagnostic          => Some("Diagnostic"),
+            ClassCode::Wireless            => Some("Wireless"),
+            ClassCode::Miscellaneous       => Some("Miscellaneous Device"),
+            ClassCode::ApplicationSpecific => Some("Application Specific Interface"),
+            ClassCode::VendorSpecific      => Some("Vendor Specific Class"),
+            ClassCode::Other(_)            => None,
+        }
+    }
+}
+
+impl fmt::Display for ClassCode {
+    /// Writes the class's name, or the class code in hexadecimal if it isn't assigned.
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match self.name() {
+            Some(name) => fmt.write_str(name),
+            None => write!(fmt, "Unknown class 0x{:02x}", self.code()),
+        }
+    }
+}
+
+
+/// A class code together with the sub class and protocol codes that refine it.
+///
+/// Names are known for the sub classes and protocols of common classes, such as HID boot
+/// keyboards and mice, CDC ACM modems, mass storage SCSI and bulk-only transport, audio, video,
+/// hubs and DFU.
+///
+/// ## Examples
+///
+/// ```
+/// let class = libusb::Class::new(0x03, 0x01, 0x01);
+///
+/// assert_eq!(libusb::ClassCode::Hid, class.class_code());
+/// assert_eq!(Some("Boot Interface Subclass"), class.sub_class_name());
+/// assert_eq!(Some("Keyboard"), class.protocol_name());
+/// ```
+#[derive(Debug,PartialEq,Eq,Clone,Copy,Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Class {
+    class_code: u8,
+    sub_class_code: u8,
+    protocol_code: u8,
+}
+
+impl Class {
+    /// Looks up a class from its class, sub class and protocol codes.
+    pub fn new(class_code: u8, sub_class_code: u8, protocol_code: u8) -> Self {
+        Class {
+            class_code: class_code,
+            sub_class_code: sub_class_code,
+            protocol_code: protocol_code,
+        }
+    }
+
+    /// Returns the class code.
+    pub fn class_code(&self) -> ClassCode {
+        ClassCode::from_code(self.class_code)
+    }
+
+    /// Returns the raw sub class code.
+    pub fn sub_class_code(&self) -> u8 {
+        self.sub_class_code
+    }
+
+    /// Returns the raw protocol code.
+    pub fn protocol_code(&self) -> u8 {
+        self.protocol_code
+    }
+
+    /// Returns the sub class's name, or `None` if it isn't known.
+    pub fn sub_class_name(&self) -> Option<&'static str> {
+        let name = match (self.class_code(), self.sub_class_code) {
+            (ClassCode::Audio, 0x01)               => "Control Device",
+            (ClassCode::Audio, 0x02)               => "Streaming",
+            (ClassCode::Audio, 0x03)               => "MIDI Streaming",
+            (ClassCode::Communications, 0x01)      => "Direct Line",
+            (ClassCode::Communications, 0x02)      => "Abstract (modem)",
+            (ClassCode::Communications, 0x03)      => "Telephone",
+            (ClassCode::Communications, 0x04)      => "Multi-Channel",
+            (ClassCode::Communications, 0x05)      => "CAPI Control",
+            (ClassCode::Communications, 0x06)      => "Ethernet Networking",
+            (ClassCode::Communications, 0x07)      => "ATM Networking",
+            (ClassCode::Communications, 0x08)      => "Wireless Handset Control",
+            (ClassCode::Communications, 0x09)      => "Device Management",
+            (ClassCode::Communications, 0x0A)      => "Mobile Direct Line",
+            (ClassCode::Communications, 0x0B)      => "OBEX",
+            (ClassCode::Communications, 0x0C)      => "Ethernet Emulation",
+            (ClassCode::Communications, 0x0D)      => "Network Control Model",
+            (ClassCode::Communications, 0x0E)      => "Mobile Broadband Interface Model",
+            (ClassCode::Hid, 0x00)                 => "No Subclass",
+            (ClassCode::Hid, 0x01)                 => "Boot Interface Subclass",
+            (ClassCode::Printer, 0x01)             => "Printer",
+            (ClassCode::MassStorage, 0x01)         => "RBC (typically Flash)",
+            (ClassCode::MassStorage, 0x02)         => "SFF-8020i, MMC-2 (ATAPI)",
+            (ClassCode::MassStorage, 0x03)         => "QIC-157",
+            (ClassCode::MassStorage, 0x04)         => "Floppy (UFI)",
+            (ClassCode::MassStorage, 0x05)         => "SFF-8070i",
+            (ClassCode::MassStorage, 0x06)         => "SCSI",
+            (ClassCode::Video, 0x01)               => "Video Control",
+            (ClassCode::Video, 0x02)               => "Video Streaming",
+            (ClassCode::Video, 0x03)               => "Video Interface Collection",
+            (ClassCode::Diagnostic, 0x01)          => "Reprogrammable Diagnostics",
+            (ClassCode::Wireless, 0x01)            => "Radio Frequency",
+            (ClassCode::Wireless, 0x02)            => "Wireless USB Wire Adapter",
+            (ClassCode::Miscellaneous, 0x01)       => "Sync",
+            (ClassCode::Miscellaneous, 0x02)       => "Common Class",
+            (ClassCode::Miscellaneous, 0x03)       => "Cable Based Association",
+            (ClassCode::Miscellaneous, 0x04)       => "RNDIS",
+            (ClassCode::Miscellaneous, 0x05)       => "USB3 Vision",
+            (ClassCode::ApplicationSpecific, 0x01) => "Device Firmware Update",
+            (ClassCode::ApplicationSpecific, 0x02) => "IRDA Bridge",
+            (ClassCode::ApplicationSpecific, 0x03) => "Test and Measurement",
+            (ClassCode::VendorSpecific, 0xFF)      => "Vendor Specific Subclass",
+            _ => return None,
+        };
+
+        Some(name)
+    }
+
+    /// Returns the protocol's name, or `None` if it isn't known.
+    pub fn protocol_name(&self) -> Option<&'static str> {
+        let name = match (self.class_code(), self.sub_class_code, self.protocol_code) {
+            (ClassCode::Audio, _, 0x20)                  => "Audio 2.0",
+            (ClassCode::Audio, _, 0x30)                  => "Audio 3.0",
+            (ClassCode::Communications, 0x02, 0x00)      => "None",
+            (ClassCode::Communications, 0x02, 0x01)      => "AT-commands (v.25ter)",
+            (ClassCode::Communications, 0x02, 0x02)      => "AT-commands (PCCA101)",
+            (ClassCode::Communications, 0x02, 0x03)      => "AT-commands (PCCA101 + wakeup)",
+            (ClassCode::Communications, 0x02, 0x04)      => "AT-commands (GSM)",
+            (ClassCode::Communications, 0x02, 0x05)      => "AT-commands (3G)",
+            (ClassCode::Communications, 0x02, 0x06)      => "AT-commands (CDMA)",
+            (ClassCode::Communications, 0x02, 0xFE)      => "Defined by command set descriptor",
+            (ClassCode::Communications, 0x02, 0xFF)      => "Vendor Specific (MSFT RNDIS?)",
+            (ClassCode::Hid, 0x00, 0x00)                 => "None",
+            (ClassCode::Hid, 0x01, 0x01)                 => "Keyboard",
+            (ClassCode::Hid, 0x01, 0x02)                 => "Mouse",
+            (ClassCode::Printer, 0x01, 0x01)             => "Unidirectional",
+            (ClassCode::Printer, 0x01, 0x02)             => "Bidirectional",
+            (ClassCode::Printer, 0x01, 0x03)             => "IEEE 1284.4 compatible bidirectional",
+            (ClassCode::Printer, 0x01, 0x04)             => "IPP over USB",
+            (ClassCode::MassStorage, _, 0x00)            => "Control/Bulk/Interrupt",
+            (ClassCode::MassStorage, _, 0x01)            => "Control/Bulk",
+            (ClassCode::MassStorage, _, 0x50)            => "Bulk-Only",
+            (ClassCode::MassStorage, _, 0x62)            => "UASP",
+            (ClassCode::Hub, 0x00, 0x00)                 => "Full speed (or root) hub",
+            (ClassCode::Hub, 0x00, 0x01)                 => "Single TT",
+            (ClassCode::Hub, 0x00, 0x02)                 => "TT per port",
+            (ClassCode::Hub, 0x00, 0x03)                 => "USB 3.0 hub",
+            (ClassCode::Video, _, 0x01)                  => "Video Class 1.5",
+            (ClassCode::Diagnostic, 0x01, 0x01)          => "USB2 Compliance",
+            (ClassCode::Wireless, 0x01, 0x01)            => "Bluetooth",
+            (ClassCode::Wireless, 0x01, 0x02)            => "Ultra WideBand Radio Control",
+            (ClassCode::Wireless, 0x01, 0x03)            => "RNDIS",
+            (ClassCode::Wireless, 0x01, 0x04)            => "Bluetooth AMP",
+            (ClassCode::Miscellaneous, 0x02, 0x01)       => "Interface Association",
+            (ClassCode::Miscellaneous, 0x02, 0x02)       => "Wire Adapter Multifunction Peripheral",
+            (ClassCode::ApplicationSpecific, 0x01, 0x01) => "Runtime",
+            (ClassCode::ApplicationSpecific, 0x01, 0x02) => "DFU Mode",
+            (ClassCode::ApplicationSpecific, 0x03, 0x00) => "TMC",
+            (ClassCode::ApplicationSpecific, 0x03, 0x01) => "USB488",
+            (ClassCode::VendorSpecific, 0xFF, 0xFF)      => "Vendor Specific Protocol",
+            _ => return None,
+        };
+
+        Some(name)
+    }
+}
+
+impl fmt::Display for Class {
+    /// Writes the names of the class, sub class and protocol that are known, separated by
+    /// " / ".
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "{}", self.class_code())?;
+
+        for name in self.sub_class_name().iter().chain(self.protocol_name().iter()) {
+            write!(fmt, " / {}", name)?;
+        }
+
+        Ok(())
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::{Class, ClassCode};
+
+    #[test]
+    fn it_round_trips_class_codes() {
+        for code in 0..256 {
+            assert_eq!(code as u8, ClassCode::from_code(code as u8).code());
+        }
+    }
+
+    #[test]
+    fn it_looks_up_class_codes() {
+        assert_eq!(ClassCode::Hid, ClassCode::from_code(0x03));
+        assert_eq!(ClassCode::VendorSpecific, ClassCode::from_code(0xFF));
+        assert_eq!(ClassCode::Other(0x04), ClassCode::from_code(0x04));
+    }
+
+    #[test]
+    fn it_has_class_names() {
+        assert_eq!("Mass Storage", ClassCode::MassStorage.to_string());
+        assert_eq!("Miscellaneous Device", ClassCode::Miscellaneous.to_string());
+        assert_eq!("Unknown class 0x42", ClassCode::Other(0x42).to_string());
+        assert_eq!(None, ClassCode::Other(0x42).name());
+    }
+
+    #[test]
+    fn it_names_well_known_sub_classes_and_protocols() {
+        assert_eq!((Some("Boot Interface Subclass"), Some("Mouse")), names(0x03, 0x01, 0x02));
+        assert_eq!((Some("Abstract (modem)"), Some("AT-commands (v.25ter)")), names(0x02, 0x02, 0x01));
+        assert_eq!((Some("SCSI"), Some("Bulk-Only")), names(0x08, 0x06, 0x50));
+        assert_eq!((Some("Streaming"), Some("Audio 2.0")), names(0x01, 0x02, 0x20));
+        assert_eq!((Some("Video Streaming"), None), names(0x0E, 0x02, 0x00));
+        assert_eq!((Some("Device Firmware Update"), Some("DFU Mode")), names(0xFE, 0x01, 0x02));
+    }
+
+    #[test]
+    fn it_doesnt_name_protocols_of_other_sub_classes() {
+        assert_eq!((None, None), names(0x03, 0x02, 0x01));
+        assert_eq!((None, None), names(0xFF, 0x00, 0x00));
+        assert_eq!((None, None), names(0x42, 0x01, 0x01));
+    }
+
+    #[test]
+    fn it_writes_known_names() {
+        assert_eq!("Human Interface Device / Boot Interface Subclass / Keyboard", Class::new(0x03, 0x01, 0x01).to_string());
+        assert_eq!("Vendor Specific Class", Class::new(0xFF, 0x42, 0x00).to_string());
+    }
+
+    fn names(class_code: u8, sub_class_code: u8, protocol_code: u8) -> (Option<&'static str>, Option<&'static str>) {
+        let class = Class::new(class_code, sub_class_code, protocol_code);
+        (class.sub_class_name(), class.protocol_name())
+    }
+}
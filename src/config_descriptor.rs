@@ -5,16 +5,23 @@ use std::slice;
 use libusb::*;
 
 use interface_descriptor::{self, Interface};
+use descriptor_parser::{self, OwnedConfig, DT_OTHER_SPEED_CONFIGURATION};
+use extra_descriptors::{self, ExtraDescriptors};
+use interface_association::{self, InterfaceAssociation, Functions};
 
 /// Describes a configuration.
 pub struct ConfigDescriptor {
     descriptor: *const libusb_config_descriptor,
+    owned: Option<OwnedConfig>,
 }
 
 impl Drop for ConfigDescriptor {
     fn drop(&mut self) {
-        unsafe {
-            libusb_free_config_descriptor(self.descriptor);
+        // Descriptors parsed by this crate are freed with their storage
+        if self.owned.is_none() {
+            unsafe {
+                libusb_free_config_descriptor(self.descriptor);
+            }
         }
     }
 }
@@ -23,6 +30,39 @@ unsafe impl Sync for ConfigDescriptor {}
 unsafe impl Send for ConfigDescriptor {}
 
 impl ConfigDescriptor {
+    /// Parses a configuration descriptor from its raw bytes.
+    ///
+    /// `bytes` must hold the whole configuration as returned by a `GET_DESCRIPTOR` request: the
+    /// configuration descriptor followed by its interface, endpoint and class-specific
+    /// descriptors, `wTotalLength` bytes in all. Bytes after `wTotalLength` are ignored. Other
+    /// speed configuration descriptors are accepted too.
+    ///
+    /// The number of interfaces and endpoints is taken from the descriptors that are present, not
+    /// from the `bNumInterfaces` and `bNumEndpoints` fields.
+    ///
+    /// ## Errors
+    ///
+    /// * `Custom` if the bytes are malformed, with a message that describes the problem.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// let bytes = [
+    ///     0x09, 0x02, 0x19, 0x00, 0x01, 0x01, 0x00, 0x80, 0x32,
+    ///     0x09, 0x04, 0x00, 0x00, 0x01, 0xFF, 0x00, 0x00, 0x00,
+    ///     0x07, 0x05, 0x81, 0x02, 0x00, 0x02, 0x00,
+    /// ];
+    ///
+    /// let config = libusb::ConfigDescriptor::from_bytes(&bytes).unwrap();
+    /// assert_eq!(1, config.num_interfaces());
+    /// ```
+    pub fn from_bytes(bytes: &[u8]) -> ::Result<Self> {
+        match bytes.get(1) {
+            Some(&DT_OTHER_SPEED_CONFIGURATION) => descriptor_parser::parse_config(bytes, DT_OTHER_SPEED_CONFIGURATION),
+            _ => descriptor_parser::parse_config(bytes, LIBUSB_DT_CONFIG),
+        }
+    }
+
     /// Returns the configuration number.
     pub fn number(&self) -> u8 {
         unsafe {
@@ -79,6 +119,40 @@ impl ConfigDescriptor {
 
         Interfaces { iter: interfaces.iter() }
     }
+
+    /// Returns the configuration's interface association descriptors, ordered by their first
+    /// interface.
+    pub fn interface_associations(&self) -> Vec<InterfaceAssociation> {
+        interface_association::associations(self)
+    }
+
+    /// Returns an iterator over the configuration's functions.
+    ///
+    /// Interfaces are grouped by the interface association descriptors (IADs) of composite
+    /// devices. Interfaces that no IAD covers are grouped by class instead: a CDC communications
+    /// interface takes the interfaces listed in its union functional descriptor, or else the CDC
+    /// data interfaces that follow it, and audio and video control interfaces take the streaming
+    /// interfaces that follow them. Every other interface is a function of its own.
+    ///
+    /// Functions are ordered by their first interface number.
+    pub fn functions(&self) -> Functions {
+        interface_association::functions(self)
+    }
+
+    /// Returns the extra descriptors that follow the configuration descriptor.
+    ///
+    /// These are descriptors that appear before the first interface descriptor, such as the
+    /// interface association descriptors of a composite device.
+    pub fn extra(&self) -> &[u8] {
+        unsafe {
+            extra_descriptors::from_libusb((*self.descriptor).extra, (*self.descriptor).extra_length)
+        }
+    }
+
+    /// Returns an iterator over the extra descriptors that follow the configuration descriptor.
+    pub fn extra_descriptors(&self) -> ExtraDescriptors {
+        ExtraDescriptors::new(self.extra())
+    }
 }
 
 impl fmt::Debug for ConfigDescriptor {
@@ -124,7 +198,12 @@ impl<'a> Iterator for Interfaces<'a> {
 
 #[doc(hidden)]
 pub unsafe fn from_libusb(config: *const libusb_config_descriptor) -> ConfigDescriptor {
-    ConfigDescriptor { descriptor: config }
+    ConfigDescriptor { descriptor: config, owned: None }
+}
+
+#[doc(hidden)]
+pub fn from_owned(config: OwnedConfig) -> ConfigDescriptor {
+    ConfigDescriptor { descriptor: config.descriptor(), owned: Some(config) }
 }
 
 
@@ -220,4 +299,22 @@ mod test {
             assert_eq!(vec![1], interface_numbers);
         });
     }
+
+    #[test]
+    fn it_has_extra_descriptors() {
+        let extra: [u8; 8] = [0x08, 0x0B, 0x00, 0x02, 0x02, 0x02, 0x01, 0x00];
+
+        with_config!(config: config_descriptor!(extra: extra.as_ptr(), extra_length: 8) => {
+            assert_eq!(&extra[..], config.extra());
+            assert_eq!(vec![0x0B], config.extra_descriptors().map(|d| d.descriptor_type()).collect::<Vec<_>>());
+        });
+    }
+
+    #[test]
+    fn it_handles_missing_extra_descriptors() {
+        with_config!(config: config_descriptor!(bConfigurationValue: 1) => {
+            assert!(config.extra().is_empty());
+            assert_eq!(0, config.extra_descriptors().count());
+        });
+    }
 }
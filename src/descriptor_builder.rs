@@ -0,0 +1,784 @@
+use libusb::*;
+
+use fields::{self, Version, TransferType, SyncType, UsageType};
+use language::Language;
+use descriptor_parser::DT_INTERFACE_ASSOCIATION;
+
+
+/// Builds the bytes of a device descriptor and its configurations.
+///
+/// The builder produces spec-correct bytes that can be served by a gadget or a mock device, or
+/// parsed with [`parse_descriptors`](fn.parse_descriptors.html). Counts and lengths, such as
+/// `bNumConfigurations`, `wTotalLength`, `bNumInterfaces` and `bNumEndpoints`, are computed from
+/// the descriptors that are added.
+///
+/// ## Examples
+///
+/// A device with a single bulk interface:
+///
+/// ```
+/// use libusb::{DeviceDescriptorBuilder, ConfigDescriptorBuilder, InterfaceDescriptorBuilder,
+///              EndpointDescriptorBuilder, TransferType};
+///
+/// let bytes = DeviceDescriptorBuilder::new(0x1209, 0x0001)
+///     .configuration(ConfigDescriptorBuilder::new(1)
+///         .max_power(100)
+///         .interface(InterfaceDescriptorBuilder::new(0)
+///             .class_code(0xFF)
+///             .endpoint(EndpointDescriptorBuilder::new(0x81, TransferType::Bulk).max_packet_size(512))
+///             .endpoint(EndpointDescriptorBuilder::new(0x01, TransferType::Bulk).max_packet_size(512))))
+///     .build();
+///
+/// let (device, configs) = libusb::parse_descriptors(&bytes).unwrap();
+///
+/// assert_eq!(0x1209, device.vendor_id());
+/// assert_eq!(2, configs[0].interfaces().next().unwrap().descriptors().next().unwrap().num_endpoints());
+/// ```
+#[derive(Debug,Clone)]
+pub struct DeviceDescriptorBuilder {
+    usb_version: Version,
+    class_code: u8,
+    sub_class_code: u8,
+    protocol_code: u8,
+    max_packet_size: u8,
+    vendor_id: u16,
+    product_id: u16,
+    device_version: Version,
+    manufacturer_string_index: u8,
+    product_string_index: u8,
+    serial_number_string_index: u8,
+    configurations: Vec<Vec<u8>>,
+}
+
+impl DeviceDescriptorBuilder {
+    /// Creates a builder for a device with the given vendor and product IDs.
+    ///
+    /// The device starts as a USB 2.0 device with a 64 byte control endpoint, no class, no
+    /// strings and no configurations.
+    pub fn new(vendor_id: u16, product_id: u16) -> Self {
+        DeviceDescriptorBuilder {
+            usb_version: Version(2, 0, 0),
+            class_code: 0,
+            sub_class_code: 0,
+            protocol_code: 0,
+            max_packet_size: 64,
+            vendor_id: vendor_id,
+            product_id: product_id,
+            device_version: Version(0, 0, 0),
+            manufacturer_string_index: 0,
+            product_string_index: 0,
+            serial_number_string_index: 0,
+            configurations: Vec::new(),
+        }
+    }
+
+    /// Sets the USB version that the device supports (`bcdUSB`).
+    pub fn usb_version(mut self, version: Version) -> Self {
+        self.usb_version = version;
+        self
+    }
+
+    /// Sets the device's class code.
+    pub fn class_code(mut self, class_code: u8) -> Self {
+        self.class_code = class_code;
+        self
+    }
+
+    /// Sets the device's sub class code.
+    pub fn sub_class_code(mut self, sub_class_code: u8) -> Self {
+        self.sub_class_code = sub_class_code;
+        self
+    }
+
+    /// Sets the device's protocol code.
+    pub fn protocol_code(mut self, protocol_code: u8) -> Self {
+        self.protocol_code = protocol_code;
+        self
+    }
+
+    /// Sets the maximum packet size of the device's control endpoint.
+    pub fn max_packet_size(mut self, max_packet_size: u8) -> Self {
+        self.max_packet_size = max_packet_size;
+        self
+    }
+
+    /// Sets the manufacturer's version of the device (`bcdDevice`).
+    pub fn device_version(mut self, version: Version) -> Self {
+        self.device_version = version;
+        self
+    }
+
+    /// Sets the index of the string descriptor that contains the manufacturer name.
+    pub fn manufacturer_string_index(mut self, index: u8) -> Self {
+        self.manufacturer_string_index = index;
+        self
+    }
+
+    /// Sets the index of the string descriptor that contains the product name.
+    pub fn product_string_index(mut self, index: u8) -> Self {
+        self.product_string_index = index;
+        self
+    }
+
+    /// Sets the index of the string descriptor that contains the device's serial number.
+    pub fn serial_number_string_index(mut self, index: u8) -> Self {
+        self.serial_number_string_index = index;
+        self
+    }
+
+    /// Adds a configuration.
+    pub fn configuration(mut self, configuration: ConfigDescriptorBuilder) -> Self {
+        self.configurations.push(configuration.build());
+        self
+    }
+
+    /// Returns the 18 bytes of the device descriptor.
+    pub fn device_descriptor(&self) -> Vec<u8> {
+        let usb_version = self.usb_version.to_bcd();
+        let device_version = self.device_version.to_bcd();
+
+        vec![
+            18,
+            LIBUSB_DT_DEVICE,
+            usb_version as u8,
+            (usb_version >> 8) as u8,
+            self.class_code,
+            self.sub_class_code,
+            self.protocol_code,
+            self.max_packet_size,
+            self.vendor_id as u8,
+            (self.vendor_id >> 8) as u8,
+            self.product_id as u8,
+            (self.product_id >> 8) as u8,
+            device_version as u8,
+            (device_version >> 8) as u8,
+            self.manufacturer_string_index,
+            self.product_string_index,
+            self.serial_number_string_index,
+            self.configurations.len() as u8,
+        ]
+    }
+
+    /// Returns the bytes of the configurations, in the order in which they were added.
+    ///
+    /// Each configuration's bytes are what a device returns for a `GET_DESCRIPTOR` request for its
+    /// full configuration descriptor.
+    pub fn config_descriptors(&self) -> &[Vec<u8>] {
+        &self.configurations
+    }
+
+    /// Returns the device descriptor followed by every configuration.
+    ///
+    /// This is the layout that [`parse_descriptors`](fn.parse_descriptors.html) reads.
+    pub fn build(&self) -> Vec<u8> {
+        let mut bytes = self.device_descriptor();
+
+        for configuration in &self.configurations {
+            bytes.extend_from_slice(configuration);
+        }
+
+        bytes
+    }
+}
+
+/// Builds the bytes of a configuration descriptor and the descriptors that follow it.
+///
+/// Descriptors are written in the order in which they're added. `wTotalLength` and
+/// `bNumInterfaces` are computed when the configuration is built.
+#[derive(Debug,Clone)]
+pub struct ConfigDescriptorBuilder {
+    number: u8,
+    description_string_index: u8,
+    attributes: u8,
+    max_power: u8,
+    interfaces: Vec<u8>,
+    body: Vec<u8>,
+}
+
+impl ConfigDescriptorBuilder {
+    /// Creates a builder for the configuration with the given number (`bConfigurationValue`).
+    ///
+    /// The configuration starts bus-powered, without remote wakeup and without power
+    /// consumption.
+    pub fn new(number: u8) -> Self {
+        ConfigDescriptorBuilder {
+            number: number,
+            description_string_index: 0,
+            attributes: 0x80,
+            max_power: 0,
+            interfaces: Vec::new(),
+            body: Vec::new(),
+        }
+    }
+
+    /// Sets the index of the string descriptor that describes the configuration.
+    pub fn description_string_index(mut self, index: u8) -> Self {
+        self.description_string_index = index;
+        self
+    }
+
+    /// Sets whether the device is self-powered in this configuration.
+    pub fn self_powered(mut self, self_powered: bool) -> Self {
+        self.attributes = self.attributes & !0x40 | (self_powered as u8) << 6;
+        self
+    }
+
+    /// Sets whether the device supports remote wakeup in this configuration.
+    pub fn remote_wakeup(mut self, remote_wakeup: bool) -> Self {
+        self.attributes = self.attributes & !0x20 | (remote_wakeup as u8) << 5;
+        self
+    }
+
+    /// Sets the device's maximum power consumption in this configuration.
+    ///
+    /// The power is given in the units of [`ConfigDescriptor::max_power`](struct.ConfigDescriptor.html#method.max_power)
+    /// and rounded up to the 2 unit granularity of `bMaxPower`.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if the power doesn't fit in `bMaxPower`.
+    pub fn max_power(mut self, max_power: u16) -> Self {
+        let units = max_power / 2 + max_power % 2;
+        assert!(units <= 0xFF, "max power {} doesn't fit in bMaxPower", max_power);
+
+        self.max_power = units as u8;
+        self
+    }
+
+    /// Adds an interface association descriptor.
+    pub fn association(mut self, association: InterfaceAssociationBuilder) -> Self {
+        self.body.extend_from_slice(&association.build());
+        self
+    }
+
+    /// Adds an interface descriptor, which describes an alternate setting of an interface.
+    ///
+    /// Every alternate setting of an interface is added separately. `bNumInterfaces` counts
+    /// the distinct interface numbers.
+    pub fn interface(mut self, interface: InterfaceDescriptorBuilder) -> Self {
+        if !self.interfaces.contains(&interface.number) {
+            self.interfaces.push(interface.number);
+        }
+
+        self.body.extend_from_slice(&interface.build());
+        self
+    }
+
+    /// Adds a class- or vendor-specific descriptor with the given type and body.
+    ///
+    /// `bLength` and `bDescriptorType` are prepended to `body`.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if the descriptor is longer than 255 bytes.
+    pub fn descriptor(mut self, descriptor_type: u8, body: &[u8]) -> Self {
+        push_descriptor(&mut self.body, descriptor_type, body);
+        self
+    }
+
+    /// Returns the bytes of the configuration.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if the configuration is longer than 65535 bytes.
+    pub fn build(&self) -> Vec<u8> {
+        let total_length = 9 + self.body.len();
+        assert!(total_length <= 0xFFFF, "configuration is {} bytes long, which overflows wTotalLength", total_length);
+
+        let mut bytes = vec![
+            9,
+            LIBUSB_DT_CONFIG,
+            total_length as u8,
+            (total_length >> 8) as u8,
+            self.interfaces.len() as u8,
+            self.number,
+            self.description_string_index,
+            self.attributes,
+            self.max_power,
+        ];
+
+        bytes.extend_from_slice(&self.body);
+        bytes
+    }
+}
+
+/// Builds the bytes of an interface descriptor, its class-specific descriptors and its endpoints.
+#[derive(Debug,Clone)]
+pub struct InterfaceDescriptorBuilder {
+    number: u8,
+    setting_number: u8,
+    class_code: u8,
+    sub_class_code: u8,
+    protocol_code: u8,
+    description_string_index: u8,
+    num_endpoints: u8,
+    extra: Vec<u8>,
+    endpoints: Vec<u8>,
+}
+
+impl InterfaceDescriptorBuilder {
+    /// Creates a builder for the default alternate setting of the interface with the given
+    /// number.
+    pub fn new(number: u8) -> Self {
+        InterfaceDescriptorBuilder {
+            number: number,
+            setting_number: 0,
+            class_code: 0,
+            sub_class_code: 0,
+            protocol_code: 0,
+            description_string_index: 0,
+            num_endpoints: 0,
+            extra: Vec::new(),
+            endpoints: Vec::new(),
+        }
+    }
+
+    /// Sets the alternate setting number.
+    pub fn setting_number(mut self, setting_number: u8) -> Self {
+        self.setting_number = setting_number;
+        self
+    }
+
+    /// Sets the interface's class code.
+    pub fn class_code(mut self, class_code: u8) -> Self {
+        self.class_code = class_code;
+        self
+    }
+
+    /// Sets the interface's sub class code.
+    pub fn sub_class_code(mut self, sub_class_code: u8) -> Self {
+        self.sub_class_code = sub_class_code;
+        self
+    }
+
+    /// Sets the interface's protocol code.
+    pub fn protocol_code(mut self, protocol_code: u8) -> Self {
+        self.protocol_code = protocol_code;
+        self
+    }
+
+    /// Sets the index of the string descriptor that describes the interface.
+    pub fn description_string_index(mut self, index: u8) -> Self {
+        self.description_string_index = index;
+        self
+    }
+
+    /// Adds a class- or vendor-specific descriptor, which is written after the interface
+    /// descriptor and before the endpoints.
+    ///
+    /// `bLength` and `bDescriptorType` are prepended to `body`.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if the descriptor is longer than 255 bytes.
+    pub fn descriptor(mut self, descriptor_type: u8, body: &[u8]) -> Self {
+        push_descriptor(&mut self.extra, descriptor_type, body);
+        self
+    }
+
+    /// Adds an endpoint.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if the interface already has 255 endpoints, which is as many as `bNumEndpoints`
+    /// can count.
+    pub fn endpoint(mut self, endpoint: EndpointDescriptorBuilder) -> Self {
+        assert!(self.num_endpoints < 0xFF, "interface {} has too many endpoints for bNumEndpoints", self.number);
+        self.num_endpoints += 1;
+        self.endpoints.extend_from_slice(&endpoint.build());
+        self
+    }
+
+    /// Returns the bytes of the interface descriptor, followed by its class-specific descriptors
+    /// and its endpoints.
+    pub fn build(&self) -> Vec<u8> {
+        let mut bytes = vec![
+            9,
+            LIBUSB_DT_INTERFACE,
+            self.number,
+            self.setting_number,
+            self.num_endpoints,
+            self.class_code,
+            self.sub_class_code,
+            self.protocol_code,
+            self.description_string_index,
+        ];
+
+        bytes.extend_from_slice(&self.extra);
+        bytes.extend_from_slice(&self.endpoints);
+        bytes
+    }
+}
+
+/// Builds the bytes of an endpoint descriptor and the descriptors that follow it.
+#[derive(Debug,Clone)]
+pub struct EndpointDescriptorBuilder {
+    address: u8,
+    transfer_type: TransferType,
+    sync_type: SyncType,
+    usage_type: UsageType,
+    max_packet_size: u16,
+    interval: u8,
+    extra: Vec<u8>,
+}
+
+impl EndpointDescriptorBuilder {
+    /// Creates a builder for an endpoint with the given address and transfer type.
+    ///
+    /// The direction is part of the address: bit 7 is set for IN endpoints.
+    pub fn new(address: u8, transfer_type: TransferType) -> Self {
+        EndpointDescriptorBuilder {
+            address: address,
+            transfer_type: transfer_type,
+            sync_type: SyncType::NoSync,
+            usage_type: UsageType::Data,
+            max_packet_size: 0,
+            interval: 0,
+            extra: Vec::new(),
+        }
+    }
+
+    /// Sets the synchronisation type of an isochronous endpoint.
+    pub fn sync_type(mut self, sync_type: SyncType) -> Self {
+        self.sync_type = sync_type;
+        self
+    }
+
+    /// Sets the usage type of an isochronous endpoint.
+    pub fn usage_type(mut self, usage_type: UsageType) -> Self {
+        self.usage_type = usage_type;
+        self
+    }
+
+    /// Sets the raw `wMaxPacketSize` field, including the additional transactions of
+    /// high-bandwidth endpoints in bits 11 and 12.
+    pub fn max_packet_size(mut self, max_packet_size: u16) -> Self {
+        self.max_packet_size = max_packet_size;
+        self
+    }
+
+    /// Sets the polling interval (`bInterval`).
+    pub fn interval(mut self, interval: u8) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Adds a SuperSpeed endpoint companion descriptor.
+    pub fn ss_companion(mut self, max_burst: u8, attributes: u8, bytes_per_interval: u16) -> Self {
+        let body = [max_burst, attributes, bytes_per_interval as u8, (bytes_per_interval >> 8) as u8];
+
+        push_descriptor(&mut self.extra, LIBUSB_DT_SS_ENDPOINT_COMPANION, &body);
+        self
+    }
+
+    /// Adds a class- or vendor-specific descriptor, which is written after the endpoint
+    /// descriptor.
+    ///
+    /// `bLength` and `bDescriptorType` are prepended to `body`.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if the descriptor is longer than 255 bytes.
+    pub fn descriptor(mut self, descriptor_type: u8, body: &[u8]) -> Self {
+        push_descriptor(&mut self.extra, descriptor_type, body);
+        self
+    }
+
+    /// Returns the bytes of the endpoint descriptor, followed by the descriptors that were added
+    /// to it.
+    pub fn build(&self) -> Vec<u8> {
+        let mut bytes = vec![
+            7,
+            LIBUSB_DT_ENDPOINT,
+            self.address,
+            fields::endpoint_attributes(self.transfer_type, self.sync_type, self.usage_type),
+            self.max_packet_size as u8,
+            (self.max_packet_size >> 8) as u8,
+            self.interval,
+        ];
+
+        bytes.extend_from_slice(&self.extra);
+        bytes
+    }
+}
+
+/// Builds the bytes of an interface association descriptor.
+#[derive(Debug,Clone,Copy)]
+pub struct InterfaceAssociationBuilder {
+    first_interface: u8,
+    interface_count: u8,
+    class_code: u8,
+    sub_class_code: u8,
+    protocol_code: u8,
+    description_string_index: u8,
+}
+
+impl InterfaceAssociationBuilder {
+    /// Creates a builder for an association of `interface_count` consecutive interfaces, starting
+    /// at `first_interface`.
+    pub fn new(first_interface: u8, interface_count: u8) -> Self {
+        InterfaceAssociationBuilder {
+            first_interface: first_interface,
+            interface_count: interface_count,
+            class_code: 0,
+            sub_class_code: 0,
+            protocol_code: 0,
+            description_string_index: 0,
+        }
+    }
+
+    /// Sets the function's class code.
+    pub fn class_code(mut self, class_code: u8) -> Self {
+        self.class_code = class_code;
+        self
+    }
+
+    /// Sets the function's sub class code.
+    pub fn sub_class_code(mut self, sub_class_code: u8) -> Self {
+        self.sub_class_code = sub_class_code;
+        self
+    }
+
+    /// Sets the function's protocol code.
+    pub fn protocol_code(mut self, protocol_code: u8) -> Self {
+        self.protocol_code = protocol_code;
+        self
+    }
+
+    /// Sets the index of the string descriptor that describes the function.
+    pub fn description_string_index(mut self, index: u8) -> Self {
+        self.description_string_index = index;
+        self
+    }
+
+    /// Returns the 8 bytes of the interface association descriptor.
+    pub fn build(&self) -> Vec<u8> {
+        vec![
+            0x08,
+            DT_INTERFACE_ASSOCIATION,
+            self.first_interface,
+            self.interface_count,
+            self.class_code,
+            self.sub_class_code,
+            self.protocol_code,
+            self.description_string_index,
+        ]
+    }
+}
+
+
+/// Returns the bytes of a string descriptor that holds `string`.
+///
+/// The string is encoded as UTF-16LE, as required by the USB specification.
+///
+/// ## Panics
+///
+/// Panics if the string is longer than the 126 UTF-16 code units that fit in a descriptor.
+pub fn string_descriptor(string: &str) -> Vec<u8> {
+    let mut body = Vec::new();
+
+    for unit in string.encode_utf16() {
+        body.push(unit as u8);
+        body.push((unit >> 8) as u8);
+    }
+
+    let mut bytes = Vec::with_capacity(body.len() + 2);
+    push_descriptor(&mut bytes, LIBUSB_DT_STRING, &body);
+    bytes
+}
+
+/// Returns the bytes of string descriptor zero, which lists the languages of a device's strings.
+///
+/// ## Panics
+///
+/// Panics if there are more than 126 languages.
+pub fn language_descriptor(languages: &[Language]) -> Vec<u8> {
+    let mut body = Vec::with_capacity(languages.len() * 2);
+
+    for language in languages {
+        body.push(language.lang_id() as u8);
+        body.push((language.lang_id() >> 8) as u8);
+    }
+
+    let mut bytes = Vec::with_capacity(body.len() + 2);
+    push_descriptor(&mut bytes, LIBUSB_DT_STRING, &body);
+    bytes
+}
+
+fn push_descriptor(bytes: &mut Vec<u8>, descriptor_type: u8, body: &[u8]) {
+    let length = body.len() + 2;
+    assert!(length <= 0xFF, "descriptor is {} bytes long, which overflows bLength", length);
+
+    bytes.push(length as u8);
+    bytes.push(descriptor_type);
+    bytes.extend_from_slice(body);
+}
+
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+    use libusb::*;
+    use fields::{Version, Direction, TransferType, SyncType, UsageType};
+    use language;
+    use descriptor_parser::parse_descriptors;
+    use device_handle_sync_api::DeviceHandleSyncApi;
+    use error::Error;
+    use test_helpers::MockDevice;
+    use super::*;
+
+    fn composite() -> DeviceDescriptorBuilder {
+        DeviceDescriptorBuilder::new(0x1209, 0x0001)
+            .usb_version(Version(2, 0, 1))
+            .class_code(0xEF)
+            .sub_class_code(0x02)
+            .protocol_code(0x01)
+            .device_version(Version(1, 2, 3))
+            .manufacturer_string_index(1)
+            .product_string_index(2)
+            .configuration(ConfigDescriptorBuilder::new(1)
+                .remote_wakeup(true)
+                .max_power(100)
+                .association(InterfaceAssociationBuilder::new(0, 2).class_code(0x02).sub_class_code(0x02).protocol_code(0x01))
+                .interface(InterfaceDescriptorBuilder::new(0)
+                    .class_code(0x02)
+                    .sub_class_code(0x02)
+                    .protocol_code(0x01)
+                    .descriptor(0x24, &[0x00, 0x10, 0x01])
+                    .descriptor(0x24, &[0x06, 0x00, 0x01])
+                    .endpoint(EndpointDescriptorBuilder::new(0x82, TransferType::Interrupt).max_packet_size(8).interval(16)))
+                .interface(InterfaceDescriptorBuilder::new(1)
+                    .class_code(0x0A)
+                    .endpoint(EndpointDescriptorBuilder::new(0x01, TransferType::Bulk).max_packet_size(64))
+                    .endpoint(EndpointDescriptorBuilder::new(0x81, TransferType::Bulk).max_packet_size(64))))
+    }
+
+    #[test]
+    fn it_builds_device_descriptor() {
+        assert_eq!(vec![0x12, 0x01, 0x01, 0x02, 0xEF, 0x02, 0x01, 0x40, 0x09, 0x12, 0x01, 0x00, 0x23, 0x01, 0x01, 0x02, 0x00, 0x01],
+                   composite().device_descriptor());
+    }
+
+    #[test]
+    fn it_computes_config_lengths_and_counts() {
+        let builder = composite();
+        let config = &builder.config_descriptors()[0];
+
+        assert_eq!(&[0x09, 0x02, 0x42, 0x00, 0x02, 0x01, 0x00, 0xA0, 0x32], &config[..9]);
+        assert_eq!(0x42, config.len());
+        assert_eq!(builder.build().len(), 18 + config.len());
+    }
+
+    #[test]
+    fn it_counts_alternate_settings_as_one_interface() {
+        let config = ConfigDescriptorBuilder::new(1)
+            .interface(InterfaceDescriptorBuilder::new(0))
+            .interface(InterfaceDescriptorBuilder::new(1))
+            .interface(InterfaceDescriptorBuilder::new(1).setting_number(1))
+            .build();
+
+        assert_eq!(2, config[4]);
+    }
+
+    #[test]
+    fn it_round_trips_through_parser() {
+        let (device, configs) = parse_descriptors(&composite().build()).unwrap();
+
+        assert_eq!(Version(2, 0, 1), device.usb_version());
+        assert_eq!(Version(1, 2, 3), device.device_version());
+        assert_eq!(0x1209, device.vendor_id());
+        assert_eq!(Some(2), device.product_string_index());
+        assert_eq!(None, device.serial_number_string_index());
+
+        let config = &configs[0];
+        assert_eq!(100, config.max_power());
+        assert!(config.remote_wakeup());
+        assert!(!config.self_powered());
+        assert_eq!(vec![(0, 2)], config.interface_associations().iter().map(|a| (a.first_interface(), a.interface_count())).collect::<Vec<_>>());
+
+        let settings: Vec<_> = config.interfaces().map(|i| i.descriptors().next().unwrap()).collect();
+        assert_eq!(&[0x05, 0x24, 0x00, 0x10, 0x01, 0x05, 0x24, 0x06, 0x00, 0x01], settings[0].extra());
+        assert_eq!(0x0A, settings[1].class_code());
+
+        let endpoints: Vec<_> = settings[1].endpoint_descriptors().map(|e| (e.address(), e.direction(), e.transfer_type(), e.max_packet_size())).collect();
+        assert_eq!(vec![(0x01, Direction::Out, TransferType::Bulk, 64), (0x81, Direction::In, TransferType::Bulk, 64)], endpoints);
+    }
+
+    #[test]
+    fn it_round_trips_endpoint_attributes() {
+        let config = ConfigDescriptorBuilder::new(1)
+            .interface(InterfaceDescriptorBuilder::new(0)
+                .endpoint(EndpointDescriptorBuilder::new(0x83, TransferType::Isochronous)
+                    .sync_type(SyncType::Adaptive)
+                    .usage_type(UsageType::FeedbackData)
+                    .max_packet_size(0x1400)
+                    .interval(1)
+                    .ss_companion(2, 0x01, 3072)))
+            .build();
+
+        let mut bytes = DeviceDescriptorBuilder::new(0, 0).device_descriptor();
+        bytes[17] = 1;
+        bytes.extend_from_slice(&config);
+
+        let (_, configs) = parse_descriptors(&bytes).unwrap();
+        let setting = configs[0].interfaces().next().unwrap().descriptors().next().unwrap();
+        let endpoint = setting.endpoint_descriptors().next().unwrap();
+
+        assert_eq!(SyncType::Adaptive, endpoint.sync_type());
+        assert_eq!(UsageType::FeedbackData, endpoint.usage_type());
+        assert_eq!(0x1400, endpoint.max_packet_size());
+        assert_eq!(3072, endpoint.ss_companion().unwrap().bytes_per_interval());
+        assert_eq!(1, endpoint.ss_companion().unwrap().mult());
+    }
+
+    #[test]
+    fn it_rounds_max_power_up() {
+        assert_eq!(50, ConfigDescriptorBuilder::new(1).max_power(99).build()[8]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn it_rejects_oversized_descriptors() {
+        ConfigDescriptorBuilder::new(1).descriptor(0x24, &[0; 254]);
+    }
+
+    #[test]
+    fn it_counts_up_to_255_endpoints() {
+        let interface = (0..255).fold(InterfaceDescriptorBuilder::new(0), |interface, _| {
+            interface.endpoint(EndpointDescriptorBuilder::new(0x81, TransferType::Bulk))
+        });
+
+        assert_eq!(255, interface.build()[4]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn it_rejects_more_endpoints_than_an_interface_can_count() {
+        (0..256).fold(InterfaceDescriptorBuilder::new(0), |interface, _| {
+            interface.endpoint(EndpointDescriptorBuilder::new(0x81, TransferType::Bulk))
+        });
+    }
+
+    #[test]
+    fn it_builds_strings_that_devices_can_serve() {
+        let device = MockDevice::new(|setup, _| {
+            match setup.value() as u8 {
+                0 => Ok(language_descriptor(&[language::from_lang_id(0x0409), language::from_lang_id(0x0407)])),
+                1 => Ok(string_descriptor("Gr\u{fc}\u{df}e \u{1F600}")),
+                _ => Err(Error::Pipe),
+            }
+        });
+
+        let timeout = Duration::from_secs(1);
+        let languages = device.read_languages(timeout).unwrap();
+
+        assert_eq!(vec![0x0409, 0x0407], languages.iter().map(|l| l.lang_id()).collect::<Vec<_>>());
+        assert_eq!("Gr\u{fc}\u{df}e \u{1F600}", device.read_string_descriptor(languages[0], 1, timeout).unwrap());
+    }
+
+    #[test]
+    fn it_builds_string_descriptor_header() {
+        assert_eq!(vec![0x06, LIBUSB_DT_STRING, 0x41, 0x00, 0x42, 0x00], string_descriptor("AB"));
+        assert_eq!(vec![0x02, LIBUSB_DT_STRING], string_descriptor(""));
+    }
+}
@@ -0,0 +1,627 @@
+use std::fmt;
+
+use libusb::*;
+
+use class_code::{Class, ClassCode};
+use fields::{self, Speed, Direction, TransferType, SyncType, UsageType, Version};
+use device_info::{DeviceInfo, ConfigInfo, AltSettingInfo, EndpointInfo};
+use bos_descriptor::{BosDescriptor, DeviceCapability, AlternateModeState};
+use endpoint_companion::SuperSpeedEndpointCompanion;
+use extra_descriptors::ExtraDescriptors;
+use descriptor_parser::{DT_INTERFACE_ASSOCIATION, DT_HID, DT_CS_INTERFACE, DT_SSP_ISOCHRONOUS_ENDPOINT_COMPANION};
+
+
+/// Column at which the values of a dump are aligned, relative to the indentation of their line.
+const VALUE_COLUMN: usize = 25;
+
+/// Formats a [`DeviceInfo`](struct.DeviceInfo.html) snapshot as a descriptor dump.
+///
+/// `DescriptorDump` implements `Display` to produce text in the style of `lsusb -v`: every
+/// descriptor field with its decoded attributes, class names and strings, followed by the
+/// class-specific descriptors of each configuration, interface and endpoint, and the device's BOS
+/// descriptor. With the `serde` feature, the `DeviceInfo` itself can be serialized to get the same
+/// information in a machine-readable form.
+///
+/// ```no_run
+/// use std::time::Duration;
+/// use libusb::{DeviceInfo, DescriptorDump};
+///
+/// let context = libusb::io::sync::Context::new().unwrap();
+///
+/// for device in context.devices().unwrap().iter() {
+///     let handle = device.open().ok();
+///     let info = DeviceInfo::from_device(&device, handle.as_ref(), Duration::from_secs(1)).unwrap();
+///
+///     println!("{}", DescriptorDump::new(&info));
+/// }
+/// ```
+#[derive(Debug,Clone,Copy)]
+pub struct DescriptorDump<'a> {
+    info: &'a DeviceInfo,
+}
+
+impl<'a> DescriptorDump<'a> {
+    /// Creates a dump of a device snapshot.
+    pub fn new(info: &'a DeviceInfo) -> Self {
+        DescriptorDump { info: info }
+    }
+}
+
+impl<'a> fmt::Display for DescriptorDump<'a> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        let info = self.info;
+
+        if let (Some(bus_number), Some(address)) = (info.bus_number(), info.address()) {
+            write!(fmt, "Bus {:03} Device {:03}: ", bus_number, address)?;
+        }
+
+        write!(fmt, "ID {:04x}:{:04x}", info.vendor_id(), info.product_id())?;
+
+        for name in info.manufacturer().iter().chain(info.product().iter()) {
+            write!(fmt, " {}", name)?;
+        }
+
+        writeln!(fmt, "")?;
+        writeln!(fmt, "Device Descriptor:")?;
+        field(fmt, 2, "bcdUSB", version(info.usb_version()), "")?;
+        field(fmt, 2, "bDeviceClass", info.class_code(), class_name(info.class()))?;
+        field(fmt, 2, "bDeviceSubClass", info.sub_class_code(), sub_class_name(info.class()))?;
+        field(fmt, 2, "bDeviceProtocol", info.protocol_code(), protocol_name(info.class()))?;
+        field(fmt, 2, "bMaxPacketSize0", info.max_packet_size(), "")?;
+        field(fmt, 2, "idVendor", format!("{:#06x}", info.vendor_id()), "")?;
+        field(fmt, 2, "idProduct", format!("{:#06x}", info.product_id()), "")?;
+        field(fmt, 2, "bcdDevice", version(info.device_version()), "")?;
+        field(fmt, 2, "iManufacturer", info.manufacturer_string_index().unwrap_or(0), info.manufacturer().unwrap_or(""))?;
+        field(fmt, 2, "iProduct", info.product_string_index().unwrap_or(0), info.product().unwrap_or(""))?;
+        field(fmt, 2, "iSerial", info.serial_number_string_index().unwrap_or(0), info.serial_number().unwrap_or(""))?;
+        field(fmt, 2, "bNumConfigurations", info.configurations().len(), "")?;
+
+        for config in info.configurations() {
+            write_config(fmt, config)?;
+        }
+
+        if let Some(bos) = info.bos() {
+            write_bos(fmt, bos)?;
+        }
+
+        Ok(())
+    }
+}
+
+
+/// Writes a line with a value that's right-aligned to `VALUE_COLUMN`, followed by a note.
+fn field<V: fmt::Display>(fmt: &mut fmt::Formatter, indent: usize, label: &str, value: V, note: &str) -> fmt::Result {
+    let value = value.to_string();
+    let width = VALUE_COLUMN.saturating_sub(label.len()).max(value.len() + 1);
+
+    write!(fmt, "{:indent$}{}{:>width$}", "", label, value, indent = indent, width = width)?;
+
+    if note.is_empty() {
+        writeln!(fmt, "")
+    } else {
+        writeln!(fmt, " {}", note)
+    }
+}
+
+/// Writes a line with text that starts at `VALUE_COLUMN`.
+fn text<V: fmt::Display>(fmt: &mut fmt::Formatter, indent: usize, label: &str, value: V) -> fmt::Result {
+    let width = VALUE_COLUMN.max(label.len() + 1);
+
+    writeln!(fmt, "{:indent$}{:width$}{}", "", label, value, indent = indent, width = width)
+}
+
+/// Writes a line without a value.
+fn heading(fmt: &mut fmt::Formatter, indent: usize, heading: &str) -> fmt::Result {
+    writeln!(fmt, "{:indent$}{}", "", heading, indent = indent)
+}
+
+fn unrecognized(fmt: &mut fmt::Formatter, indent: usize, bytes: &[u8]) -> fmt::Result {
+    write!(fmt, "{:indent$}** UNRECOGNIZED: ", "", indent = indent)?;
+
+    for byte in bytes {
+        write!(fmt, " {:02x}", byte)?;
+    }
+
+    writeln!(fmt, "")
+}
+
+fn write_config(fmt: &mut fmt::Formatter, config: &ConfigInfo) -> fmt::Result {
+    heading(fmt, 2, "Configuration Descriptor:")?;
+    field(fmt, 4, "bNumInterfaces", config.interfaces().len(), "")?;
+    field(fmt, 4, "bConfigurationValue", config.number(), "")?;
+    field(fmt, 4, "iConfiguration", config.description_string_index().unwrap_or(0), config.description().unwrap_or(""))?;
+    field(fmt, 4, "bmAttributes", format!("{:#04x}", config_attributes(config)), "")?;
+
+    if config.self_powered() {
+        heading(fmt, 6, "Self Powered")?;
+    } else {
+        heading(fmt, 6, "(Bus Powered)")?;
+    }
+
+    if config.remote_wakeup() {
+        heading(fmt, 6, "Remote Wakeup")?;
+    }
+
+    writeln!(fmt, "    MaxPower{:>17}mA", config.max_power())?;
+    write_extra(fmt, 4, config.extra(), None)?;
+
+    for interface in config.interfaces() {
+        for setting in interface.settings() {
+            write_setting(fmt, setting)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn write_setting(fmt: &mut fmt::Formatter, setting: &AltSettingInfo) -> fmt::Result {
+    heading(fmt, 4, "Interface Descriptor:")?;
+    field(fmt, 6, "bInterfaceNumber", setting.interface_number(), "")?;
+    field(fmt, 6, "bAlternateSetting", setting.setting_number(), "")?;
+    field(fmt, 6, "bNumEndpoints", setting.endpoints().len(), "")?;
+    field(fmt, 6, "bInterfaceClass", setting.class_code(), class_name(setting.class()))?;
+    field(fmt, 6, "bInterfaceSubClass", setting.sub_class_code(), sub_class_name(setting.class()))?;
+    field(fmt, 6, "bInterfaceProtocol", setting.protocol_code(), protocol_name(setting.class()))?;
+    field(fmt, 6, "iInterface", setting.description_string_index().unwrap_or(0), setting.description().unwrap_or(""))?;
+    write_extra(fmt, 6, setting.extra(), Some(ClassCode::from_code(setting.class_code())))?;
+
+    for endpoint in setting.endpoints() {
+        write_endpoint(fmt, endpoint)?;
+    }
+
+    Ok(())
+}
+
+fn write_endpoint(fmt: &mut fmt::Formatter, endpoint: &EndpointInfo) -> fmt::Result {
+    let direction = match endpoint.direction() {
+        Direction::In => "IN",
+        Direction::Out => "OUT",
+    };
+
+    let packet_size = endpoint.max_packet_size();
+
+    heading(fmt, 6, "Endpoint Descriptor:")?;
+    field(fmt, 8, "bEndpointAddress", format!("{:#04x}", endpoint.address()), &format!(" EP {} {}", endpoint.number(), direction))?;
+    field(fmt, 8, "bmAttributes", endpoint_attributes(endpoint), "")?;
+    text(fmt, 10, "Transfer Type", capitalized(fields::transfer_type_name(endpoint.transfer_type())))?;
+    text(fmt, 10, "Synch Type", sync_type_name(endpoint.sync_type()))?;
+    text(fmt, 10, "Usage Type", usage_type_name(endpoint.usage_type()))?;
+    field(fmt, 8, "wMaxPacketSize", format!("{:#06x}", packet_size),
+          &format!(" {}x {} bytes", (packet_size >> 11 & 0x03) + 1, packet_size & 0x7FF))?;
+    field(fmt, 8, "bInterval", endpoint.interval(), "")?;
+
+    if let Some(companion) = endpoint.ss_companion() {
+        write_companion(fmt, endpoint.transfer_type(), &companion)?;
+    }
+
+    write_extra(fmt, 8, endpoint.extra(), None)
+}
+
+fn write_companion(fmt: &mut fmt::Formatter, transfer_type: TransferType, companion: &SuperSpeedEndpointCompanion) -> fmt::Result {
+    field(fmt, 8, "bMaxBurst", companion.max_burst(), "")?;
+
+    match transfer_type {
+        TransferType::Bulk if companion.max_streams() > 0 => {
+            field(fmt, 8, "MaxStreams", companion.max_streams(), "")?;
+        },
+        TransferType::Isochronous => {
+            field(fmt, 8, "Mult", companion.mult() + 1, "")?;
+        },
+        _ => {},
+    }
+
+    match transfer_type {
+        TransferType::Isochronous | TransferType::Interrupt => {
+            field(fmt, 8, "wBytesPerInterval", companion.bytes_per_interval(), "")
+        },
+        _ => Ok(()),
+    }
+}
+
+/// Writes the class-specific and vendor-specific descriptors in a buffer of extra descriptors.
+///
+/// `class_code` is the class of the interface that the descriptors belong to, if any.
+fn write_extra(fmt: &mut fmt::Formatter, indent: usize, extra: &[u8], class_code: Option<ClassCode>) -> fmt::Result {
+    let mut descriptors = ExtraDescriptors::new(extra);
+
+    for descriptor in descriptors.by_ref() {
+        let bytes = descriptor.as_bytes();
+
+        match (descriptor.descriptor_type(), bytes.len()) {
+            (LIBUSB_DT_SS_ENDPOINT_COMPANION, _) | (DT_SSP_ISOCHRONOUS_ENDPOINT_COMPANION, _) => {},
+            (DT_INTERFACE_ASSOCIATION, length) if length >= 8 => write_association(fmt, indent, bytes)?,
+            (DT_HID, length) if length >= 6 => write_hid(fmt, indent, bytes)?,
+            (DT_CS_INTERFACE, length) if length >= 3 && class_code == Some(ClassCode::Communications) => write_cdc(fmt, indent, bytes)?,
+            _ => unrecognized(fmt, indent, bytes)?,
+        }
+    }
+
+    if !descriptors.remainder().is_empty() {
+        unrecognized(fmt, indent, descriptors.remainder())?;
+    }
+
+    Ok(())
+}
+
+fn write_association(fmt: &mut fmt::Formatter, indent: usize, bytes: &[u8]) -> fmt::Result {
+    heading(fmt, indent, "Interface Association:")?;
+    field(fmt, indent + 2, "bFirstInterface", bytes[2], "")?;
+    field(fmt, indent + 2, "bInterfaceCount", bytes[3], "")?;
+    let class = Class::new(bytes[4], bytes[5], bytes[6]);
+
+    field(fmt, indent + 2, "bFunctionClass", bytes[4], class_name(class))?;
+    field(fmt, indent + 2, "bFunctionSubClass", bytes[5], sub_class_name(class))?;
+    field(fmt, indent + 2, "bFunctionProtocol", bytes[6], protocol_name(class))?;
+    field(fmt, indent + 2, "iFunction", bytes[7], "")
+}
+
+fn write_hid(fmt: &mut fmt::Formatter, indent: usize, bytes: &[u8]) -> fmt::Result {
+    heading(fmt, indent, "HID Device Descriptor:")?;
+    field(fmt, indent + 2, "bcdHID", version(Version::from_bcd(bytes[2] as u16 | (bytes[3] as u16) << 8)), "")?;
+    field(fmt, indent + 2, "bCountryCode", bytes[4], "")?;
+    field(fmt, indent + 2, "bNumDescriptors", bytes[5], "")?;
+
+    for class_descriptor in bytes[6..].chunks(3).filter(|d| d.len() == 3) {
+        let name = match class_descriptor[0] {
+            0x22 => "Report",
+            0x23 => "Physical",
+            _ => "",
+        };
+
+        field(fmt, indent + 2, "bDescriptorType", class_descriptor[0], name)?;
+        field(fmt, indent + 2, "wDescriptorLength", class_descriptor[1] as u16 | (class_descriptor[2] as u16) << 8, "")?;
+    }
+
+    Ok(())
+}
+
+fn write_cdc(fmt: &mut fmt::Formatter, indent: usize, bytes: &[u8]) -> fmt::Result {
+    match (bytes[2], bytes.len()) {
+        (0x00, length) if length >= 5 => {
+            heading(fmt, indent, "CDC Header:")?;
+            field(fmt, indent + 2, "bcdCDC", version(Version::from_bcd(bytes[3] as u16 | (bytes[4] as u16) << 8)), "")
+        },
+        (0x01, length) if length >= 5 => {
+            heading(fmt, indent, "CDC Call Management:")?;
+            field(fmt, indent + 2, "bmCapabilities", format!("{:#04x}", bytes[3]), "")?;
+            field(fmt, indent + 2, "bDataInterface", bytes[4], "")
+        },
+        (0x02, length) if length >= 4 => {
+            heading(fmt, indent, "CDC ACM:")?;
+            field(fmt, indent + 2, "bmCapabilities", format!("{:#04x}", bytes[3]), "")
+        },
+        (0x06, length) if length >= 4 => {
+            heading(fmt, indent, "CDC Union:")?;
+            field(fmt, indent + 2, "bMasterInterface", bytes[3], "")?;
+
+            for interface in &bytes[4..] {
+                field(fmt, indent + 2, "bSlaveInterface", interface, "")?;
+            }
+
+            Ok(())
+        },
+        _ => unrecognized(fmt, indent, bytes),
+    }
+}
+
+fn write_bos(fmt: &mut fmt::Formatter, bos: &BosDescriptor) -> fmt::Result {
+    heading(fmt, 0, "Binary Object Store Descriptor:")?;
+    field(fmt, 2, "bNumDeviceCaps", bos.num_capabilities(), "")?;
+
+    for capability in bos.capabilities() {
+        match *capability {
+            DeviceCapability::Usb2Extension(ref c) => {
+                heading(fmt, 2, "USB 2.0 Extension Device Capability:")?;
+                field(fmt, 4, "bmAttributes", format!("{:#010x}", c.attributes()), "")?;
+
+                if c.lpm_supported() {
+                    heading(fmt, 6, "Link Power Management (LPM) Supported")?;
+                }
+
+                if c.besl_supported() {
+                    heading(fmt, 6, "BESL Link Power Management (LPM) Supported")?;
+                }
+
+                if let Some(besl) = c.baseline_besl() {
+                    field(fmt, 6, "Baseline BESL value", besl, "")?;
+                }
+
+                if let Some(besl) = c.deep_besl() {
+                    field(fmt, 6, "Deep BESL value", besl, "")?;
+                }
+            },
+            DeviceCapability::SuperSpeed(ref c) => {
+                heading(fmt, 2, "SuperSpeed USB Device Capability:")?;
+                field(fmt, 4, "bmAttributes", format!("{:#04x}", c.ltm_capable() as u8 * 0x02), "")?;
+
+                if c.ltm_capable() {
+                    heading(fmt, 6, "Latency Tolerance Messages (LTM) Supported")?;
+                }
+
+                field(fmt, 4, "wSpeedsSupported", format!("{:#06x}", c.speeds_supported()), "")?;
+
+                for &speed in &[Speed::Low, Speed::Full, Speed::High, Speed::Super] {
+                    if c.supports_speed(speed) {
+                        heading(fmt, 6, &format!("Device can operate at {}", fields::speed_name(speed)))?;
+                    }
+                }
+
+                field(fmt, 4, "bFunctionalitySupport", speed_index(c.functionality_support()), "")?;
+                heading(fmt, 6, &format!("Lowest fully-functional device speed is {}", fields::speed_name(c.functionality_support())))?;
+                field(fmt, 4, "bU1DevExitLat", c.u1_exit_latency(), "micro seconds")?;
+                field(fmt, 4, "bU2DevExitLat", c.u2_exit_latency(), "micro seconds")?;
+            },
+            DeviceCapability::SuperSpeedPlus(ref c) => {
+                heading(fmt, 2, "SuperSpeedPlus USB Device Capability:")?;
+                field(fmt, 4, "bmAttributes", format!("{:#010x}", c.attributes()), "")?;
+                text(fmt, 6, "Sublink Speed Attribute count", c.sublink_speeds().len())?;
+                text(fmt, 6, "Sublink Speed ID count", c.num_sublink_speed_ids())?;
+                field(fmt, 4, "ssid", c.min_sublink_speed_id(), "")?;
+                field(fmt, 4, "Min Rx Lane count", c.min_rx_lanes(), "")?;
+                field(fmt, 4, "Min Tx Lane count", c.min_tx_lanes(), "")?;
+
+                for (i, speed) in c.sublink_speeds().iter().enumerate() {
+                    field(fmt, 4, &format!("bmSublinkSpeedAttr[{}]", i), format!("{:#010x}", speed.attributes()), "")?;
+                    heading(fmt, 6, &format!("Speed Attribute ID: {} {} Mb/s {} {} {}",
+                                             speed.id(),
+                                             speed.lane_speed() / 1_000_000,
+                                             if speed.symmetric() { "Symmetric" } else { "Asymmetric" },
+                                             if speed.transmit() { "TX" } else { "RX" },
+                                             if speed.protocol() == 0 { "SuperSpeed" } else { "SuperSpeedPlus" }))?;
+                }
+            },
+            DeviceCapability::ContainerId(uuid) => {
+                heading(fmt, 2, "Container ID Device Capability:")?;
+                text(fmt, 4, "ContainerID", format!("{{{}}}", uuid))?;
+            },
+            DeviceCapability::Platform(ref c) => {
+                heading(fmt, 2, "Platform Device Capability:")?;
+                text(fmt, 4, "PlatformCapabilityUUID", format!("{{{}}}", c.uuid()))?;
+
+                for (i, byte) in c.data().iter().enumerate() {
+                    field(fmt, 4, &format!("CapabilityData[{}]", i), format!("{:#04x}", byte), "")?;
+                }
+            },
+            DeviceCapability::Billboard(ref c) => {
+                heading(fmt, 2, "Billboard Capability:")?;
+                field(fmt, 4, "iAdditionalInfoURL", c.additional_info_url_string_index().unwrap_or(0), "")?;
+                field(fmt, 4, "bNumberOfAlternateModes", c.modes().len(), "")?;
+                field(fmt, 4, "bPreferredAlternateMode", c.preferred_mode(), "")?;
+                field(fmt, 4, "VCONN Power", format!("{:#06x}", c.vconn_power()), "")?;
+                field(fmt, 4, "bcdVersion", version(c.version()), "")?;
+                field(fmt, 4, "bAdditionalFailureInfo", c.additional_failure_info(), "")?;
+
+                for (i, mode) in c.modes().iter().enumerate() {
+                    heading(fmt, 4, &format!("Alternate Mode {} : {}", i, alternate_mode_state_name(mode.state())))?;
+                    field(fmt, 6, &format!("wSVID[{}]", i), format!("{:#06x}", mode.svid()), "")?;
+                    field(fmt, 6, &format!("bAlternateMode[{}]", i), mode.mode(), "")?;
+                    field(fmt, 6, &format!("iAlternateModeString[{}]", i), mode.description_string_index().unwrap_or(0), "")?;
+                }
+            },
+            DeviceCapability::Unknown(ref c) => {
+                unrecognized(fmt, 2, c.as_bytes())?;
+            },
+        }
+    }
+
+    Ok(())
+}
+
+
+fn config_attributes(config: &ConfigInfo) -> u8 {
+    0x80 | (config.self_powered() as u8) << 6 | (config.remote_wakeup() as u8) << 5
+}
+
+fn endpoint_attributes(endpoint: &EndpointInfo) -> u8 {
+    fields::endpoint_attributes(endpoint.transfer_type(), endpoint.sync_type(), endpoint.usage_type())
+}
+
+fn version(version: Version) -> String {
+    format!("{}.{}{}", version.major(), version.minor(), version.sub_minor())
+}
+
+fn class_name(class: Class) -> &'static str {
+    class.class_code().name().unwrap_or("")
+}
+
+fn sub_class_name(class: Class) -> &'static str {
+    class.sub_class_name().unwrap_or("")
+}
+
+fn protocol_name(class: Class) -> &'static str {
+    class.protocol_name().unwrap_or("")
+}
+
+/// Capitalizes the first letter of a name, the way `lsusb` writes values.
+fn capitalized(name: &str) -> String {
+    let mut chars = name.chars();
+
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+fn sync_type_name(sync_type: SyncType) -> &'static str {
+    match sync_type {
+        SyncType::NoSync => "None",
+        SyncType::Asynchronous => "Asynchronous",
+        SyncType::Adaptive => "Adaptive",
+        SyncType::Synchronous => "Synchronous",
+    }
+}
+
+fn usage_type_name(usage_type: UsageType) -> &'static str {
+    match usage_type {
+        UsageType::Data => "Data",
+        UsageType::Feedback => "Feedback",
+        UsageType::FeedbackData => "Implicit feedback Data",
+        UsageType::Reserved => "Reserved",
+    }
+}
+
+/// Returns the index of a speed in `wSpeedsSupported` and `bFunctionalitySupport`.
+fn speed_index(speed: Speed) -> u8 {
+    match speed {
+        Speed::Low => 0,
+        Speed::Full => 1,
+        Speed::High => 2,
+        Speed::Super | Speed::Unknown => 3,
+    }
+}
+
+fn alternate_mode_state_name(state: AlternateModeState) -> &'static str {
+    match state {
+        AlternateModeState::Error => "Unspecified Error",
+        AlternateModeState::NotAttempted => "Alternate Mode configuration not attempted",
+        AlternateModeState::Unsuccessful => "Alternate Mode configuration attempted but unsuccessful",
+        AlternateModeState::Successful => "Alternate Mode configuration successful",
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+    use libusb::*;
+    use error::Error;
+    use descriptor_parser::parse_descriptors;
+    use device_info::DeviceInfo;
+    use descriptor_builder::string_descriptor;
+    use test_helpers::MockDevice;
+    use super::DescriptorDump;
+
+    const COMPOSITE: &'static [u8] = &[
+        0x12, 0x01, 0x01, 0x02, 0xEF, 0x02, 0x01, 0x40, 0x09, 0x12, 0x01, 0x00, 0x00, 0x01, 0x01, 0x02, 0x00, 0x01,
+        0x09, 0x02, 0x64, 0x00, 0x03, 0x01, 0x00, 0xA0, 0x32,
+        0x08, 0x0B, 0x00, 0x02, 0x02, 0x02, 0x01, 0x00,
+        0x09, 0x04, 0x00, 0x00, 0x01, 0x02, 0x02, 0x01, 0x00,
+        0x05, 0x24, 0x00, 0x10, 0x01,
+        0x05, 0x24, 0x01, 0x00, 0x01,
+        0x04, 0x24, 0x02, 0x02,
+        0x05, 0x24, 0x06, 0x00, 0x01,
+        0x07, 0x05, 0x82, 0x03, 0x08, 0x00, 0x10,
+        0x09, 0x04, 0x01, 0x00, 0x02, 0x0A, 0x00, 0x00, 0x00,
+        0x07, 0x05, 0x01, 0x02, 0x40, 0x00, 0x00,
+        0x07, 0x05, 0x81, 0x02, 0x40, 0x00, 0x00,
+        0x09, 0x04, 0x02, 0x00, 0x01, 0x03, 0x00, 0x00, 0x00,
+        0x09, 0x21, 0x11, 0x01, 0x00, 0x01, 0x22, 0x3F, 0x00,
+        0x07, 0x05, 0x83, 0x03, 0x40, 0x00, 0x0A,
+    ];
+
+    const VENDOR: &'static [u8] = &[
+        0x12, 0x01, 0x00, 0x02, 0xFF, 0x00, 0x00, 0x40, 0x34, 0x12, 0x78, 0x56, 0x00, 0x01, 0x00, 0x00, 0x00, 0x01,
+        0x09, 0x02, 0x1D, 0x00, 0x01, 0x01, 0x00, 0xC0, 0x00,
+        0x09, 0x04, 0x00, 0x00, 0x01, 0xFF, 0x00, 0x00, 0x00,
+        0x04, 0x42, 0x01, 0x02,
+        0x07, 0x05, 0x81, 0x02, 0x00, 0x02, 0x00,
+    ];
+
+    const BOS: &'static [u8] = &[
+        0x05, 0x0F, 0x0C, 0x00, 0x01,
+        0x07, 0x10, 0x02, 0x02, 0x00, 0x00, 0x00,
+    ];
+
+    fn snapshot(descriptors: &[u8]) -> DeviceInfo {
+        let (device, configs) = parse_descriptors(descriptors).unwrap();
+        DeviceInfo::from_descriptors(&device, &configs)
+    }
+
+    fn composite() -> DeviceInfo {
+        let handle = MockDevice::new(|setup, _| {
+            match (setup.value() >> 8, setup.value() as u8) {
+                (0x03, 0) => Ok(vec![0x04, LIBUSB_DT_STRING, 0x09, 0x04]),
+                (0x03, 1) => Ok(string_descriptor("Maker")),
+                (0x03, 2) => Ok(string_descriptor("Gadget")),
+                (0x0F, 0) => Ok(BOS.to_vec()),
+                _ => Err(Error::Pipe),
+            }
+        });
+
+        let mut info = snapshot(COMPOSITE);
+        info.read_strings(&handle, Duration::from_secs(1)).unwrap();
+        info.read_bos(&handle, Duration::from_secs(1)).unwrap();
+        info
+    }
+
+    #[test]
+    fn it_writes_device_descriptor() {
+        let dump = DescriptorDump::new(&composite()).to_string();
+
+        assert!(dump.starts_with("ID 1209:0001 Maker Gadget\nDevice Descriptor:\n"));
+        assert!(dump.contains("\n  bcdUSB               2.01\n"));
+        assert!(dump.contains("\n  bDeviceClass          239 Miscellaneous Device\n"));
+        assert!(dump.contains("\n  idVendor           0x1209\n"));
+        assert!(dump.contains("\n  iManufacturer           1 Maker\n"));
+        assert!(dump.contains("\n  iSerial                 0\n"));
+    }
+
+    #[test]
+    fn it_writes_configuration_attributes() {
+        let dump = DescriptorDump::new(&composite()).to_string();
+
+        assert!(dump.contains(concat!(
+            "    bmAttributes         0xa0\n",
+            "      (Bus Powered)\n",
+            "      Remote Wakeup\n",
+            "    MaxPower              100mA\n",
+        )));
+    }
+
+    #[test]
+    fn it_writes_endpoint_attributes() {
+        let dump = DescriptorDump::new(&composite()).to_string();
+
+        assert!(dump.contains(concat!(
+            "      Endpoint Descriptor:\n",
+            "        bEndpointAddress     0x82  EP 2 IN\n",
+            "        bmAttributes            3\n",
+            "          Transfer Type            Interrupt\n",
+            "          Synch Type               None\n",
+            "          Usage Type               Data\n",
+            "        wMaxPacketSize     0x0008  1x 8 bytes\n",
+            "        bInterval              16\n",
+        )));
+    }
+
+    #[test]
+    fn it_decodes_class_specific_descriptors() {
+        let dump = DescriptorDump::new(&composite()).to_string();
+
+        assert!(dump.contains("    Interface Association:\n      bFirstInterface         0\n      bInterfaceCount         2\n"));
+        assert!(dump.contains("      CDC Header:\n        bcdCDC               1.10\n"));
+        assert!(dump.contains("      CDC Union:\n        bMasterInterface        0\n        bSlaveInterface         1\n"));
+        assert!(dump.contains("      HID Device Descriptor:\n        bcdHID               1.11\n"));
+        assert!(dump.contains("        bDescriptorType        34 Report\n        wDescriptorLength      63\n"));
+        assert!(!dump.contains("UNRECOGNIZED"));
+    }
+
+    #[test]
+    fn it_names_sub_classes_and_protocols() {
+        let dump = DescriptorDump::new(&composite()).to_string();
+
+        assert!(dump.contains("\n  bDeviceSubClass         2 Common Class\n  bDeviceProtocol         1 Interface Association\n"));
+        assert!(dump.contains("\n      bFunctionSubClass       2 Abstract (modem)\n      bFunctionProtocol       1 AT-commands (v.25ter)\n"));
+        assert!(dump.contains("\n      bInterfaceClass         3 Human Interface Device\n      bInterfaceSubClass      0 No Subclass\n      bInterfaceProtocol      0 None\n"));
+    }
+
+    #[test]
+    fn it_writes_unrecognized_descriptors_as_bytes() {
+        let dump = DescriptorDump::new(&snapshot(VENDOR)).to_string();
+
+        assert!(dump.contains("\n      ** UNRECOGNIZED:  04 42 01 02\n"));
+    }
+
+    #[test]
+    fn it_writes_bos_descriptor() {
+        let dump = DescriptorDump::new(&composite()).to_string();
+
+        assert!(dump.ends_with(concat!(
+            "Binary Object Store Descriptor:\n",
+            "  bNumDeviceCaps          1\n",
+            "  USB 2.0 Extension Device Capability:\n",
+            "    bmAttributes   0x00000002\n",
+            "      Link Power Management (LPM) Supported\n",
+        )));
+    }
+}
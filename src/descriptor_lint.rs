@@ -0,0 +1,943 @@
+use std::fmt;
+
+use libusb::*;
+
+use device_descriptor::DeviceDescriptor;
+use config_descriptor::ConfigDescriptor;
+use endpoint_descriptor::EndpointDescriptor;
+use descriptor_parser::{self, read_u16, DT_INTERFACE_ASSOCIATION, DT_SSP_ISOCHRONOUS_ENDPOINT_COMPANION};
+use class_code::ClassCode;
+use fields::{Speed, TransferType, speed_name, transfer_type_name};
+
+
+/// How serious a finding is.
+#[derive(Debug,PartialEq,Eq,PartialOrd,Ord,Clone,Copy,Hash)]
+pub enum Severity {
+    /// The descriptors are legal, but hosts or users may handle the device poorly.
+    Warning,
+
+    /// The descriptors violate the specification. Hosts may reject the device or fail to use it.
+    Error,
+}
+
+/// The kind of problem that a finding describes.
+#[derive(Debug,PartialEq,Eq,Clone,Copy,Hash)]
+pub enum FindingKind {
+    /// The descriptors can't be parsed.
+    Malformed,
+
+    /// A descriptor's `bLength` is wrong for its type.
+    Length,
+
+    /// `bNumConfigurations`, `bNumInterfaces` or `bNumEndpoints` doesn't match the descriptors
+    /// that follow.
+    Count,
+
+    /// Interfaces aren't numbered consecutively from zero, or an interface has no alternate
+    /// setting zero.
+    InterfaceNumber,
+
+    /// An endpoint descriptor describes endpoint zero or sets reserved address bits.
+    EndpointAddress,
+
+    /// An endpoint address is used by more than one endpoint that can be active at the same time.
+    DuplicateEndpoint,
+
+    /// The endpoint's transfer type isn't allowed at the device's speed.
+    TransferType,
+
+    /// `wMaxPacketSize` or `bMaxPacketSize0` isn't allowed for the transfer type at the device's
+    /// speed.
+    MaxPacketSize,
+
+    /// `bInterval` is out of range for the transfer type at the device's speed.
+    Interval,
+
+    /// An endpoint of a SuperSpeed device has no SuperSpeed endpoint companion descriptor.
+    MissingCompanion,
+
+    /// A string descriptor that hosts or users rely on has no index.
+    MissingString,
+}
+
+/// The descriptor that a finding refers to.
+#[derive(Debug,PartialEq,Eq,Clone,Copy,Hash)]
+pub enum FindingLocation {
+    /// The descriptor that starts at a byte offset in raw descriptors.
+    Offset(usize),
+
+    /// The device descriptor.
+    Device,
+
+    /// A configuration descriptor.
+    Configuration { config: u8 },
+
+    /// All alternate settings of an interface.
+    Interface { config: u8, interface: u8 },
+
+    /// An alternate setting of an interface.
+    AlternateSetting { config: u8, interface: u8, setting: u8 },
+
+    /// An endpoint descriptor.
+    Endpoint { config: u8, interface: u8, setting: u8, address: u8 },
+}
+
+/// A problem found in a device's descriptors.
+#[derive(Debug,PartialEq,Eq,Clone)]
+pub struct Finding {
+    severity: Severity,
+    kind: FindingKind,
+    location: FindingLocation,
+    message: String,
+    spec_reference: &'static str,
+}
+
+impl Finding {
+    fn error(kind: FindingKind, location: FindingLocation, spec_reference: &'static str, message: String) -> Self {
+        Finding {
+            severity: Severity::Error,
+            kind: kind,
+            location: location,
+            message: message,
+            spec_reference: spec_reference,
+        }
+    }
+
+    fn warning(kind: FindingKind, location: FindingLocation, spec_reference: &'static str, message: String) -> Self {
+        Finding {
+            severity: Severity::Warning,
+            ..Finding::error(kind, location, spec_reference, message)
+        }
+    }
+
+    /// Returns the finding's severity.
+    pub fn severity(&self) -> Severity {
+        self.severity
+    }
+
+    /// Returns the kind of problem that was found.
+    pub fn kind(&self) -> FindingKind {
+        self.kind
+    }
+
+    /// Returns the descriptor that the finding refers to.
+    pub fn location(&self) -> FindingLocation {
+        self.location
+    }
+
+    /// Returns a description of the problem.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Returns the section of the specification that the descriptors violate, such as
+    /// `"USB 2.0 §9.6.6"`.
+    pub fn spec_reference(&self) -> &'static str {
+        self.spec_reference
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Severity::Warning => f.write_str("warning"),
+            Severity::Error   => f.write_str("error"),
+        }
+    }
+}
+
+impl fmt::Display for FindingLocation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            FindingLocation::Offset(offset) => write!(f, "offset {}", offset),
+            FindingLocation::Device => f.write_str("device"),
+            FindingLocation::Configuration { config } => write!(f, "configuration {}", config),
+            FindingLocation::Interface { config, interface } => {
+                write!(f, "configuration {}, interface {}", config, interface)
+            },
+            FindingLocation::AlternateSetting { config, interface, setting } => {
+                write!(f, "configuration {}, interface {}, alternate setting {}", config, interface, setting)
+            },
+            FindingLocation::Endpoint { config, interface, setting, address } => {
+                write!(f, "configuration {}, interface {}, alternate setting {}, endpoint {:#04x}", config, interface, setting, address)
+            },
+        }
+    }
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}: {} ({})", self.severity, self.location, self.message, self.spec_reference)
+    }
+}
+
+
+/// Checks raw descriptors for violations of the USB specifications.
+///
+/// `bytes` holds a device descriptor followed by the device's configurations, in the format
+/// accepted by [`parse_descriptors`](fn.parse_descriptors.html). Besides the checks of
+/// [`lint_descriptors`](fn.lint_descriptors.html), the raw bytes are checked for descriptors
+/// with the wrong `bLength` and for counts that don't match the descriptors that follow them.
+/// If the descriptors can't be parsed, a `Malformed` finding describes the problem and the
+/// descriptors after it aren't checked.
+///
+/// ## Examples
+///
+/// ```no_run
+/// use std::fs::File;
+/// use std::io::Read;
+///
+/// let mut bytes = Vec::new();
+/// File::open("/sys/bus/usb/devices/1-1/descriptors").unwrap().read_to_end(&mut bytes).unwrap();
+///
+/// for finding in libusb::lint_descriptor_bytes(&bytes, libusb::Speed::High) {
+///     println!("{}", finding);
+/// }
+/// ```
+pub fn lint_descriptor_bytes(bytes: &[u8], speed: Speed) -> Vec<Finding> {
+    let mut findings = Vec::new();
+
+    let device = match descriptor_parser::parse_device(bytes) {
+        Ok(device) => device,
+        Err(e) => {
+            findings.push(Finding::error(FindingKind::Malformed, FindingLocation::Offset(0), "USB 2.0 §9.6.1", e.to_string()));
+            return findings;
+        },
+    };
+
+    check_length(&mut findings, FindingLocation::Device, "device", bytes[0], 18, "USB 2.0 §9.6.1");
+
+    let mut configs = Vec::new();
+    let mut offset = bytes[0] as usize;
+
+    while offset < bytes.len() {
+        let rest = &bytes[offset..];
+
+        match descriptor_parser::parse_config(rest, LIBUSB_DT_CONFIG) {
+            Ok(config) => {
+                let total_length = read_u16(rest, 2) as usize;
+
+                lint_config_bytes(&mut findings, &rest[..total_length]);
+                configs.push(config);
+                offset += total_length;
+            },
+            Err(e) => {
+                findings.push(Finding::error(FindingKind::Malformed, FindingLocation::Offset(offset), "USB 2.0 §9.6.3", e.to_string()));
+                return findings;
+            },
+        }
+    }
+
+    if device.num_configurations() as usize != configs.len() {
+        findings.push(Finding::error(FindingKind::Count, FindingLocation::Device, "USB 2.0 §9.6.1",
+                                     format!("bNumConfigurations is {}, but {} configurations follow", device.num_configurations(), configs.len())));
+    }
+
+    findings.extend(lint_descriptors(&device, &configs, speed));
+    findings
+}
+
+/// Checks a device's descriptors for violations of the USB specifications at the speed at which
+/// the device operates.
+///
+/// The findings describe illegal packet sizes and polling intervals for `speed`, illegal or
+/// duplicate endpoint addresses, missing SuperSpeed endpoint companion descriptors, interfaces
+/// that aren't numbered consecutively from zero and missing manufacturer, product and (for mass
+/// storage devices) serial number strings. Packet sizes and intervals aren't checked if `speed`
+/// is `Unknown`.
+///
+/// An endpoint address may be reused by the alternate settings of an interface, because only one
+/// of them is active at a time, but not by two interfaces of the same configuration.
+///
+/// Parsed descriptors don't keep `bLength` and the declared counts of the raw descriptors. Use
+/// [`lint_descriptor_bytes`](fn.lint_descriptor_bytes.html) to check them as well.
+pub fn lint_descriptors(device: &DeviceDescriptor, configs: &[ConfigDescriptor], speed: Speed) -> Vec<Finding> {
+    let mut findings = Vec::new();
+
+    lint_device(&mut findings, device, speed);
+
+    for config in configs {
+        lint_config(&mut findings, config, speed);
+    }
+
+    let mass_storage = device.class().class_code() == ClassCode::MassStorage || configs.iter().any(|config| {
+        config.interfaces().any(|interface| {
+            interface.descriptors().any(|setting| setting.class().class_code() == ClassCode::MassStorage)
+        })
+    });
+
+    if mass_storage && device.serial_number_string_index().is_none() {
+        findings.push(Finding::error(FindingKind::MissingString, FindingLocation::Device, "USB Mass Storage Bulk-Only Transport 1.0 §4.1.1",
+                                     "mass storage devices must have a serial number string (iSerialNumber)".to_owned()));
+    }
+
+    findings
+}
+
+
+fn check_length(findings: &mut Vec<Finding>, location: FindingLocation, name: &str, length: u8, expected: u8, spec_reference: &'static str) {
+    if length != expected {
+        findings.push(Finding::error(FindingKind::Length, location, spec_reference,
+                                     format!("{} descriptor has bLength {}, expected {}", name, length, expected)));
+    }
+}
+
+/// Checks the lengths and counts of a configuration's raw descriptors, which `parse_config` has
+/// already found to be well-formed.
+fn lint_config_bytes(findings: &mut Vec<Finding>, bytes: &[u8]) {
+    struct Setting {
+        location: FindingLocation,
+        class_code: ClassCode,
+        num_endpoints: u8,
+        endpoints: usize,
+    }
+
+    fn check_endpoints(findings: &mut Vec<Finding>, setting: Option<Setting>) {
+        if let Some(setting) = setting {
+            if setting.num_endpoints as usize != setting.endpoints {
+                findings.push(Finding::error(FindingKind::Count, setting.location, "USB 2.0 §9.6.5",
+                                             format!("bNumEndpoints is {}, but {} endpoints follow", setting.num_endpoints, setting.endpoints)));
+            }
+        }
+    }
+
+    let config = bytes[5];
+    let mut interfaces: Vec<u8> = Vec::new();
+    let mut current: Option<Setting> = None;
+    let mut endpoint: Option<FindingLocation> = None;
+    let mut offset = bytes[0] as usize;
+
+    check_length(findings, FindingLocation::Configuration { config: config }, "configuration", bytes[0], 9, "USB 2.0 §9.6.3");
+
+    while offset < bytes.len() {
+        let descriptor = &bytes[offset..offset + bytes[offset] as usize];
+        let length = descriptor[0];
+
+        match descriptor[1] {
+            LIBUSB_DT_INTERFACE => {
+                check_endpoints(findings, current.take());
+
+                let location = FindingLocation::AlternateSetting { config: config, interface: descriptor[2], setting: descriptor[3] };
+
+                if !interfaces.contains(&descriptor[2]) {
+                    interfaces.push(descriptor[2]);
+                }
+
+                check_length(findings, location, "interface", length, 9, "USB 2.0 §9.6.5");
+
+                current = Some(Setting {
+                    location: location,
+                    class_code: ClassCode::from_code(descriptor[5]),
+                    num_endpoints: descriptor[4],
+                    endpoints: 0,
+                });
+
+                endpoint = None;
+            },
+            LIBUSB_DT_ENDPOINT if current.is_some() => {
+                let setting = current.as_mut().unwrap();
+                let location = match setting.location {
+                    FindingLocation::AlternateSetting { config, interface, setting } => {
+                        FindingLocation::Endpoint { config: config, interface: interface, setting: setting, address: descriptor[2] }
+                    },
+                    location => location,
+                };
+
+                // Audio 1.0 endpoints append bRefresh and bSynchAddress.
+                if !(length == 7 || (length == 9 && setting.class_code == ClassCode::Audio)) {
+                    check_length(findings, location, "endpoint", length, 7, "USB 2.0 §9.6.6");
+                }
+
+                setting.endpoints += 1;
+                endpoint = Some(location);
+            },
+            DT_INTERFACE_ASSOCIATION => {
+                check_length(findings, FindingLocation::Configuration { config: config }, "interface association", length, 8, "USB 3.2 §9.6.4");
+            },
+            LIBUSB_DT_SS_ENDPOINT_COMPANION => {
+                if let Some(location) = endpoint {
+                    check_length(findings, location, "SuperSpeed endpoint companion", length, 6, "USB 3.2 §9.6.7");
+                }
+            },
+            DT_SSP_ISOCHRONOUS_ENDPOINT_COMPANION => {
+                if let Some(location) = endpoint {
+                    check_length(findings, location, "SuperSpeedPlus isochronous endpoint companion", length, 8, "USB 3.2 §9.6.8");
+                }
+            },
+            _ => {},
+        }
+
+        offset += length as usize;
+    }
+
+    check_endpoints(findings, current);
+
+    if bytes[4] as usize != interfaces.len() {
+        findings.push(Finding::error(FindingKind::Count, FindingLocation::Configuration { config: config }, "USB 2.0 §9.6.3",
+                                     format!("bNumInterfaces is {}, but {} interfaces follow", bytes[4], interfaces.len())));
+    }
+}
+
+fn lint_device(findings: &mut Vec<Finding>, device: &DeviceDescriptor, speed: Speed) {
+    let size = device.max_packet_size();
+
+    if speed == Speed::Super {
+        if device.usb_version().major() >= 3 && size != 9 {
+            findings.push(Finding::error(FindingKind::MaxPacketSize, FindingLocation::Device, "USB 3.2 §9.6.1",
+                                         format!("bMaxPacketSize0 is {}, but must be 9 (512 bytes) at super speed", size)));
+        }
+    }
+    else if let Some((sizes, spec_reference)) = packet_sizes(speed, TransferType::Control) {
+        let what = format!("the default control endpoint at {}", speed_name(speed));
+        check_packet_size(findings, FindingLocation::Device, "bMaxPacketSize0", size as u16, sizes, &what, spec_reference);
+    }
+
+    if device.manufacturer_string_index().is_none() {
+        findings.push(Finding::warning(FindingKind::MissingString, FindingLocation::Device, "USB 2.0 §9.6.1",
+                                       "device has no manufacturer string (iManufacturer)".to_owned()));
+    }
+
+    if device.product_string_index().is_none() {
+        findings.push(Finding::warning(FindingKind::MissingString, FindingLocation::Device, "USB 2.0 §9.6.1",
+                                       "device has no product string (iProduct)".to_owned()));
+    }
+}
+
+fn lint_config(findings: &mut Vec<Finding>, config: &ConfigDescriptor, speed: Speed) {
+    let number = config.number();
+    let mut numbers: Vec<u8> = Vec::new();
+    let mut owners: Vec<(u8, u8)> = Vec::new();
+    let mut reported: Vec<(u8, u8)> = Vec::new();
+
+    for interface in config.interfaces() {
+        let mut has_default_setting = false;
+
+        numbers.push(interface.number());
+
+        for setting in interface.descriptors() {
+            let mut addresses: Vec<u8> = Vec::new();
+
+            has_default_setting |= setting.setting_number() == 0;
+
+            for endpoint in setting.endpoint_descriptors() {
+                let address = endpoint.address();
+                let location = FindingLocation::Endpoint {
+                    config: number,
+                    interface: interface.number(),
+                    setting: setting.setting_number(),
+                    address: address,
+                };
+
+                if addresses.contains(&address) {
+                    findings.push(Finding::error(FindingKind::DuplicateEndpoint, location, "USB 2.0 §9.6.6",
+                                                 format!("endpoint {:#04x} is described more than once in the alternate setting", address)));
+                }
+
+                addresses.push(address);
+
+                match owners.iter().find(|&&(a, _)| a == address).map(|&(_, owner)| owner) {
+                    Some(owner) if owner != interface.number() && !reported.contains(&(address, interface.number())) => {
+                        findings.push(Finding::error(FindingKind::DuplicateEndpoint, location, "USB 2.0 §9.6.6",
+                                                     format!("endpoint {:#04x} is also used by interface {}, which can be active at the same time", address, owner)));
+                        reported.push((address, interface.number()));
+                    },
+                    Some(_) => {},
+                    None => owners.push((address, interface.number())),
+                }
+
+                lint_endpoint(findings, location, &endpoint, speed);
+            }
+        }
+
+        if !has_default_setting {
+            findings.push(Finding::error(FindingKind::InterfaceNumber, FindingLocation::Interface { config: number, interface: interface.number() }, "USB 2.0 §9.6.5",
+                                         "interface has no alternate setting 0".to_owned()));
+        }
+    }
+
+    numbers.sort();
+
+    if numbers.iter().enumerate().any(|(i, &n)| n as usize != i) {
+        let list = numbers.iter().map(|n| n.to_string()).collect::<Vec<_>>().join(", ");
+
+        findings.push(Finding::error(FindingKind::InterfaceNumber, FindingLocation::Configuration { config: number }, "USB 2.0 §9.6.5",
+                                     format!("interfaces are numbered {}, but must be numbered consecutively from 0", list)));
+    }
+}
+
+fn lint_endpoint(findings: &mut Vec<Finding>, location: FindingLocation, endpoint: &EndpointDescriptor, speed: Speed) {
+    let address = endpoint.address();
+    let transfer_type = endpoint.transfer_type();
+    let size = endpoint.max_packet_size() & 0x07FF;
+    let transactions = (endpoint.max_packet_size() >> 11) & 0x03;
+    let name = transfer_type_name(transfer_type);
+
+    if address & 0x0F == 0 {
+        findings.push(Finding::error(FindingKind::EndpointAddress, location, "USB 2.0 §9.6.6",
+                                     "endpoint zero is the default control endpoint and has no endpoint descriptor".to_owned()));
+    }
+
+    if address & 0x70 != 0 {
+        findings.push(Finding::error(FindingKind::EndpointAddress, location, "USB 2.0 §9.6.6",
+                                     format!("bEndpointAddress {:#04x} sets reserved bits 4 to 6", address)));
+    }
+
+    if speed == Speed::Unknown {
+        return;
+    }
+
+    if speed == Speed::Low && (transfer_type == TransferType::Bulk || transfer_type == TransferType::Isochronous) {
+        let spec_reference = if transfer_type == TransferType::Bulk { "USB 2.0 §5.8.3" } else { "USB 2.0 §5.6.3" };
+
+        findings.push(Finding::error(FindingKind::TransferType, location, spec_reference,
+                                     format!("low-speed devices can't have {} endpoints", name)));
+    }
+
+    if let Some((sizes, spec_reference)) = packet_sizes(speed, transfer_type) {
+        let what = format!("{} endpoints at {}", name, speed_name(speed));
+        check_packet_size(findings, location, "wMaxPacketSize", size, sizes, &what, spec_reference);
+    }
+
+    let periodic = transfer_type == TransferType::Interrupt || transfer_type == TransferType::Isochronous;
+
+    if transactions != 0 {
+        if speed == Speed::High && periodic {
+            let min_size = [0, 513, 683, 0][transactions as usize];
+
+            if transactions == 3 {
+                findings.push(Finding::error(FindingKind::MaxPacketSize, location, "USB 2.0 §9.6.6",
+                                             "bits 11 and 12 of wMaxPacketSize have the reserved value 3".to_owned()));
+            }
+            else if size < min_size {
+                findings.push(Finding::error(FindingKind::MaxPacketSize, location, "USB 2.0 §9.6.6",
+                                             format!("wMaxPacketSize {} is too small for {} additional transactions per microframe, which need at least {}", size, transactions, min_size)));
+            }
+        }
+        else {
+            let spec_reference = if speed == Speed::Super { "USB 3.2 §9.6.6" } else { "USB 2.0 §9.6.6" };
+
+            findings.push(Finding::error(FindingKind::MaxPacketSize, location, spec_reference,
+                                         format!("bits 11 and 12 of wMaxPacketSize must be zero for {} endpoints at {}", name, speed_name(speed))));
+        }
+    }
+
+    if let Some((min, max, spec_reference)) = intervals(speed, transfer_type) {
+        let interval = endpoint.interval();
+
+        if interval < min || interval > max {
+            findings.push(Finding::error(FindingKind::Interval, location, spec_reference,
+                                         format!("bInterval {} is illegal for {} endpoints at {}, which must be between {} and {}", interval, name, speed_name(speed), min, max)));
+        }
+        else if speed == Speed::Low && interval < 10 {
+            findings.push(Finding::warning(FindingKind::Interval, location, "USB 2.0 §5.7.4",
+                                           format!("bInterval {} is shorter than the 10 ms that hosts guarantee for low-speed interrupt endpoints", interval)));
+        }
+    }
+
+    if speed == Speed::Super {
+        match endpoint.ss_companion() {
+            None => {
+                findings.push(Finding::error(FindingKind::MissingCompanion, location, "USB 3.2 §9.6.7",
+                                             "endpoint has no SuperSpeed endpoint companion descriptor".to_owned()));
+            },
+            Some(companion) => {
+                if periodic && companion.max_burst() > 0 && size != 1024 {
+                    findings.push(Finding::error(FindingKind::MaxPacketSize, location, "USB 3.2 §9.6.6",
+                                                 format!("wMaxPacketSize {} is illegal for {} endpoints with bMaxBurst {}, which must be 1024", size, name, companion.max_burst())));
+                }
+            },
+        }
+    }
+}
+
+/// The packet sizes that a field may hold.
+enum PacketSizes {
+    OneOf(&'static [u16]),
+    Between(u16, u16),
+}
+
+fn packet_sizes(speed: Speed, transfer_type: TransferType) -> Option<(PacketSizes, &'static str)> {
+    match (speed, transfer_type) {
+        (Speed::Low, TransferType::Control)          => Some((PacketSizes::OneOf(&[8]), "USB 2.0 §5.5.3")),
+        (Speed::Low, TransferType::Interrupt)        => Some((PacketSizes::Between(1, 8), "USB 2.0 §5.7.3")),
+        (Speed::Full, TransferType::Control)         => Some((PacketSizes::OneOf(&[8, 16, 32, 64]), "USB 2.0 §5.5.3")),
+        (Speed::Full, TransferType::Bulk)            => Some((PacketSizes::OneOf(&[8, 16, 32, 64]), "USB 2.0 §5.8.3")),
+        (Speed::Full, TransferType::Interrupt)       => Some((PacketSizes::Between(1, 64), "USB 2.0 §5.7.3")),
+        (Speed::Full, TransferType::Isochronous)     => Some((PacketSizes::Between(0, 1023), "USB 2.0 §5.6.3")),
+        (Speed::High, TransferType::Control)         => Some((PacketSizes::OneOf(&[64]), "USB 2.0 §5.5.3")),
+        (Speed::High, TransferType::Bulk)            => Some((PacketSizes::OneOf(&[512]), "USB 2.0 §5.8.3")),
+        (Speed::High, TransferType::Interrupt)       => Some((PacketSizes::Between(1, 1024), "USB 2.0 §5.7.3")),
+        (Speed::High, TransferType::Isochronous)     => Some((PacketSizes::Between(0, 1024), "USB 2.0 §5.6.3")),
+        (Speed::Super, TransferType::Control)        => Some((PacketSizes::OneOf(&[512]), "USB 3.2 §9.6.6")),
+        (Speed::Super, TransferType::Bulk)           => Some((PacketSizes::OneOf(&[1024]), "USB 3.2 §9.6.6")),
+        (Speed::Super, TransferType::Interrupt)      => Some((PacketSizes::Between(1, 1024), "USB 3.2 §9.6.6")),
+        (Speed::Super, TransferType::Isochronous)    => Some((PacketSizes::Between(0, 1024), "USB 3.2 §9.6.6")),
+        _ => None,
+    }
+}
+
+fn check_packet_size(findings: &mut Vec<Finding>, location: FindingLocation, field: &str, size: u16, sizes: PacketSizes, what: &str, spec_reference: &'static str) {
+    let allowed = match sizes {
+        PacketSizes::OneOf(sizes) if sizes.contains(&size) => return,
+        PacketSizes::OneOf(sizes) if sizes.len() == 1 => sizes[0].to_string(),
+        PacketSizes::OneOf(sizes) => {
+            let list = sizes[..sizes.len() - 1].iter().map(|s| s.to_string()).collect::<Vec<_>>().join(", ");
+            format!("one of {} or {}", list, sizes[sizes.len() - 1])
+        },
+        PacketSizes::Between(min, max) if size >= min && size <= max => return,
+        PacketSizes::Between(min, max) => format!("between {} and {}", min, max),
+    };
+
+    findings.push(Finding::error(FindingKind::MaxPacketSize, location, spec_reference,
+                                 format!("{} {} is illegal for {}, which must be {}", field, size, what, allowed)));
+}
+
+fn intervals(speed: Speed, transfer_type: TransferType) -> Option<(u8, u8, &'static str)> {
+    match (speed, transfer_type) {
+        (Speed::Low, TransferType::Interrupt) |
+        (Speed::Full, TransferType::Interrupt)     => Some((1, 255, "USB 2.0 §9.6.6")),
+        (Speed::Full, TransferType::Isochronous) |
+        (Speed::High, TransferType::Interrupt) |
+        (Speed::High, TransferType::Isochronous)   => Some((1, 16, "USB 2.0 §9.6.6")),
+        (Speed::Super, TransferType::Interrupt) |
+        (Speed::Super, TransferType::Isochronous)  => Some((1, 16, "USB 3.2 §9.6.6")),
+        _ => None,
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use descriptor_builder::{DeviceDescriptorBuilder, ConfigDescriptorBuilder, InterfaceDescriptorBuilder, EndpointDescriptorBuilder};
+    use descriptor_parser::parse_descriptors;
+    use fields::{Speed, TransferType, Version};
+    use super::*;
+
+    fn device() -> DeviceDescriptorBuilder {
+        DeviceDescriptorBuilder::new(0x1209, 0x0001)
+            .manufacturer_string_index(1)
+            .product_string_index(2)
+    }
+
+    fn full_speed_device() -> DeviceDescriptorBuilder {
+        device()
+            .configuration(ConfigDescriptorBuilder::new(1)
+                .interface(InterfaceDescriptorBuilder::new(0)
+                    .class_code(0x03)
+                    .endpoint(EndpointDescriptorBuilder::new(0x81, TransferType::Interrupt).max_packet_size(8).interval(10)))
+                .interface(InterfaceDescriptorBuilder::new(1)
+                    .class_code(0xFF)
+                    .endpoint(EndpointDescriptorBuilder::new(0x02, TransferType::Bulk).max_packet_size(64))
+                    .endpoint(EndpointDescriptorBuilder::new(0x82, TransferType::Bulk).max_packet_size(64))))
+    }
+
+    fn endpoint_device(endpoint: EndpointDescriptorBuilder) -> DeviceDescriptorBuilder {
+        device()
+            .configuration(ConfigDescriptorBuilder::new(1)
+                .interface(InterfaceDescriptorBuilder::new(0).class_code(0xFF).endpoint(endpoint)))
+    }
+
+    fn summary(findings: &[Finding]) -> Vec<(Severity, FindingKind, FindingLocation)> {
+        findings.iter().map(|f| (f.severity(), f.kind(), f.location())).collect()
+    }
+
+    fn endpoint_location(address: u8) -> FindingLocation {
+        FindingLocation::Endpoint { config: 1, interface: 0, setting: 0, address: address }
+    }
+
+    /// Appends a zero byte to the descriptor at `offset` and fixes up `bLength` and the first
+    /// configuration's `wTotalLength`.
+    fn lengthen(bytes: &mut Vec<u8>, offset: usize) {
+        let length = bytes[offset] as usize;
+
+        bytes.insert(offset + length, 0);
+        bytes[offset] += 1;
+
+        if offset != 0 {
+            let config = bytes[0] as usize;
+            bytes[config + 2] += 1;
+        }
+    }
+
+    #[test]
+    fn it_accepts_compliant_descriptors() {
+        assert_eq!(vec![] as Vec<Finding>, lint_descriptor_bytes(&full_speed_device().build(), Speed::Full));
+
+        let high_speed = device()
+            .configuration(ConfigDescriptorBuilder::new(1)
+                .interface(InterfaceDescriptorBuilder::new(0)
+                    .endpoint(EndpointDescriptorBuilder::new(0x81, TransferType::Bulk).max_packet_size(512))
+                    .endpoint(EndpointDescriptorBuilder::new(0x82, TransferType::Interrupt).max_packet_size(0x1400).interval(4)))
+                .interface(InterfaceDescriptorBuilder::new(1))
+                .interface(InterfaceDescriptorBuilder::new(1)
+                    .setting_number(1)
+                    .endpoint(EndpointDescriptorBuilder::new(0x83, TransferType::Isochronous).max_packet_size(1024).interval(1)))
+                .interface(InterfaceDescriptorBuilder::new(1)
+                    .setting_number(2)
+                    .endpoint(EndpointDescriptorBuilder::new(0x83, TransferType::Isochronous).max_packet_size(512).interval(1))));
+
+        assert_eq!(vec![] as Vec<Finding>, lint_descriptor_bytes(&high_speed.build(), Speed::High));
+
+        let super_speed = device()
+            .usb_version(Version(3, 0, 0))
+            .max_packet_size(9)
+            .configuration(ConfigDescriptorBuilder::new(1)
+                .interface(InterfaceDescriptorBuilder::new(0)
+                    .endpoint(EndpointDescriptorBuilder::new(0x81, TransferType::Bulk).max_packet_size(1024).ss_companion(15, 0, 0))
+                    .endpoint(EndpointDescriptorBuilder::new(0x82, TransferType::Interrupt).max_packet_size(1024).interval(4).ss_companion(1, 0, 2048))));
+
+        assert_eq!(vec![] as Vec<Finding>, lint_descriptor_bytes(&super_speed.build(), Speed::Super));
+    }
+
+    #[test]
+    fn it_reports_wrong_descriptor_lengths() {
+        let mut bytes = full_speed_device().build();
+
+        lengthen(&mut bytes, 18 + 9 + 9 + 7 + 9);
+        lengthen(&mut bytes, 18 + 9);
+        lengthen(&mut bytes, 0);
+
+        assert_eq!(vec![
+            (Severity::Error, FindingKind::Length, FindingLocation::Device),
+            (Severity::Error, FindingKind::Length, FindingLocation::AlternateSetting { config: 1, interface: 0, setting: 0 }),
+            (Severity::Error, FindingKind::Length, FindingLocation::Endpoint { config: 1, interface: 1, setting: 0, address: 0x02 }),
+        ], summary(&lint_descriptor_bytes(&bytes, Speed::Full)));
+    }
+
+    #[test]
+    fn it_accepts_audio_endpoints_with_refresh_fields() {
+        let mut bytes = endpoint_device(EndpointDescriptorBuilder::new(0x01, TransferType::Isochronous).max_packet_size(192).interval(1)).build();
+
+        bytes[18 + 9 + 5] = 0x01;
+        lengthen(&mut bytes, 18 + 9 + 9);
+        lengthen(&mut bytes, 18 + 9 + 9);
+
+        assert_eq!(vec![] as Vec<Finding>, lint_descriptor_bytes(&bytes, Speed::Full));
+
+        bytes[18 + 9 + 5] = 0xFF;
+
+        assert_eq!(vec![(Severity::Error, FindingKind::Length, endpoint_location(0x01))],
+                   summary(&lint_descriptor_bytes(&bytes, Speed::Full)));
+    }
+
+    #[test]
+    fn it_reports_count_mismatches() {
+        let mut bytes = full_speed_device().build();
+
+        bytes[17] = 2;
+        bytes[18 + 4] = 3;
+        bytes[18 + 9 + 4] = 0;
+
+        let findings = lint_descriptor_bytes(&bytes, Speed::Full);
+
+        assert_eq!(vec![
+            (Severity::Error, FindingKind::Count, FindingLocation::AlternateSetting { config: 1, interface: 0, setting: 0 }),
+            (Severity::Error, FindingKind::Count, FindingLocation::Configuration { config: 1 }),
+            (Severity::Error, FindingKind::Count, FindingLocation::Device),
+        ], summary(&findings));
+
+        assert_eq!("bNumEndpoints is 0, but 1 endpoints follow", findings[0].message());
+        assert_eq!("bNumInterfaces is 3, but 2 interfaces follow", findings[1].message());
+        assert_eq!("bNumConfigurations is 2, but 1 configurations follow", findings[2].message());
+    }
+
+    #[test]
+    fn it_reports_malformed_descriptors() {
+        let bytes = full_speed_device().build();
+
+        assert_eq!(vec![(Severity::Error, FindingKind::Malformed, FindingLocation::Offset(0))],
+                   summary(&lint_descriptor_bytes(&bytes[..10], Speed::Full)));
+
+        assert_eq!(vec![(Severity::Error, FindingKind::Malformed, FindingLocation::Offset(18))],
+                   summary(&lint_descriptor_bytes(&bytes[..30], Speed::Full)));
+    }
+
+    #[test]
+    fn it_reports_illegal_packet_sizes_for_speed() {
+        let findings = lint_descriptor_bytes(&full_speed_device().build(), Speed::High);
+
+        assert_eq!(vec![
+            (Severity::Error, FindingKind::MaxPacketSize, FindingLocation::Endpoint { config: 1, interface: 1, setting: 0, address: 0x02 }),
+            (Severity::Error, FindingKind::MaxPacketSize, FindingLocation::Endpoint { config: 1, interface: 1, setting: 0, address: 0x82 }),
+        ], summary(&findings));
+
+        assert_eq!("wMaxPacketSize 64 is illegal for bulk endpoints at high speed, which must be 512", findings[0].message());
+        assert_eq!("USB 2.0 §5.8.3", findings[0].spec_reference());
+
+        let findings = lint_descriptor_bytes(&device().max_packet_size(12).build(), Speed::Full);
+
+        assert_eq!(vec![(Severity::Error, FindingKind::MaxPacketSize, FindingLocation::Device)], summary(&findings));
+        assert_eq!("bMaxPacketSize0 12 is illegal for the default control endpoint at full speed, which must be one of 8, 16, 32 or 64", findings[0].message());
+
+        let interrupt = EndpointDescriptorBuilder::new(0x81, TransferType::Interrupt).interval(1);
+
+        assert_eq!(vec![(Severity::Error, FindingKind::MaxPacketSize, endpoint_location(0x81))],
+                   summary(&lint_descriptor_bytes(&endpoint_device(interrupt.clone().max_packet_size(65)).build(), Speed::Full)));
+        assert_eq!(vec![(Severity::Error, FindingKind::MaxPacketSize, endpoint_location(0x81))],
+                   summary(&lint_descriptor_bytes(&endpoint_device(interrupt.clone().max_packet_size(0)).build(), Speed::High)));
+        assert_eq!(vec![] as Vec<(Severity, FindingKind, FindingLocation)>,
+                   summary(&lint_descriptor_bytes(&endpoint_device(interrupt.clone().max_packet_size(0)).build(), Speed::Unknown)));
+    }
+
+    #[test]
+    fn it_reports_illegal_high_bandwidth_transactions() {
+        let interrupt = EndpointDescriptorBuilder::new(0x81, TransferType::Interrupt).interval(1);
+
+        let findings = lint_descriptor_bytes(&endpoint_device(interrupt.clone().max_packet_size(0x0800 | 512)).build(), Speed::High);
+        assert_eq!(vec![(Severity::Error, FindingKind::MaxPacketSize, endpoint_location(0x81))], summary(&findings));
+        assert_eq!("wMaxPacketSize 512 is too small for 1 additional transactions per microframe, which need at least 513", findings[0].message());
+
+        assert_eq!(vec![(Severity::Error, FindingKind::MaxPacketSize, endpoint_location(0x81))],
+                   summary(&lint_descriptor_bytes(&endpoint_device(interrupt.clone().max_packet_size(0x1800 | 1024)).build(), Speed::High)));
+        assert_eq!(vec![(Severity::Error, FindingKind::MaxPacketSize, endpoint_location(0x81))],
+                   summary(&lint_descriptor_bytes(&endpoint_device(interrupt.clone().max_packet_size(0x0800 | 64)).build(), Speed::Full)));
+
+        let bulk = EndpointDescriptorBuilder::new(0x81, TransferType::Bulk).max_packet_size(0x0800 | 512);
+
+        assert_eq!(vec![(Severity::Error, FindingKind::MaxPacketSize, endpoint_location(0x81))],
+                   summary(&lint_descriptor_bytes(&endpoint_device(bulk).build(), Speed::High)));
+    }
+
+    #[test]
+    fn it_reports_transfer_types_illegal_at_low_speed() {
+        let findings = lint_descriptor_bytes(&full_speed_device().max_packet_size(8).build(), Speed::Low);
+
+        assert_eq!(vec![
+            (Severity::Error, FindingKind::TransferType, FindingLocation::Endpoint { config: 1, interface: 1, setting: 0, address: 0x02 }),
+            (Severity::Error, FindingKind::TransferType, FindingLocation::Endpoint { config: 1, interface: 1, setting: 0, address: 0x82 }),
+        ], summary(&findings));
+
+        assert_eq!("low-speed devices can't have bulk endpoints", findings[0].message());
+    }
+
+    #[test]
+    fn it_reports_illegal_intervals() {
+        let interrupt = EndpointDescriptorBuilder::new(0x81, TransferType::Interrupt).max_packet_size(8);
+
+        let findings = lint_descriptor_bytes(&endpoint_device(interrupt.clone().interval(0)).build(), Speed::Full);
+        assert_eq!(vec![(Severity::Error, FindingKind::Interval, endpoint_location(0x81))], summary(&findings));
+        assert_eq!("bInterval 0 is illegal for interrupt endpoints at full speed, which must be between 1 and 255", findings[0].message());
+
+        assert_eq!(vec![(Severity::Error, FindingKind::Interval, endpoint_location(0x81))],
+                   summary(&lint_descriptor_bytes(&endpoint_device(interrupt.clone().interval(32)).build(), Speed::High)));
+        assert_eq!(vec![(Severity::Warning, FindingKind::Interval, endpoint_location(0x81))],
+                   summary(&lint_descriptor_bytes(&endpoint_device(interrupt.clone().interval(5)).max_packet_size(8).build(), Speed::Low)));
+
+        let bulk = EndpointDescriptorBuilder::new(0x81, TransferType::Bulk).max_packet_size(512).interval(255);
+
+        assert_eq!(vec![] as Vec<Finding>, lint_descriptor_bytes(&endpoint_device(bulk).build(), Speed::High));
+    }
+
+    #[test]
+    fn it_reports_duplicate_endpoint_addresses() {
+        let bulk = |address| EndpointDescriptorBuilder::new(address, TransferType::Bulk).max_packet_size(64);
+
+        let bytes = device()
+            .configuration(ConfigDescriptorBuilder::new(1)
+                .interface(InterfaceDescriptorBuilder::new(0).endpoint(bulk(0x81)))
+                .interface(InterfaceDescriptorBuilder::new(0).setting_number(1).endpoint(bulk(0x81)))
+                .interface(InterfaceDescriptorBuilder::new(1).endpoint(bulk(0x02)).endpoint(bulk(0x02)))
+                .interface(InterfaceDescriptorBuilder::new(1).setting_number(1).endpoint(bulk(0x81)))
+                .interface(InterfaceDescriptorBuilder::new(1).setting_number(2).endpoint(bulk(0x81))))
+            .build();
+
+        let findings = lint_descriptor_bytes(&bytes, Speed::Full);
+
+        assert_eq!(vec![
+            (Severity::Error, FindingKind::DuplicateEndpoint, FindingLocation::Endpoint { config: 1, interface: 1, setting: 0, address: 0x02 }),
+            (Severity::Error, FindingKind::DuplicateEndpoint, FindingLocation::Endpoint { config: 1, interface: 1, setting: 1, address: 0x81 }),
+        ], summary(&findings));
+
+        assert_eq!("endpoint 0x02 is described more than once in the alternate setting", findings[0].message());
+        assert_eq!("endpoint 0x81 is also used by interface 0, which can be active at the same time", findings[1].message());
+    }
+
+    #[test]
+    fn it_reports_illegal_endpoint_addresses() {
+        let bulk = |address| EndpointDescriptorBuilder::new(address, TransferType::Bulk).max_packet_size(64);
+
+        let bytes = device()
+            .configuration(ConfigDescriptorBuilder::new(1)
+                .interface(InterfaceDescriptorBuilder::new(0).endpoint(bulk(0x80)).endpoint(bulk(0x91))))
+            .build();
+
+        assert_eq!(vec![
+            (Severity::Error, FindingKind::EndpointAddress, endpoint_location(0x80)),
+            (Severity::Error, FindingKind::EndpointAddress, endpoint_location(0x91)),
+        ], summary(&lint_descriptor_bytes(&bytes, Speed::Full)));
+    }
+
+    #[test]
+    fn it_reports_interface_numbering() {
+        let bytes = device()
+            .configuration(ConfigDescriptorBuilder::new(1)
+                .interface(InterfaceDescriptorBuilder::new(0))
+                .interface(InterfaceDescriptorBuilder::new(2).setting_number(1)))
+            .build();
+
+        let findings = lint_descriptor_bytes(&bytes, Speed::Full);
+
+        assert_eq!(vec![
+            (Severity::Error, FindingKind::InterfaceNumber, FindingLocation::Interface { config: 1, interface: 2 }),
+            (Severity::Error, FindingKind::InterfaceNumber, FindingLocation::Configuration { config: 1 }),
+        ], summary(&findings));
+
+        assert_eq!("interfaces are numbered 0, 2, but must be numbered consecutively from 0", findings[1].message());
+    }
+
+    #[test]
+    fn it_reports_missing_strings() {
+        let findings = lint_descriptor_bytes(&DeviceDescriptorBuilder::new(0x1209, 0x0001).build(), Speed::Full);
+
+        assert_eq!(vec![
+            (Severity::Warning, FindingKind::MissingString, FindingLocation::Device),
+            (Severity::Warning, FindingKind::MissingString, FindingLocation::Device),
+        ], summary(&findings));
+
+        let mass_storage = device()
+            .configuration(ConfigDescriptorBuilder::new(1)
+                .interface(InterfaceDescriptorBuilder::new(0).class_code(0x08).sub_class_code(0x06).protocol_code(0x50)
+                    .endpoint(EndpointDescriptorBuilder::new(0x81, TransferType::Bulk).max_packet_size(512))
+                    .endpoint(EndpointDescriptorBuilder::new(0x02, TransferType::Bulk).max_packet_size(512))));
+
+        let findings = lint_descriptor_bytes(&mass_storage.clone().build(), Speed::High);
+
+        assert_eq!(vec![(Severity::Error, FindingKind::MissingString, FindingLocation::Device)], summary(&findings));
+        assert_eq!("USB Mass Storage Bulk-Only Transport 1.0 §4.1.1", findings[0].spec_reference());
+
+        assert_eq!(vec![] as Vec<Finding>, lint_descriptor_bytes(&mass_storage.serial_number_string_index(3).build(), Speed::High));
+    }
+
+    #[test]
+    fn it_checks_super_speed_endpoints() {
+        let bytes = device()
+            .usb_version(Version(3, 2, 0))
+            .configuration(ConfigDescriptorBuilder::new(1)
+                .interface(InterfaceDescriptorBuilder::new(0)
+                    .endpoint(EndpointDescriptorBuilder::new(0x81, TransferType::Bulk).max_packet_size(1024))
+                    .endpoint(EndpointDescriptorBuilder::new(0x82, TransferType::Interrupt).max_packet_size(512).interval(4).ss_companion(1, 0, 1024))))
+            .build();
+
+        let findings = lint_descriptor_bytes(&bytes, Speed::Super);
+
+        assert_eq!(vec![
+            (Severity::Error, FindingKind::MaxPacketSize, FindingLocation::Device),
+            (Severity::Error, FindingKind::MissingCompanion, endpoint_location(0x81)),
+            (Severity::Error, FindingKind::MaxPacketSize, endpoint_location(0x82)),
+        ], summary(&findings));
+
+        assert_eq!("bMaxPacketSize0 is 64, but must be 9 (512 bytes) at super speed", findings[0].message());
+        assert_eq!("wMaxPacketSize 512 is illegal for interrupt endpoints with bMaxBurst 1, which must be 1024", findings[2].message());
+    }
+
+    #[test]
+    fn it_lints_parsed_descriptors() {
+        let (device, configs) = parse_descriptors(&full_speed_device().build()).unwrap();
+        let findings = lint_descriptors(&device, &configs, Speed::High);
+
+        assert_eq!(lint_descriptor_bytes(&full_speed_device().build(), Speed::High), findings);
+        assert_eq!("error: configuration 1, interface 1, alternate setting 0, endpoint 0x02: \
+                    wMaxPacketSize 64 is illegal for bulk endpoints at high speed, which must be 512 (USB 2.0 §5.8.3)",
+                   findings[0].to_string());
+    }
+}
@@ -0,0 +1,904 @@
+use std::ptr;
+
+use libc::{c_int, c_uchar};
+use libusb::*;
+
+use config_descriptor::{self, ConfigDescriptor};
+use device_descriptor::{self, DeviceDescriptor};
+use device_qualifier::{self, DeviceQualifier};
+
+
+/// Descriptor type of the device qualifier descriptor.
+pub const DT_DEVICE_QUALIFIER: u8 = 0x06;
+
+/// Descriptor type of the other speed configuration descriptor.
+pub const DT_OTHER_SPEED_CONFIGURATION: u8 = 0x07;
+
+/// Descriptor type of the interface association descriptor.
+pub const DT_INTERFACE_ASSOCIATION: u8 = 0x0B;
+
+/// Descriptor type of the HID descriptor.
+pub const DT_HID: u8 = 0x21;
+
+/// Descriptor type of class-specific interface descriptors.
+pub const DT_CS_INTERFACE: u8 = 0x24;
+
+/// Descriptor type of the SuperSpeedPlus isochronous endpoint companion descriptor.
+pub const DT_SSP_ISOCHRONOUS_ENDPOINT_COMPANION: u8 = 0x31;
+
+/// Storage for a configuration descriptor that was parsed from raw bytes.
+///
+/// The `libusb` structs point into the vectors, whose heap buffers don't move when the storage is
+/// moved, so a `ConfigDescriptor` can keep using the same pointers as for libusb-owned descriptors.
+pub struct OwnedConfig {
+    descriptor: Box<libusb_config_descriptor>,
+    _interfaces: Vec<libusb_interface>,
+    _settings: Vec<Vec<libusb_interface_descriptor>>,
+    _endpoints: Vec<Vec<libusb_endpoint_descriptor>>,
+    _extras: Vec<Vec<u8>>,
+}
+
+impl OwnedConfig {
+    pub fn descriptor(&self) -> *const libusb_config_descriptor {
+        &*self.descriptor
+    }
+}
+
+struct Setting {
+    descriptor: libusb_interface_descriptor,
+    extra: Vec<u8>,
+    endpoints: Vec<(libusb_endpoint_descriptor, Vec<u8>)>,
+}
+
+struct Interface {
+    number: u8,
+    settings: Vec<Setting>,
+}
+
+/// Checks the header of the descriptor at the start of `bytes`.
+pub fn check_header(bytes: &[u8], descriptor_type: u8, min_length: usize, name: &str) -> ::Result<usize> {
+    if bytes.len() < min_length {
+        return Err(format!("{} descriptor needs at least {} bytes, got {}", name, min_length, bytes.len()).into());
+    }
+
+    if bytes[1] != descriptor_type {
+        return Err(format!("expected {} descriptor (type {:#04x}), got type {:#04x}", name, descriptor_type, bytes[1]).into());
+    }
+
+    let length = bytes[0] as usize;
+
+    if length < min_length {
+        return Err(format!("{} descriptor has bLength {}, expected at least {}", name, length, min_length).into());
+    }
+
+    if length > bytes.len() {
+        return Err(format!("{} descriptor has bLength {}, but only {} bytes are available", name, length, bytes.len()).into());
+    }
+
+    Ok(length)
+}
+
+pub fn read_u16(bytes: &[u8], offset: usize) -> u16 {
+    bytes[offset] as u16 | (bytes[offset + 1] as u16) << 8
+}
+
+/// Parses a device descriptor.
+pub fn parse_device(bytes: &[u8]) -> ::Result<DeviceDescriptor> {
+    check_header(bytes, LIBUSB_DT_DEVICE, 18, "device")?;
+
+    Ok(device_descriptor::from_libusb(libusb_device_descriptor {
+        bLength:            bytes[0],
+        bDescriptorType:    bytes[1],
+        bcdUSB:             read_u16(bytes, 2),
+        bDeviceClass:       bytes[4],
+        bDeviceSubClass:    bytes[5],
+        bDeviceProtocol:    bytes[6],
+        bMaxPacketSize0:    bytes[7],
+        idVendor:           read_u16(bytes, 8),
+        idProduct:          read_u16(bytes, 10),
+        bcdDevice:          read_u16(bytes, 12),
+        iManufacturer:      bytes[14],
+        iProduct:           bytes[15],
+        iSerialNumber:      bytes[16],
+        bNumConfigurations: bytes[17],
+    }))
+}
+
+/// Parses a device qualifier descriptor.
+pub fn parse_device_qualifier(bytes: &[u8]) -> ::Result<DeviceQualifier> {
+    check_header(bytes, DT_DEVICE_QUALIFIER, 10, "device qualifier")?;
+    Ok(device_qualifier::from_raw(bytes))
+}
+
+/// Parses a device descriptor followed by the device's configurations.
+///
+/// This is the format of the `descriptors` file that Linux exposes for every device in sysfs, and
+/// of descriptor dumps that concatenate the device descriptor and the full configuration
+/// descriptors as returned by `GET_DESCRIPTOR`.
+///
+/// ## Errors
+///
+/// * `Custom` if any of the descriptors is malformed, with a message that describes the problem
+///   and where it was found.
+///
+/// ## Examples
+///
+/// ```no_run
+/// use std::fs::File;
+/// use std::io::Read;
+///
+/// let mut bytes = Vec::new();
+/// File::open("/sys/bus/usb/devices/1-1/descriptors").unwrap().read_to_end(&mut bytes).unwrap();
+///
+/// let (device, configs) = libusb::parse_descriptors(&bytes).unwrap();
+/// println!("{:04x}:{:04x} has {} configurations", device.vendor_id(), device.product_id(), configs.len());
+/// ```
+pub fn parse_descriptors(bytes: &[u8]) -> ::Result<(DeviceDescriptor, Vec<ConfigDescriptor>)> {
+    let device_length = check_header(bytes, LIBUSB_DT_DEVICE, 18, "device")?;
+    let device = parse_device(bytes)?;
+    let mut configs = Vec::new();
+    let mut offset = device_length;
+
+    while offset < bytes.len() {
+        let rest = &bytes[offset..];
+
+        if rest.len() < 4 {
+            return Err(format!("{} trailing bytes at offset {} are too short for a configuration descriptor", rest.len(), offset).into());
+        }
+
+        let config = parse_config(rest, LIBUSB_DT_CONFIG)
+            .map_err(|e| ::Error::Custom(format!("configuration descriptor at offset {}: {}", offset, e)))?;
+
+        offset += read_u16(rest, 2) as usize;
+        configs.push(config);
+    }
+
+    Ok((device, configs))
+}
+
+/// Parses a configuration descriptor and the interface, endpoint and extra descriptors that follow
+/// it.
+///
+/// `descriptor_type` is the type of the configuration's header, either `LIBUSB_DT_CONFIG` or
+/// `DT_OTHER_SPEED_CONFIGURATION`. Bytes after `wTotalLength` are ignored.
+pub fn parse_config(bytes: &[u8], descriptor_type: u8) -> ::Result<ConfigDescriptor> {
+    let name = if descriptor_type == DT_OTHER_SPEED_CONFIGURATION { "other speed configuration" } else { "configuration" };
+    let header_length = check_header(bytes, descriptor_type, 9, name)?;
+    let total_length = read_u16(bytes, 2) as usize;
+
+    if total_length < header_length {
+        return Err(format!("{} descriptor has wTotalLength {}, which is shorter than its bLength {}", name, total_length, header_length).into());
+    }
+
+    if total_length > bytes.len() {
+        return Err(format!("{} descriptor has wTotalLength {}, but only {} bytes are available", name, total_length, bytes.len()).into());
+    }
+
+    let bytes = &bytes[..total_length];
+    let mut config_extra = Vec::new();
+    let mut interfaces: Vec<Interface> = Vec::new();
+    let mut current: Option<usize> = None;
+    let mut offset = header_length;
+
+    while offset < bytes.len() {
+        if bytes.len() - offset < 2 {
+            return Err(format!("{} descriptor has a trailing byte at offset {}", name, offset).into());
+        }
+
+        let length = bytes[offset] as usize;
+
+        if length < 2 {
+            return Err(format!("descriptor at offset {} has invalid bLength {}", offset, length).into());
+        }
+
+        if offset + length > bytes.len() {
+            return Err(format!("descriptor at offset {} has bLength {}, which overruns wTotalLength {}", offset, length, total_length).into());
+        }
+
+        let descriptor = &bytes[offset..offset + length];
+
+        match descriptor[1] {
+            LIBUSB_DT_INTERFACE => {
+                if length < 9 {
+                    return Err(format!("interface descriptor at offset {} has bLength {}, expected at least 9", offset, length).into());
+                }
+
+                let setting = Setting {
+                    descriptor: libusb_interface_descriptor {
+                        bLength:            descriptor[0],
+                        bDescriptorType:    descriptor[1],
+                        bInterfaceNumber:   descriptor[2],
+                        bAlternateSetting:  descriptor[3],
+                        bNumEndpoints:      descriptor[4],
+                        bInterfaceClass:    descriptor[5],
+                        bInterfaceSubClass: descriptor[6],
+                        bInterfaceProtocol: descriptor[7],
+                        iInterface:         descriptor[8],
+                        endpoint:           ptr::null(),
+                        extra:              ptr::null(),
+                        extra_length:       0,
+                    },
+                    extra: Vec::new(),
+                    endpoints: Vec::new(),
+                };
+
+                let number = descriptor[2];
+                let index = match interfaces.iter().position(|interface| interface.number == number) {
+                    Some(index) => index,
+                    None => {
+                        interfaces.push(Interface { number: number, settings: Vec::new() });
+                        interfaces.len() - 1
+                    },
+                };
+
+                interfaces[index].settings.push(setting);
+                current = Some(index);
+            },
+            LIBUSB_DT_ENDPOINT if current.is_some() => {
+                if length < 7 {
+                    return Err(format!("endpoint descriptor at offset {} has bLength {}, expected at least 7", offset, length).into());
+                }
+
+                let endpoint = libusb_endpoint_descriptor {
+                    bLength:          descriptor[0],
+                    bDescriptorType:  descriptor[1],
+                    bEndpointAddress: descriptor[2],
+                    bmAttributes:     descriptor[3],
+                    wMaxPacketSize:   read_u16(descriptor, 4),
+                    bInterval:        descriptor[6],
+                    bRefresh:         if length >= 9 { descriptor[7] } else { 0 },
+                    bSynchAddress:    if length >= 9 { descriptor[8] } else { 0 },
+                    extra:            ptr::null(),
+                    extra_length:     0,
+                };
+
+                let setting = current_setting(&mut interfaces, current);
+                setting.endpoints.push((endpoint, Vec::new()));
+            },
+            _ => {
+                let extra = match current {
+                    None => &mut config_extra,
+                    Some(_) => {
+                        let setting = current_setting(&mut interfaces, current);
+                        match setting.endpoints.last_mut() {
+                            Some(&mut (_, ref mut extra)) => extra,
+                            None => &mut setting.extra,
+                        }
+                    },
+                };
+
+                extra.extend_from_slice(descriptor);
+            },
+        }
+
+        offset += length;
+    }
+
+    build_config(bytes, config_extra, interfaces)
+}
+
+fn current_setting(interfaces: &mut Vec<Interface>, current: Option<usize>) -> &mut Setting {
+    interfaces[current.unwrap()].settings.last_mut().unwrap()
+}
+
+/// Stores an extra descriptor buffer and returns the pointer and length for a `libusb` struct.
+fn store_extra(extras: &mut Vec<Vec<u8>>, extra: Vec<u8>) -> (*const c_uchar, c_int) {
+    if extra.is_empty() {
+        return (ptr::null(), 0);
+    }
+
+    let res = (extra.as_ptr(), extra.len() as c_int);
+    extras.push(extra);
+    res
+}
+
+fn build_config(header: &[u8], config_extra: Vec<u8>, interfaces: Vec<Interface>) -> ::Result<ConfigDescriptor> {
+    if interfaces.len() > u8::max_value() as usize {
+        return Err(format!("configuration has {} interfaces, at most 255 are supported", interfaces.len()).into());
+    }
+
+    let mut extras = Vec::new();
+    let mut endpoint_storage = Vec::new();
+    let mut setting_storage = Vec::new();
+    let mut interface_storage = Vec::with_capacity(interfaces.len());
+
+    for interface in interfaces {
+        let mut settings = Vec::with_capacity(interface.settings.len());
+
+        for setting in interface.settings {
+            if setting.endpoints.len() > u8::max_value() as usize {
+                return Err(format!("interface {} has {} endpoints, at most 255 are supported", interface.number, setting.endpoints.len()).into());
+            }
+
+            let endpoints: Vec<libusb_endpoint_descriptor> = setting.endpoints.into_iter().map(|(mut endpoint, extra)| {
+                let (extra, extra_length) = store_extra(&mut extras, extra);
+                endpoint.extra = extra;
+                endpoint.extra_length = extra_length;
+                endpoint
+            }).collect();
+
+            let mut descriptor = setting.descriptor;
+            let (extra, extra_length) = store_extra(&mut extras, setting.extra);
+            descriptor.bNumEndpoints = endpoints.len() as u8;
+            descriptor.endpoint = endpoints.as_ptr();
+            descriptor.extra = extra;
+            descriptor.extra_length = extra_length;
+
+            endpoint_storage.push(endpoints);
+            settings.push(descriptor);
+        }
+
+        interface_storage.push(libusb_interface {
+            altsetting: settings.as_ptr(),
+            num_altsetting: settings.len() as c_int,
+        });
+
+        setting_storage.push(settings);
+    }
+
+    let (extra, extra_length) = store_extra(&mut extras, config_extra);
+
+    let descriptor = Box::new(libusb_config_descriptor {
+        bLength:             header[0],
+        bDescriptorType:     header[1],
+        wTotalLength:        read_u16(header, 2),
+        bNumInterfaces:      interface_storage.len() as u8,
+        bConfigurationValue: header[5],
+        iConfiguration:      header[6],
+        bmAttributes:        header[7],
+        bMaxPower:           header[8],
+        interface:           interface_storage.as_ptr(),
+        extra:               extra,
+        extra_length:        extra_length,
+    });
+
+    Ok(config_descriptor::from_owned(OwnedConfig {
+        descriptor: descriptor,
+        _interfaces: interface_storage,
+        _settings: setting_storage,
+        _endpoints: endpoint_storage,
+        _extras: extras,
+    }))
+}
+
+
+#[cfg(test)]
+mod test {
+    use libusb::*;
+    use config_descriptor::ConfigDescriptor;
+    use descriptor_builder::{ConfigDescriptorBuilder, InterfaceDescriptorBuilder, EndpointDescriptorBuilder, InterfaceAssociationBuilder};
+    use endpoint_descriptor;
+    use fields::{Direction, TransferType, Version};
+    use test_helpers::build_config;
+    use super::{parse_config, parse_device, parse_device_qualifier, parse_descriptors, DT_OTHER_SPEED_CONFIGURATION, DT_CS_INTERFACE};
+
+    const DEVICE: &'static [u8] = &[
+        0x12, 0x01, 0x00, 0x02, 0x00, 0x00, 0x00, 0x40, 0x81, 0x07, 0x51, 0x55, 0x00, 0x01, 0x01, 0x02, 0x03, 0x01,
+    ];
+
+    // A flash drive: one bulk-only mass storage interface.
+    fn mass_storage() -> ConfigDescriptorBuilder {
+        ConfigDescriptorBuilder::new(1).max_power(100)
+            .interface(InterfaceDescriptorBuilder::new(0).class_code(0x08).sub_class_code(0x06).protocol_code(0x50)
+                .endpoint(EndpointDescriptorBuilder::new(0x81, TransferType::Bulk).max_packet_size(512))
+                .endpoint(EndpointDescriptorBuilder::new(0x02, TransferType::Bulk).max_packet_size(512)))
+    }
+
+    // A CDC ACM serial port: an interface association, functional descriptors on the control
+    // interface and a data interface.
+    fn cdc_acm() -> ConfigDescriptorBuilder {
+        ConfigDescriptorBuilder::new(1).self_powered(true).max_power(100)
+            .association(InterfaceAssociationBuilder::new(0, 2).class_code(0x02).sub_class_code(0x02).protocol_code(0x01))
+            .interface(InterfaceDescriptorBuilder::new(0).class_code(0x02).sub_class_code(0x02).protocol_code(0x01)
+                .descriptor(DT_CS_INTERFACE, &[0x00, 0x10, 0x01])
+                .descriptor(DT_CS_INTERFACE, &[0x01, 0x00, 0x01])
+                .descriptor(DT_CS_INTERFACE, &[0x02, 0x02])
+                .descriptor(DT_CS_INTERFACE, &[0x06, 0x00, 0x01])
+                .endpoint(EndpointDescriptorBuilder::new(0x83, TransferType::Interrupt).max_packet_size(8).interval(16)))
+            .interface(InterfaceDescriptorBuilder::new(1).class_code(0x0A)
+                .endpoint(EndpointDescriptorBuilder::new(0x81, TransferType::Bulk).max_packet_size(64))
+                .endpoint(EndpointDescriptorBuilder::new(0x02, TransferType::Bulk).max_packet_size(64)))
+    }
+
+    // An audio streaming interface with a zero-bandwidth setting and an isochronous endpoint that
+    // uses the 9 byte audio class endpoint descriptor.
+    const AUDIO_STREAMING: &'static [u8] = &[
+        0x09, 0x02, 0x32, 0x00, 0x01, 0x01, 0x00, 0x80, 0x32,
+        0x09, 0x04, 0x01, 0x00, 0x00, 0x01, 0x02, 0x00, 0x00,
+        0x09, 0x04, 0x01, 0x01, 0x01, 0x01, 0x02, 0x00, 0x00,
+        0x07, 0x24, 0x01, 0x01, 0x01, 0x01, 0x00,
+        0x09, 0x05, 0x01, 0x09, 0xC8, 0x00, 0x01, 0x00, 0x00,
+        0x07, 0x25, 0x01, 0x01, 0x00, 0x00, 0x00,
+    ];
+
+    // A SuperSpeed bulk interface with endpoint companion descriptors.
+    fn superspeed() -> ConfigDescriptorBuilder {
+        ConfigDescriptorBuilder::new(1).max_power(100)
+            .interface(InterfaceDescriptorBuilder::new(0).class_code(0xFF)
+                .endpoint(EndpointDescriptorBuilder::new(0x81, TransferType::Bulk).max_packet_size(1024).ss_companion(0x0F, 0x04, 0))
+                .endpoint(EndpointDescriptorBuilder::new(0x02, TransferType::Bulk).max_packet_size(1024).ss_companion(0x0F, 0x00, 0)))
+    }
+
+    fn fixtures() -> Vec<Vec<u8>> {
+        vec![mass_storage().build(), cdc_acm().build(), AUDIO_STREAMING.to_vec(), superspeed().build()]
+    }
+
+    fn error(result: ::Result<ConfigDescriptor>) -> String {
+        match result {
+            Err(::Error::Custom(message)) => message,
+            Err(e) => panic!("expected custom error, got {:?}", e),
+            Ok(config) => panic!("expected error, got {:?}", config),
+        }
+    }
+
+    fn with_total_length(bytes: &[u8], total_length: usize) -> Vec<u8> {
+        let mut bytes = bytes.to_vec();
+        bytes[2] = total_length as u8;
+        bytes[3] = (total_length >> 8) as u8;
+        bytes
+    }
+
+    // Device descriptors
+
+    #[test]
+    fn it_parses_device_descriptor() {
+        let device = parse_device(DEVICE).unwrap();
+
+        assert_eq!(Version(2, 0, 0), device.usb_version());
+        assert_eq!(64, device.max_packet_size());
+        assert_eq!(0x0781, device.vendor_id());
+        assert_eq!(0x5551, device.product_id());
+        assert_eq!(Version(1, 0, 0), device.device_version());
+        assert_eq!(Some(1), device.manufacturer_string_index());
+        assert_eq!(Some(2), device.product_string_index());
+        assert_eq!(Some(3), device.serial_number_string_index());
+        assert_eq!(1, device.num_configurations());
+    }
+
+    #[test]
+    fn it_ignores_bytes_after_device_descriptor() {
+        let mut bytes = DEVICE.to_vec();
+        bytes.extend_from_slice(&[0xFF, 0xFF]);
+
+        assert_eq!(0x0781, parse_device(&bytes).unwrap().vendor_id());
+    }
+
+    #[test]
+    fn it_rejects_short_device_descriptor() {
+        assert!(parse_device(&DEVICE[..17]).is_err());
+        assert!(parse_device(&[]).is_err());
+    }
+
+    #[test]
+    fn it_rejects_device_descriptor_with_short_length() {
+        let mut bytes = DEVICE.to_vec();
+        bytes[0] = 0x11;
+
+        assert!(parse_device(&bytes).is_err());
+    }
+
+    #[test]
+    fn it_rejects_device_descriptor_of_wrong_type() {
+        let mut bytes = DEVICE.to_vec();
+        bytes[1] = LIBUSB_DT_CONFIG;
+
+        assert!(parse_device(&bytes).is_err());
+    }
+
+    // Device qualifiers
+
+    #[test]
+    fn it_parses_device_qualifier() {
+        let qualifier = parse_device_qualifier(&[0x0A, 0x06, 0x00, 0x02, 0xEF, 0x02, 0x01, 0x40, 0x01, 0x00]).unwrap();
+
+        assert_eq!(Version(2, 0, 0), qualifier.usb_version());
+        assert_eq!(0xEF, qualifier.class_code());
+        assert_eq!(0x02, qualifier.sub_class_code());
+        assert_eq!(0x01, qualifier.protocol_code());
+        assert_eq!(64, qualifier.max_packet_size());
+        assert_eq!(1, qualifier.num_configurations());
+    }
+
+    #[test]
+    fn it_rejects_short_device_qualifier() {
+        assert!(parse_device_qualifier(&[0x0A, 0x06, 0x00, 0x02, 0xEF, 0x02, 0x01, 0x40, 0x01]).is_err());
+    }
+
+    #[test]
+    fn it_rejects_device_qualifier_of_wrong_type() {
+        assert!(parse_device_qualifier(&[0x0A, 0x01, 0x00, 0x02, 0xEF, 0x02, 0x01, 0x40, 0x01, 0x00]).is_err());
+    }
+
+    // Configuration headers
+
+    #[test]
+    fn it_parses_config_header() {
+        let config = build_config(cdc_acm());
+
+        assert_eq!(1, config.number());
+        assert_eq!(100, config.max_power());
+        assert!(config.self_powered());
+        assert!(!config.remote_wakeup());
+        assert_eq!(None, config.description_string_index());
+        assert_eq!(2, config.num_interfaces());
+    }
+
+    #[test]
+    fn it_parses_other_speed_config() {
+        let mut bytes = mass_storage().build();
+        bytes[1] = DT_OTHER_SPEED_CONFIGURATION;
+
+        assert_eq!(1, parse_config(&bytes, DT_OTHER_SPEED_CONFIGURATION).unwrap().num_interfaces());
+        assert_eq!(1, ConfigDescriptor::from_bytes(&bytes).unwrap().num_interfaces());
+        assert!(parse_config(&bytes, LIBUSB_DT_CONFIG).is_err());
+    }
+
+    #[test]
+    fn it_parses_config_without_interfaces() {
+        let config = build_config(ConfigDescriptorBuilder::new(1));
+
+        assert_eq!(0, config.num_interfaces());
+        assert_eq!(0, config.interfaces().count());
+    }
+
+    #[test]
+    fn it_ignores_bytes_after_total_length() {
+        let mut bytes = mass_storage().build();
+        bytes.extend_from_slice(&[0x00, 0xFF, 0x13]);
+
+        assert_eq!(1, parse_config(&bytes, LIBUSB_DT_CONFIG).unwrap().num_interfaces());
+    }
+
+    #[test]
+    fn it_accepts_longer_config_header() {
+        let mut bytes = vec![0x0A, 0x02, 0x0A, 0x00, 0x00, 0x01, 0x00, 0x80, 0x32, 0xEE];
+        bytes.extend_from_slice(&mass_storage().build()[9..]);
+        let total_length = bytes.len();
+        let bytes = with_total_length(&bytes, total_length);
+
+        assert_eq!(1, parse_config(&bytes, LIBUSB_DT_CONFIG).unwrap().num_interfaces());
+    }
+
+    // Interfaces and endpoints
+
+    #[test]
+    fn it_parses_endpoints() {
+        let config = build_config(mass_storage());
+        let setting = config.interfaces().next().unwrap().descriptors().next().unwrap();
+        let endpoints: Vec<_> = setting.endpoint_descriptors().map(|endpoint| {
+            (endpoint.address(), endpoint.direction(), endpoint.transfer_type(), endpoint.max_packet_size())
+        }).collect();
+
+        assert_eq!(0x08, setting.class_code());
+        assert_eq!(0x06, setting.sub_class_code());
+        assert_eq!(0x50, setting.protocol_code());
+        assert_eq!(vec![(0x81, Direction::In, TransferType::Bulk, 512),
+                        (0x02, Direction::Out, TransferType::Bulk, 512)], endpoints);
+    }
+
+    #[test]
+    fn it_parses_multiple_interfaces() {
+        let config = build_config(cdc_acm());
+        let classes: Vec<(u8, u8, u8)> = config.interfaces().map(|interface| {
+            let setting = interface.descriptors().next().unwrap();
+            (interface.number(), setting.class_code(), setting.num_endpoints())
+        }).collect();
+
+        assert_eq!(vec![(0, 0x02, 1), (1, 0x0A, 2)], classes);
+    }
+
+    #[test]
+    fn it_groups_alternate_settings_by_interface_number() {
+        let config = parse_config(AUDIO_STREAMING, LIBUSB_DT_CONFIG).unwrap();
+        let interfaces: Vec<_> = config.interfaces().collect();
+        let settings: Vec<(u8, u8)> = interfaces[0].descriptors().map(|setting| (setting.setting_number(), setting.num_endpoints())).collect();
+
+        assert_eq!(1, interfaces.len());
+        assert_eq!(1, interfaces[0].number());
+        assert_eq!(vec![(0, 0), (1, 1)], settings);
+    }
+
+    #[test]
+    fn it_groups_interleaved_alternate_settings() {
+        let config = build_config(ConfigDescriptorBuilder::new(1)
+            .interface(InterfaceDescriptorBuilder::new(0).class_code(0xFF))
+            .interface(InterfaceDescriptorBuilder::new(1).class_code(0xFF))
+            .interface(InterfaceDescriptorBuilder::new(0).setting_number(1).class_code(0xFF))
+            .interface(InterfaceDescriptorBuilder::new(1).setting_number(1).class_code(0xFF)));
+
+        let settings: Vec<(u8, usize)> = config.interfaces().map(|interface| (interface.number(), interface.descriptors().count())).collect();
+
+        assert_eq!(vec![(0, 2), (1, 2)], settings);
+    }
+
+    #[test]
+    fn it_parses_audio_endpoint_fields() {
+        let config = parse_config(AUDIO_STREAMING, LIBUSB_DT_CONFIG).unwrap();
+        let interface = config.interfaces().next().unwrap();
+        let setting = interface.descriptors().nth(1).unwrap();
+        let endpoint = setting.endpoint_descriptors().next().unwrap();
+
+        assert_eq!(0x01, endpoint.address());
+        assert_eq!(TransferType::Isochronous, endpoint.transfer_type());
+        assert_eq!(200, endpoint.max_packet_size());
+        assert_eq!(1, endpoint.interval());
+    }
+
+    #[test]
+    fn it_counts_parsed_interfaces_instead_of_header_field() {
+        let mut bytes = mass_storage().build();
+        bytes[4] = 3;
+
+        assert_eq!(1, parse_config(&bytes, LIBUSB_DT_CONFIG).unwrap().num_interfaces());
+    }
+
+    #[test]
+    fn it_counts_parsed_endpoints_instead_of_interface_field() {
+        let mut bytes = mass_storage().build();
+        bytes[13] = 5;
+
+        let config = parse_config(&bytes, LIBUSB_DT_CONFIG).unwrap();
+        let setting = config.interfaces().next().unwrap().descriptors().next().unwrap();
+
+        assert_eq!(2, setting.num_endpoints());
+        assert_eq!(2, setting.endpoint_descriptors().count());
+    }
+
+    // Extra descriptors
+
+    #[test]
+    fn it_keeps_class_descriptors_with_endpoint() {
+        let config = build_config(superspeed());
+        let interface = config.interfaces().next().unwrap();
+        let setting = interface.descriptors().next().unwrap();
+        let streams: Vec<u32> = setting.endpoint_descriptors().map(|endpoint| endpoint_descriptor::max_streams(&endpoint)).collect();
+
+        assert_eq!(vec![16, 0], streams);
+    }
+
+    #[test]
+    fn it_keeps_descriptors_before_first_interface_with_config() {
+        let config = build_config(cdc_acm());
+
+        assert_eq!(&cdc_acm().build()[9..17], config.extra());
+    }
+
+    #[test]
+    fn it_keeps_class_descriptors_with_interface() {
+        let config = build_config(cdc_acm());
+        let interface = config.interfaces().next().unwrap();
+        let setting = interface.descriptors().next().unwrap();
+        let subtypes: Vec<u8> = setting.extra_descriptors().map(|descriptor| descriptor.body()[0]).collect();
+
+        assert_eq!(&cdc_acm().build()[26..45], setting.extra());
+        assert_eq!(vec![0x00, 0x01, 0x02, 0x06], subtypes);
+        assert!(setting.endpoint_descriptors().next().unwrap().extra().is_empty());
+    }
+
+    #[test]
+    fn it_keeps_class_descriptors_with_each_alternate_setting() {
+        let config = parse_config(AUDIO_STREAMING, LIBUSB_DT_CONFIG).unwrap();
+        let interface = config.interfaces().next().unwrap();
+        let extras: Vec<&[u8]> = interface.descriptors().map(|setting| setting.extra()).collect();
+        let setting = interface.descriptors().nth(1).unwrap();
+
+        assert_eq!(vec![&[][..], &AUDIO_STREAMING[27..34]], extras);
+        assert_eq!(&AUDIO_STREAMING[43..50], setting.endpoint_descriptors().next().unwrap().extra());
+    }
+
+    #[test]
+    fn it_keeps_endpoint_before_interface_with_config() {
+        let bytes = [
+            0x09, 0x02, 0x10, 0x00, 0x00, 0x01, 0x00, 0x80, 0x32,
+            0x07, 0x05, 0x81, 0x02, 0x40, 0x00, 0x00,
+        ];
+
+        assert_eq!(&bytes[9..], parse_config(&bytes, LIBUSB_DT_CONFIG).unwrap().extra());
+    }
+
+    #[test]
+    fn it_keeps_endpoint_before_interface_out_of_interfaces() {
+        let bytes = [
+            0x09, 0x02, 0x10, 0x00, 0x00, 0x01, 0x00, 0x80, 0x32,
+            0x07, 0x05, 0x81, 0x02, 0x40, 0x00, 0x00,
+        ];
+        let config = parse_config(&bytes, LIBUSB_DT_CONFIG).unwrap();
+
+        assert_eq!(0, config.num_interfaces());
+    }
+
+    #[test]
+    fn it_rejects_empty_config() {
+        assert!(error(parse_config(&[], LIBUSB_DT_CONFIG)).contains("needs at least 9 bytes"));
+    }
+
+    #[test]
+    fn it_rejects_config_of_wrong_type() {
+        assert!(error(parse_config(DEVICE, LIBUSB_DT_CONFIG)).contains("got type 0x01"));
+    }
+
+    #[test]
+    fn it_rejects_config_header_with_short_length() {
+        let mut bytes = mass_storage().build();
+        bytes[0] = 0x08;
+
+        assert!(error(parse_config(&bytes, LIBUSB_DT_CONFIG)).contains("bLength 8"));
+    }
+
+    #[test]
+    fn it_rejects_config_header_longer_than_input() {
+        let bytes = [0xFF, 0x02, 0x09, 0x00, 0x00, 0x01, 0x00, 0x80, 0x32];
+
+        assert!(error(parse_config(&bytes, LIBUSB_DT_CONFIG)).contains("bLength 255"));
+    }
+
+    #[test]
+    fn it_rejects_total_length_shorter_than_header() {
+        let bytes = with_total_length(&mass_storage().build(), 5);
+
+        assert!(error(parse_config(&bytes, LIBUSB_DT_CONFIG)).contains("wTotalLength 5"));
+    }
+
+    #[test]
+    fn it_rejects_truncated_config() {
+        let bytes = mass_storage().build();
+        let message = error(parse_config(&bytes[..bytes.len() - 1], LIBUSB_DT_CONFIG));
+
+        assert!(message.contains("wTotalLength 32"));
+        assert!(message.contains("31 bytes"));
+    }
+
+    #[test]
+    fn it_rejects_zero_length_descriptor() {
+        let mut bytes = mass_storage().build();
+        bytes[9] = 0x00;
+
+        assert!(error(parse_config(&bytes, LIBUSB_DT_CONFIG)).contains("offset 9 has invalid bLength 0"));
+    }
+
+    #[test]
+    fn it_rejects_one_byte_descriptor() {
+        let mut bytes = mass_storage().build();
+        bytes[18] = 0x01;
+
+        assert!(error(parse_config(&bytes, LIBUSB_DT_CONFIG)).contains("offset 18 has invalid bLength 1"));
+    }
+
+    #[test]
+    fn it_rejects_descriptor_overrunning_total_length() {
+        let mut bytes = mass_storage().build();
+        bytes[25] = 0x08;
+
+        assert!(error(parse_config(&bytes, LIBUSB_DT_CONFIG)).contains("offset 25 has bLength 8"));
+    }
+
+    #[test]
+    fn it_rejects_trailing_byte() {
+        let mut bytes = mass_storage().build();
+        bytes.push(0x00);
+        let total_length = bytes.len();
+        let bytes = with_total_length(&bytes, total_length);
+
+        assert!(error(parse_config(&bytes, LIBUSB_DT_CONFIG)).contains("trailing byte at offset 32"));
+    }
+
+    #[test]
+    fn it_rejects_short_interface_descriptor() {
+        let bytes = [
+            0x09, 0x02, 0x11, 0x00, 0x01, 0x01, 0x00, 0x80, 0x32,
+            0x08, 0x04, 0x00, 0x00, 0x00, 0xFF, 0x00, 0x00,
+        ];
+
+        assert!(error(parse_config(&bytes, LIBUSB_DT_CONFIG)).contains("interface descriptor at offset 9"));
+    }
+
+    #[test]
+    fn it_rejects_short_endpoint_descriptor() {
+        let bytes = [
+            0x09, 0x02, 0x18, 0x00, 0x01, 0x01, 0x00, 0x80, 0x32,
+            0x09, 0x04, 0x00, 0x00, 0x01, 0xFF, 0x00, 0x00, 0x00,
+            0x06, 0x05, 0x81, 0x02, 0x40, 0x00,
+        ];
+
+        assert!(error(parse_config(&bytes, LIBUSB_DT_CONFIG)).contains("endpoint descriptor at offset 18"));
+    }
+
+    #[test]
+    fn it_handles_every_truncation() {
+        for fixture in &fixtures() {
+            for len in 0..fixture.len() {
+                assert!(parse_config(&fixture[..len], LIBUSB_DT_CONFIG).is_err());
+
+                if len >= 9 {
+                    let _ = parse_config(&with_total_length(&fixture[..len], len), LIBUSB_DT_CONFIG);
+                }
+            }
+
+            assert!(parse_config(fixture, LIBUSB_DT_CONFIG).is_ok());
+        }
+    }
+
+    #[test]
+    fn it_handles_every_single_byte_corruption() {
+        for fixture in &fixtures() {
+            for offset in 0..fixture.len() {
+                for &value in &[0x00, 0x01, 0x02, 0x04, 0x05, 0x07, 0x09, 0x0B, 0x24, 0x30, 0x7F, 0xFF] {
+                    let mut bytes = fixture.to_vec();
+                    bytes[offset] = value;
+
+                    if let Ok(config) = parse_config(&bytes, LIBUSB_DT_CONFIG) {
+                        for interface in config.interfaces() {
+                            for setting in interface.descriptors() {
+                                assert_eq!(setting.num_endpoints() as usize, setting.endpoint_descriptors().count());
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    // Descriptor sets
+
+    #[test]
+    fn it_parses_device_with_configurations() {
+        let mut bytes = DEVICE.to_vec();
+        bytes.extend_from_slice(&mass_storage().build());
+        bytes.extend_from_slice(&cdc_acm().build());
+
+        let (device, configs) = parse_descriptors(&bytes).unwrap();
+        let interfaces: Vec<u8> = configs.iter().map(|config| config.num_interfaces()).collect();
+
+        assert_eq!(0x0781, device.vendor_id());
+        assert_eq!(vec![1, 2], interfaces);
+    }
+
+    #[test]
+    fn it_parses_device_without_configurations() {
+        let (_, configs) = parse_descriptors(DEVICE).unwrap();
+
+        assert!(configs.is_empty());
+    }
+
+    #[test]
+    fn it_reports_offset_of_malformed_configuration() {
+        let mut bytes = DEVICE.to_vec();
+        bytes.extend_from_slice(&mass_storage().build());
+        bytes.extend_from_slice(&cdc_acm().build()[..20]);
+
+        match parse_descriptors(&bytes) {
+            Err(::Error::Custom(message)) => assert!(message.contains("offset 50")),
+            _ => panic!("expected custom error"),
+        }
+    }
+
+    #[test]
+    fn it_rejects_trailing_bytes_after_configurations() {
+        let mut bytes = DEVICE.to_vec();
+        bytes.extend_from_slice(&mass_storage().build());
+        bytes.extend_from_slice(&[0x09, 0x02]);
+
+        assert!(parse_descriptors(&bytes).is_err());
+    }
+
+    #[test]
+    fn it_rejects_descriptors_without_device() {
+        assert!(parse_descriptors(&mass_storage().build()).is_err());
+    }
+
+    #[test]
+    fn it_handles_every_single_byte_corruption_of_descriptor_set() {
+        let mut bytes = DEVICE.to_vec();
+        bytes.extend_from_slice(&mass_storage().build());
+        bytes.extend_from_slice(&superspeed().build());
+
+        for offset in 0..bytes.len() {
+            for &value in &[0x00, 0x01, 0x02, 0x09, 0xFF] {
+                let mut corrupted = bytes.clone();
+                corrupted[offset] = value;
+
+                let _ = parse_descriptors(&corrupted);
+            }
+        }
+    }
+}
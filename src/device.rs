@@ -8,6 +8,7 @@ use context::Context;
 use device_handle::{self, DeviceHandle};
 use device_descriptor::{self, DeviceDescriptor};
 use config_descriptor::{self, ConfigDescriptor};
+use endpoint_query::{self, EndpointQuery};
 use fields::{self, Speed};
 
 
@@ -65,6 +66,13 @@ impl<'ctx, Io> Device<'ctx, Io>
         Ok(unsafe { config_descriptor::from_libusb(config) })
     }
 
+    /// Starts a query for the device's endpoints.
+    ///
+    /// See [`EndpointQuery`](struct.EndpointQuery.html) for the available criteria.
+    pub fn find_endpoints<'d>(&'d self) -> EndpointQuery<'d, 'ctx, Io> {
+        endpoint_query::from_device(self)
+    }
+
     /// Returns the number of the bus that the device is connected to.
     pub fn bus_number(&self) -> u8 {
         unsafe {
@@ -2,7 +2,10 @@ use std::fmt;
 
 use libusb::*;
 
+use class_code::Class;
+use usb_ids::UsbIds;
 use fields::Version;
+use descriptor_parser;
 
 /// Describes a device.
 pub struct DeviceDescriptor {
@@ -10,6 +13,17 @@ pub struct DeviceDescriptor {
 }
 
 impl DeviceDescriptor {
+    /// Parses a device descriptor from its raw bytes.
+    ///
+    /// Bytes after the descriptor's `bLength` are ignored.
+    ///
+    /// ## Errors
+    ///
+    /// * `Custom` if the bytes don't hold a device descriptor.
+    pub fn from_bytes(bytes: &[u8]) -> ::Result<Self> {
+        descriptor_parser::parse_device(bytes)
+    }
+
     /// Returns the device's maximum supported USB version.
     pub fn usb_version(&self) -> Version {
         Version::from_bcd(self.descriptor.bcdUSB)
@@ -59,6 +73,11 @@ impl DeviceDescriptor {
         self.descriptor.bDeviceProtocol
     }
 
+    /// Returns the device's class, sub class and protocol codes as a [`Class`](struct.Class.html).
+    pub fn class(&self) -> Class {
+        Class::new(self.class_code(), self.sub_class_code(), self.protocol_code())
+    }
+
     /// Returns the device's vendor ID.
     pub fn vendor_id(&self) -> u16 {
         self.descriptor.idVendor
@@ -69,6 +88,18 @@ impl DeviceDescriptor {
         self.descriptor.idProduct
     }
 
+    /// Looks up the name of the device's vendor in the USB ID database.
+    pub fn vendor_name<'a>(&self, ids: &'a UsbIds) -> Option<&'a str> {
+        ids.vendor_name(self.vendor_id())
+    }
+
+    /// Looks up the name of the device's product in the USB ID database.
+    ///
+    /// Unlike the product string descriptor, the name doesn't require opening the device.
+    pub fn product_name<'a>(&self, ids: &'a UsbIds) -> Option<&'a str> {
+        ids.product_name(self.vendor_id(), self.product_id())
+    }
+
     /// Returns the maximum packet size of the device's first endpoint.
     pub fn max_packet_size(&self) -> u8 {
         self.descriptor.bMaxPacketSize0
@@ -178,6 +209,16 @@ mod test {
         assert_eq!(42, super::from_libusb(device_descriptor!(idProduct: 42)).product_id());
     }
 
+    #[test]
+    fn it_looks_up_vendor_and_product_names() {
+        let ids = ::usb_ids::UsbIds::parse("1d6b  Linux Foundation\n\t0002  2.0 root hub\n");
+        let descriptor = super::from_libusb(device_descriptor!(idVendor: 0x1d6b, idProduct: 0x0002));
+
+        assert_eq!(Some("Linux Foundation"), descriptor.vendor_name(&ids));
+        assert_eq!(Some("2.0 root hub"), descriptor.product_name(&ids));
+        assert_eq!(None, super::from_libusb(device_descriptor!(idVendor: 0x1d6b, idProduct: 0x0042)).product_name(&ids));
+    }
+
     #[test]
     fn it_has_max_packet_size() {
         assert_eq!(42, super::from_libusb(device_descriptor!(bMaxPacketSize0: 42)).max_packet_size());
@@ -1,5 +1,8 @@
 use std::marker::PhantomData;
 use std::mem;
+use std::ptr;
+use std::sync::Mutex;
+use std::time::Duration;
 
 use bit_set::BitSet;
 use libc::c_int;
@@ -7,6 +10,13 @@ use libusb::*;
 
 use io::IoType;
 use context::Context;
+use config_descriptor::{self, ConfigDescriptor};
+use endpoint_descriptor;
+use fields::TransferType;
+use stream_set::{self, StreamSet};
+use claimed_interface::{self, ClaimedInterface, KernelDriver};
+use bos_descriptor::BosDescriptor;
+use device_handle_sync_api::DeviceHandleSyncApi;
 use error;
 
 
@@ -17,7 +27,21 @@ pub struct DeviceHandle<'ctx, Io>
     context: PhantomData<&'ctx Context<Io>>,
     io_handle: <Io as IoType<'ctx>>::Handle,
     handle: *mut libusb_device_handle,
-    interfaces: BitSet,
+    interfaces: Mutex<BitSet>,
+    stall_recovery: StallRecovery,
+}
+
+/// What a device handle does when a bulk or interrupt endpoint stalls.
+#[derive(Debug,PartialEq,Eq,Clone,Copy,Hash)]
+pub enum StallRecovery {
+    /// Return `Pipe` to the caller, leaving the endpoint halted (default).
+    Disabled,
+
+    /// Clear the endpoint's halt condition and retry the transfer once.
+    ///
+    /// The transfer's error is returned if the halt can't be cleared or if the retried transfer
+    /// fails too.
+    ClearHaltAndRetry,
 }
 
 impl<'ctx, Io> Drop for DeviceHandle<'ctx, Io>
@@ -26,7 +50,7 @@ impl<'ctx, Io> Drop for DeviceHandle<'ctx, Io>
     /// Closes the device.
     fn drop(&mut self) {
         unsafe {
-            for iface in self.interfaces.iter() {
+            for iface in self.interfaces.get_mut().expect("Could not get claimed interfaces").iter() {
                 libusb_release_interface(self.handle, iface as c_int);
             }
             libusb_close(self.handle);
@@ -99,55 +123,242 @@ impl<'ctx, Io> DeviceHandle<'ctx, Io>
     /// when the device handle goes out of scope.
     pub fn claim_interface(&mut self, iface: u8) -> ::Result<()> {
         try_unsafe!(libusb_claim_interface(self.handle, iface as c_int));
-        self.interfaces.insert(iface as usize);
+        self.interfaces.get_mut().expect("Could not get claimed interfaces").insert(iface as usize);
         Ok(())
     }
 
     /// Releases a claimed interface.
     pub fn release_interface(&mut self, iface: u8) -> ::Result<()> {
         try_unsafe!(libusb_release_interface(self.handle, iface as c_int));
-        self.interfaces.remove((iface as usize));
+        self.interfaces.get_mut().expect("Could not get claimed interfaces").remove(iface as usize);
         Ok(())
     }
 
+    /// Claims one of the device's interfaces, returning a guard that releases it.
+    ///
+    /// The interface is released when the returned guard goes out of scope. Alternate settings and
+    /// endpoint I/O can be done through the guard.
+    ///
+    /// ## Errors
+    ///
+    /// * `Busy` if the interface is already claimed through this handle or by another program.
+    pub fn claim<'dh>(&'dh self, iface: u8) -> ::Result<ClaimedInterface<'dh, 'ctx, Io>> {
+        self.claim_with(iface, false)
+    }
+
+    /// Claims one of the device's interfaces, detaching its kernel driver first.
+    ///
+    /// If a kernel driver is attached to the interface, it is detached before the interface is
+    /// claimed and reattached after the interface is released. `libusb`'s auto-detach feature is
+    /// used where it is available. On platforms without kernel driver support this behaves like
+    /// [`claim`](#method.claim).
+    ///
+    /// ## Errors
+    ///
+    /// * `Busy` if the interface is already claimed through this handle or by another program.
+    pub fn claim_detaching_kernel_driver<'dh>(&'dh self, iface: u8) -> ::Result<ClaimedInterface<'dh, 'ctx, Io>> {
+        self.claim_with(iface, true)
+    }
+
+    fn claim_with<'dh>(&'dh self, iface: u8, detach: bool) -> ::Result<ClaimedInterface<'dh, 'ctx, Io>> {
+        let mut interfaces = self.interfaces.lock().expect("Could not lock claimed interfaces");
+
+        if interfaces.contains(iface as usize) {
+            return Err(::Error::Busy);
+        }
+
+        let kernel_driver = if detach {
+            self.detach_for_claim(iface)?
+        } else {
+            KernelDriver::Untouched
+        };
+
+        let res = unsafe { libusb_claim_interface(self.handle, iface as c_int) };
+
+        if kernel_driver == KernelDriver::AutoDetached {
+            unsafe { libusb_set_auto_detach_kernel_driver(self.handle, 0) };
+        }
+
+        if res < 0 {
+            if kernel_driver == KernelDriver::Detached {
+                unsafe { libusb_attach_kernel_driver(self.handle, iface as c_int) };
+            }
+            return Err(error::from_libusb(res));
+        }
+
+        interfaces.insert(iface as usize);
+        Ok(claimed_interface::from_libusb(self, iface, kernel_driver))
+    }
+
+    /// Prepares an interface's kernel driver to be detached when the interface is claimed.
+    fn detach_for_claim(&self, iface: u8) -> ::Result<KernelDriver> {
+        match unsafe { libusb_set_auto_detach_kernel_driver(self.handle, 1) } {
+            LIBUSB_SUCCESS => return Ok(KernelDriver::AutoDetached),
+            LIBUSB_ERROR_NOT_SUPPORTED => (),
+            err => return Err(error::from_libusb(err)),
+        }
+
+        match self.kernel_driver_active(iface) {
+            Ok(true) => {
+                try_unsafe!(libusb_detach_kernel_driver(self.handle, iface as c_int));
+                Ok(KernelDriver::Detached)
+            },
+            Ok(false) | Err(::Error::NotSupported) => Ok(KernelDriver::Untouched),
+            Err(e) => Err(e),
+        }
+    }
+
     /// Sets an interface's active setting.
     pub fn set_alternate_setting(&mut self, iface: u8, setting: u8) -> ::Result<()> {
-        try_unsafe!(libusb_set_interface_alt_setting(self.handle, iface as c_int, setting as c_int));
+        set_alternate_setting(self, iface, setting)
+    }
+
+    /// Clears the halt condition of an endpoint.
+    ///
+    /// An endpoint halts (stalls) when the device can't process a transfer, which is reported as a
+    /// `Pipe` error. No transfers to the endpoint succeed until the halt is cleared.
+    pub fn clear_halt(&self, endpoint: u8) -> ::Result<()> {
+        try_unsafe!(libusb_clear_halt(self.handle, endpoint));
         Ok(())
     }
+
+    /// Returns how the handle reacts to stalled bulk and interrupt endpoints.
+    pub fn stall_recovery(&self) -> StallRecovery {
+        self.stall_recovery
+    }
+
+    /// Sets how the handle reacts to stalled bulk and interrupt endpoints.
+    ///
+    /// By default a stalled endpoint makes the transfer fail with `Pipe`. With
+    /// `StallRecovery::ClearHaltAndRetry` the handle clears the halt and retries the transfer once.
+    pub fn set_stall_recovery(&mut self, stall_recovery: StallRecovery) {
+        self.stall_recovery = stall_recovery;
+    }
+
+    /// Runs a bulk or interrupt transfer, applying the stall recovery policy.
+    fn recover_stall<F>(&self, endpoint: u8, mut transfer: F) -> ::Result<usize>
+        where F: FnMut() -> ::Result<usize>,
+    {
+        match transfer() {
+            Err(::Error::Pipe) if self.stall_recovery == StallRecovery::ClearHaltAndRetry => {
+                warn!("endpoint {:#04x} stalled, clearing halt and retrying", endpoint);
+                if let Err(e) = self.clear_halt(endpoint) {
+                    error!("could not clear halt on endpoint {:#04x}: {}", endpoint, e);
+                    return Err(::Error::Pipe);
+                }
+                transfer()
+            },
+            res => res,
+        }
+    }
+
+    /// Allocates bulk streams on SuperSpeed endpoints.
+    ///
+    /// Allocates `num_streams` streams on each endpoint in `endpoints`. The endpoints must be bulk
+    /// endpoints of an interface in the active configuration, and the interface must be claimed.
+    /// The streams are freed when the returned stream set goes out of scope.
+    ///
+    /// ## Errors
+    ///
+    /// * `InvalidParam` if `num_streams` or `endpoints` is empty, if an endpoint is not a bulk
+    ///   endpoint, or if an endpoint's SuperSpeed endpoint companion descriptor allows fewer than
+    ///   `num_streams` streams.
+    /// * `NotFound` if an endpoint is not part of the active configuration.
+    /// * `NotSupported` if the platform or the host controller does not support streams.
+    pub fn alloc_streams<'dh>(&'dh self, num_streams: u32, endpoints: &[u8]) -> ::Result<StreamSet<'dh, 'ctx, Io>> {
+        if num_streams == 0 || endpoints.is_empty() {
+            return Err(::Error::InvalidParam);
+        }
+
+        for &endpoint in endpoints {
+            let max_streams = self.max_streams(endpoint)?;
+            if num_streams > max_streams {
+                debug!("endpoint {:#04x} supports {} streams, {} requested", endpoint, max_streams, num_streams);
+                return Err(::Error::InvalidParam);
+            }
+        }
+
+        let mut endpoints = endpoints.to_vec();
+        let n = unsafe { libusb_alloc_streams(self.handle, num_streams, endpoints.as_mut_ptr(), endpoints.len() as c_int) };
+
+        if n < 0 {
+            Err(error::from_libusb(n))
+        } else {
+            Ok(stream_set::from_libusb(self, endpoints, n as u32))
+        }
+    }
+
+    /// Returns the number of streams supported by an endpoint of the active configuration.
+    fn max_streams(&self, address: u8) -> ::Result<u32> {
+        let config = active_config_descriptor(self)?;
+
+        for interface in config.interfaces() {
+            for setting in interface.descriptors() {
+                for endpoint in setting.endpoint_descriptors() {
+                    if endpoint.address() == address {
+                        if endpoint.transfer_type() != TransferType::Bulk {
+                            return Err(::Error::InvalidParam);
+                        }
+                        return Ok(endpoint_descriptor::max_streams(&endpoint));
+                    }
+                }
+            }
+        }
+
+        Err(::Error::NotFound)
+    }
+}
+
+impl<'ctx, Io> DeviceHandle<'ctx, Io>
+    where Io: IoType<'ctx>,
+          DeviceHandle<'ctx, Io>: DeviceHandleSyncApi,
+{
+    /// Reads the device's Binary Object Store descriptor.
+    ///
+    /// This is a shorthand for `DeviceHandleSyncApi::read_bos_descriptor`, which documents the
+    /// errors returned.
+    pub fn bos_descriptor(&self, timeout: Duration) -> ::Result<BosDescriptor> {
+        self.read_bos_descriptor(timeout)
+    }
 }
 
 mod async_api {
     use std::slice;
-    use std::time::Duration;
-    use libc::c_uint;
     use libusb::*;
     use io::{IoType, AsyncIoType, AsyncIoTransferBuilderType};
+    use transfer_options::{TransferOptions, check_no_stream_id};
+    use setup_packet::SetupPacket;
     use super::DeviceHandle;
 
-    macro_rules! fcsm {
-        (       ;$($v:expr),*) => {};
-        ($e:expr;$($v:expr),*) => { unsafe{_libusb_fill_control_setup($($v),*)} };
+    macro_rules! prep {
+        (       ;$buf:expr;$($v:expr),*) => { $buf };
+        ($f:ident;$buf:expr;$($v:expr),*) => { $f($buf, $($v),*)? };
     }
     macro_rules! tb {
-        ($( $fn_nam:ident {$($var:ident : $typ:ty),*} $fill:ident  {$($v1:ident),*} {$($len:ident),*} {$($nip:ident),*} {$($znip:expr),*} {$($fcs:expr),*} )*) => {
+        ($( $(#[$attr:meta])* $fn_nam:ident {$($var:ident : $typ:ty),*} $fill:ident  {$($v1:ident),*} {$($len:ident),*} {$($nip:ident),*} {$($znip:expr),*} {$($prep:ident),*} )*) => {
 
             impl<'ctx, 'dh, Io> DeviceHandle<'ctx, Io>
                 where Io: IoType<'ctx>,
                       <Io as IoType<'ctx>>::Handle: AsyncIoType<'ctx, 'dh>
             {$(
+                $(#[$attr])*
                 #[allow(non_snake_case)]
-                pub fn $fn_nam<F>(&'dh self, buf: Vec<u8>, timeout: Duration, callback: Option<F>, $( $var: $typ ),*) -> ::Result<<<Io as IoType<'ctx>>::Handle as AsyncIoType<'ctx, 'dh>>::TransferHandle>
+                pub fn $fn_nam<F, O>(&'dh self, buf: Vec<u8>, options: O, callback: Option<F>, $( $var: $typ ),*) -> ::Result<<<Io as IoType<'ctx>>::Handle as AsyncIoType<'ctx, 'dh>>::TransferHandle>
                     where     F: FnMut(<<Io as IoType<'ctx>>::Handle as AsyncIoType<'ctx, 'dh>>::TransferCallbackData) -> <<Io as IoType<'ctx>>::Handle as AsyncIoType<'ctx, 'dh>>::TransferCallbackResult,
                               F: 'static,
+                              O: Into<TransferOptions>,
                 {
                     // debug!("BUF: {:?}", buf);
-                    let timeout_ms = (timeout.as_secs() * 1000 + timeout.subsec_nanos() as u64 / 1_000_000) as c_uint;
+                    let options = options.into();
+                    check_no_stream_id(&options)?;
+                    let buf = prep!($($prep),* ; buf ; $($var),*);
                     let ar = self.io_handle.allocate(&self.handle, callback.map(|x| Box::new(x) as Box<_>), buf);
                     // debug!("{:?}", ar);
                     let tr = unsafe { libusb_alloc_transfer( $($nip),* $($znip),* ) };
-                    fcsm!($($fcs),* ; ar.buf_ptr, $($var),*);
-                    unsafe { $fill(tr, self.handle, $($v1,)* ar.buf_ptr, $(ar.$len,)* $($nip,)* ar.callback, ar.user_data_ptr, timeout_ms); }
+                    unsafe {
+                        $fill(tr, self.handle, $($v1,)* ar.buf_ptr, $(ar.$len,)* $($nip,)* ar.callback, ar.user_data_ptr, options.timeout_ms());
+                        (*tr).flags = options.libusb_flags();
+                    }
                     let res = ar.builder.submit(tr);
                     if let Err(ref e) = res {
                         error!("Error submitting: {:?} ; {:?} ; buf: {:?}",
@@ -162,22 +373,51 @@ mod async_api {
         }
     }
 
-    tb!(control      {bmRequestType: u8, bRequest: u8, wValue: u16, wIndex: u16 , wLength: u16}   _libusb_fill_control_transfer     {}                     {}     {}                 {0} {0}
+    tb!(
+        /// Submits a control transfer.
+        ///
+        /// `buf` is the data stage: the bytes to send for outbound requests, or the space to
+        /// receive into for inbound requests, so its length must match the setup packet's
+        /// `wLength`. The buffer passed to the callback starts with the 8-byte setup packet,
+        /// followed by the data stage, so received data begins at offset `SetupPacket::SIZE`.
+        /// The callback's `actual_length` counts only the data stage.
+        control      {setup: SetupPacket }                                                        _libusb_fill_control_transfer     {}                     {}     {}                 {0} {control_buffer}
+        /// Submits an isochronous transfer.
         isochronous  {endpoint: u8, num_iso_packets: i32 }                                        _libusb_fill_iso_transfer         {endpoint}             {len}  {num_iso_packets}  {}  {}
+        /// Submits an interrupt transfer.
         interrupt    {endpoint: u8 }                                                              _libusb_fill_interrupt_transfer   {endpoint}             {len}  {}                 {0} {}
+        /// Submits a bulk transfer.
         bulk         {endpoint: u8 }                                                              _libusb_fill_bulk_transfer        {endpoint}             {len}  {}                 {0} {}
+        /// Submits a bulk transfer on the stream given by `stream_id`.
         bulk_stream  {endpoint: u8, stream_id: u32 }                                              _libusb_fill_bulk_stream_transfer {endpoint, stream_id}  {len}  {}                 {0} {}
     );
+
+    /// Prepends the setup packet to the data stage of a control transfer.
+    ///
+    /// `data` holds the bytes to send for outbound requests and the space to receive into for
+    /// inbound requests, so its length must match the setup packet's `wLength`. The buffer handed
+    /// to the transfer's callback keeps the setup packet in its first 8 bytes, as it always has.
+    fn control_buffer(data: Vec<u8>, setup: SetupPacket) -> ::Result<Vec<u8>> {
+        if data.len() != setup.length() as usize {
+            return Err(format!("control transfer has {} data bytes, but setup packet's wLength is {}", data.len(), setup.length()).into());
+        }
+
+        let mut buf = Vec::with_capacity(SetupPacket::SIZE + data.len());
+        buf.extend_from_slice(&setup.to_bytes());
+        buf.extend_from_slice(&data);
+        Ok(buf)
+    }
 }
 
 #[cfg(any(target_os = "linux", target_os = "macos"))]
 mod unix_async_io {
     use std::time::Duration;
     use std::sync::mpsc::channel;
-    use std::mem::size_of;
     use libusb::*;
     use super::DeviceHandle;
     use device_handle_sync_api::DeviceHandleSyncApi;
+    use transfer_options::{TransferOptions, without_stream_id};
+    use setup_packet::SetupPacket;
     use io::unix_async::{UnixAsyncIo, UnixAsyncIoCallbackResult};
 
     enum BufVar<'a> {
@@ -186,28 +426,20 @@ mod unix_async_io {
     }
 
     impl<'ctx, 'dh> DeviceHandle<'ctx, UnixAsyncIo> {
-        #[inline] fn control_msg<'a>(&'dh self, request_type: u8, request: u8, value: u16, index: u16, buf_var: BufVar<'a>, timeout: Duration) -> ::Result<usize> {
+        #[inline] fn control_msg<'a>(&'dh self, request_type: u8, request: u8, value: u16, index: u16, buf_var: BufVar<'a>, options: TransferOptions) -> ::Result<usize> {
             let (snd, rcv) = channel();
             let callback = Some(move |dat| { snd.send(dat).expect("control message channel send error"); UnixAsyncIoCallbackResult::Handled });
-            let csl = size_of::<libusb_control_setup>();
-            let (v,s) = match buf_var {
-                BufVar::In(ref buf) => {
-                    let mut v = Vec::with_capacity(csl+buf.len());
-                    v.resize(csl+buf.len(), 0);
-                    (v, buf.len())
-                },
-                BufVar::Out(ref buf) => {
-                    let mut v = Vec::with_capacity(csl+buf.len());
-                    v.resize(csl, 0);
-                    v.extend_from_slice(buf);
-                    (v, buf.len())
-                }
+            let v = match buf_var {
+                BufVar::In(ref buf) => vec![0; buf.len()],
+                BufVar::Out(ref buf) => buf.to_vec(),
             };
-            let _handle = self.control(v, timeout, callback, request_type, request, value, index, s as u16)?;
+            let setup = SetupPacket::new(request_type, request, value, index, v.len() as u16);
+            let _handle = self.control(v, options, callback, setup)?;
             match rcv.recv() {
                 Ok(res) => {
                     if let BufVar::In(buf) = buf_var {
-                        for i in 0..res.actual_length { buf[i] = res.buf[csl+i]; }
+                        // The callback's buffer still starts with the setup packet
+                        buf[..res.actual_length].copy_from_slice(&res.buf[SetupPacket::SIZE..SetupPacket::SIZE + res.actual_length]);
                     }
                     Ok(res.actual_length)
                 },
@@ -215,7 +447,7 @@ mod unix_async_io {
             }
         }
 
-        #[inline] fn int_blk_msg<'a>(&'dh self, endpoint: u8, buf_var: BufVar<'a>, timeout: Duration, interrupt: bool) -> ::Result<usize> {
+        #[inline] fn int_blk_msg<'a>(&'dh self, endpoint: u8, buf_var: BufVar<'a>, options: TransferOptions, interrupt: bool) -> ::Result<usize> {
             let (snd, rcv) = channel();
             let callback = Some(move |dat| { snd.send(dat).expect("int_blk_msg channel send error"); UnixAsyncIoCallbackResult::Handled });
             let v = match buf_var {
@@ -230,10 +462,10 @@ mod unix_async_io {
                     v
                 }
             };
-            let _handle = if interrupt {
-                self.interrupt(v, timeout, callback, endpoint)?
-            } else {
-                self.bulk(v, timeout, callback, endpoint)?
+            let _handle = match (interrupt, options.stream_id()) {
+                (true, _)              => self.interrupt(v, options, callback, endpoint)?,
+                (false, None)          => self.bulk(v, options, callback, endpoint)?,
+                (false, Some(stream))  => self.bulk_stream(v, without_stream_id(options), callback, endpoint, stream)?,
             };
             match rcv.recv() {
                 Ok(res) => {
@@ -249,48 +481,238 @@ mod unix_async_io {
 
     impl<'ctx> DeviceHandleSyncApi for DeviceHandle<'ctx, UnixAsyncIo> {
         fn read_interrupt(&self, endpoint: u8, buf: &mut [u8], timeout: Duration) -> ::Result<usize> {
-            if endpoint & LIBUSB_ENDPOINT_DIR_MASK != LIBUSB_ENDPOINT_IN { return Err(::Error::InvalidParam); }
-            self.int_blk_msg(endpoint, BufVar::In(buf), timeout, true)
+            self.read_interrupt_with_options(endpoint, buf, &TransferOptions::new(timeout))
         }
 
         fn write_interrupt(&self, endpoint: u8, buf: &[u8], timeout: Duration) -> ::Result<usize> {
-            if endpoint & LIBUSB_ENDPOINT_DIR_MASK != LIBUSB_ENDPOINT_OUT { return Err(::Error::InvalidParam); }
-            self.int_blk_msg(endpoint, BufVar::Out(buf), timeout, true)
+            self.write_interrupt_with_options(endpoint, buf, &TransferOptions::new(timeout))
         }
 
         fn read_bulk(&self, endpoint: u8, buf: &mut [u8], timeout: Duration) -> ::Result<usize> {
-            if endpoint & LIBUSB_ENDPOINT_DIR_MASK != LIBUSB_ENDPOINT_IN { return Err(::Error::InvalidParam); }
-            self.int_blk_msg(endpoint, BufVar::In(buf), timeout, false)
+            self.read_bulk_with_options(endpoint, buf, &TransferOptions::new(timeout))
         }
 
         fn write_bulk(&self, endpoint: u8, buf: &[u8], timeout: Duration) -> ::Result<usize> {
-            if endpoint & LIBUSB_ENDPOINT_DIR_MASK != LIBUSB_ENDPOINT_OUT { return Err(::Error::InvalidParam); }
-            self.int_blk_msg(endpoint, BufVar::Out(buf), timeout, false)
+            self.write_bulk_with_options(endpoint, buf, &TransferOptions::new(timeout))
         }
 
         fn read_control(&self, request_type: u8, request: u8, value: u16, index: u16, buf: &mut [u8], timeout: Duration) -> ::Result<usize> {
-            if request_type & LIBUSB_ENDPOINT_DIR_MASK != LIBUSB_ENDPOINT_IN { return Err(::Error::InvalidParam); }
-            self.control_msg(request_type, request, value, index, BufVar::In(buf), timeout)
+            self.read_control_with_options(request_type, request, value, index, buf, &TransferOptions::new(timeout))
         }
 
         fn write_control(&self, request_type: u8, request: u8, value: u16, index: u16, buf: &[u8], timeout: Duration) -> ::Result<usize> {
+            self.write_control_with_options(request_type, request, value, index, buf, &TransferOptions::new(timeout))
+        }
+
+        fn read_interrupt_with_options(&self, endpoint: u8, buf: &mut [u8], options: &TransferOptions) -> ::Result<usize> {
+            if endpoint & LIBUSB_ENDPOINT_DIR_MASK != LIBUSB_ENDPOINT_IN { return Err(::Error::InvalidParam); }
+            self.recover_stall(endpoint, || self.int_blk_msg(endpoint, BufVar::In(&mut *buf), *options, true))
+        }
+
+        fn write_interrupt_with_options(&self, endpoint: u8, buf: &[u8], options: &TransferOptions) -> ::Result<usize> {
+            if endpoint & LIBUSB_ENDPOINT_DIR_MASK != LIBUSB_ENDPOINT_OUT { return Err(::Error::InvalidParam); }
+            self.recover_stall(endpoint, || self.int_blk_msg(endpoint, BufVar::Out(buf), *options, true))
+        }
+
+        fn read_bulk_with_options(&self, endpoint: u8, buf: &mut [u8], options: &TransferOptions) -> ::Result<usize> {
+            if endpoint & LIBUSB_ENDPOINT_DIR_MASK != LIBUSB_ENDPOINT_IN { return Err(::Error::InvalidParam); }
+            self.recover_stall(endpoint, || self.int_blk_msg(endpoint, BufVar::In(&mut *buf), *options, false))
+        }
+
+        fn write_bulk_with_options(&self, endpoint: u8, buf: &[u8], options: &TransferOptions) -> ::Result<usize> {
+            if endpoint & LIBUSB_ENDPOINT_DIR_MASK != LIBUSB_ENDPOINT_OUT { return Err(::Error::InvalidParam); }
+            self.recover_stall(endpoint, || self.int_blk_msg(endpoint, BufVar::Out(buf), *options, false))
+        }
+
+        fn read_control_with_options(&self, request_type: u8, request: u8, value: u16, index: u16, buf: &mut [u8], options: &TransferOptions) -> ::Result<usize> {
+            if request_type & LIBUSB_ENDPOINT_DIR_MASK != LIBUSB_ENDPOINT_IN { return Err(::Error::InvalidParam); }
+            self.control_msg(request_type, request, value, index, BufVar::In(buf), *options)
+        }
+
+        fn write_control_with_options(&self, request_type: u8, request: u8, value: u16, index: u16, buf: &[u8], options: &TransferOptions) -> ::Result<usize> {
             if request_type & LIBUSB_ENDPOINT_DIR_MASK != LIBUSB_ENDPOINT_OUT { return Err(::Error::InvalidParam); }
-            self.control_msg(request_type, request, value, index, BufVar::Out(buf), timeout)
+            self.control_msg(request_type, request, value, index, BufVar::Out(buf), *options)
         }
     }
 }
 
 mod sync_io {
     use std::mem;
+    use std::ptr;
+    use std::slice;
     use std::time::Duration;
-    use libc::{c_int, c_uint, c_uchar};
+    use libc::{c_int, c_uchar, c_void};
     use libusb::*;
 
     use io::sync::SyncIo;
     use error::{self, Error};
     use device_handle_sync_api::DeviceHandleSyncApi;
+    use transfer_options::{TransferOptions, check_no_stream_id};
     use super::DeviceHandle;
 
+    /// How often event handling may fail while waiting for a cancelled transfer to complete.
+    const CANCEL_ATTEMPTS: usize = 16;
+
+    extern "C" fn sync_transfer_callback(transfer: *mut libusb_transfer) {
+        unsafe {
+            *((*transfer).user_data as *mut c_int) = 1;
+        }
+    }
+
+    impl<'ctx> DeviceHandle<'ctx, SyncIo> {
+        /// Submits a transfer and handles events until it completes.
+        ///
+        /// libusb's synchronous api has no way to set transfer flags or stream ids, so transfers
+        /// that need them are submitted asynchronously and waited for here, the same way libusb
+        /// implements its own synchronous functions.
+        ///
+        /// The transfer owns `buf` until it completes, and `buf` is returned along with the number
+        /// of bytes transferred.
+        fn submit_and_wait(&self, transfer_type: u8, endpoint: u8, mut buf: Vec<u8>, options: &TransferOptions) -> ::Result<(Vec<u8>, usize)> {
+            if transfer_type != LIBUSB_TRANSFER_TYPE_BULK {
+                check_no_stream_id(options)?;
+            }
+
+            let transfer = unsafe { libusb_alloc_transfer(0) };
+
+            if transfer.is_null() {
+                return Err(Error::NoMem);
+            }
+
+            let mut completed = Box::new(0 as c_int);
+            let user_data = &mut *completed as *mut c_int as *mut c_void;
+            let timeout_ms = options.timeout_ms();
+            let ptr = buf.as_mut_ptr();
+            let len = buf.len() as c_int;
+
+            unsafe {
+                match (transfer_type, options.stream_id()) {
+                    (LIBUSB_TRANSFER_TYPE_CONTROL, _) => _libusb_fill_control_transfer(transfer, self.handle, ptr, sync_transfer_callback, user_data, timeout_ms),
+                    (LIBUSB_TRANSFER_TYPE_INTERRUPT, _) => _libusb_fill_interrupt_transfer(transfer, self.handle, endpoint, ptr, len, sync_transfer_callback, user_data, timeout_ms),
+                    (_, None) => _libusb_fill_bulk_transfer(transfer, self.handle, endpoint, ptr, len, sync_transfer_callback, user_data, timeout_ms),
+                    (_, Some(stream_id)) => _libusb_fill_bulk_stream_transfer(transfer, self.handle, endpoint, stream_id, ptr, len, sync_transfer_callback, user_data, timeout_ms),
+                }
+                (*transfer).flags = options.libusb_flags();
+            }
+
+            match unsafe { libusb_submit_transfer(transfer) } {
+                0 => (),
+                err => {
+                    unsafe { libusb_free_transfer(transfer) };
+                    return Err(error::from_libusb(err));
+                },
+            }
+
+            while *completed == 0 {
+                match unsafe { libusb_handle_events_completed(self.io_handle, &mut *completed) } {
+                    0 | LIBUSB_ERROR_INTERRUPTED => (),
+                    err => {
+                        // libusb uses the transfer, its buffer and its completion flag until the
+                        // callback has run, so events are handled until the cancelled transfer
+                        // completes. If event handling keeps failing, all three are leaked.
+                        unsafe { libusb_cancel_transfer(transfer) };
+
+                        let mut failures = 0;
+
+                        while *completed == 0 && failures < CANCEL_ATTEMPTS {
+                            match unsafe { libusb_handle_events_completed(self.io_handle, &mut *completed) } {
+                                0 | LIBUSB_ERROR_INTERRUPTED => (),
+                                _ => failures += 1,
+                            }
+                        }
+
+                        if *completed == 0 {
+                            mem::forget(completed);
+                            mem::forget(buf);
+                        } else {
+                            unsafe { libusb_free_transfer(transfer) };
+                        }
+
+                        return Err(error::from_libusb(err));
+                    },
+                }
+            }
+
+            let (status, actual_length) = unsafe { ((*transfer).status, (*transfer).actual_length) };
+            unsafe { libusb_free_transfer(transfer) };
+
+            match status {
+                LIBUSB_TRANSFER_COMPLETED => Ok((buf, actual_length as usize)),
+                LIBUSB_TRANSFER_TIMED_OUT => Err(Error::Timeout),
+                LIBUSB_TRANSFER_STALL     => Err(Error::Pipe),
+                LIBUSB_TRANSFER_NO_DEVICE => Err(Error::NoDevice),
+                LIBUSB_TRANSFER_OVERFLOW  => Err(Error::Overflow),
+                _                         => Err(Error::Io),
+            }
+        }
+
+        fn control_with_options(&self, request_type: u8, request: u8, value: u16, index: u16, buf: &mut [u8], options: &TransferOptions) -> ::Result<usize> {
+            if buf.len() > u16::max_value() as usize {
+                return Err(Error::InvalidParam);
+            }
+
+            let setup_len = mem::size_of::<libusb_control_setup>();
+            let mut transfer_buf = vec![0u8; setup_len + buf.len()];
+
+            unsafe { _libusb_fill_control_setup(transfer_buf.as_mut_ptr(), request_type, request, value, index, buf.len() as u16) };
+            if request_type & LIBUSB_ENDPOINT_DIR_MASK == LIBUSB_ENDPOINT_OUT {
+                transfer_buf[setup_len..].copy_from_slice(buf);
+            }
+
+            let (transfer_buf, transferred) = self.submit_and_wait(LIBUSB_TRANSFER_TYPE_CONTROL, 0, transfer_buf, options)?;
+
+            if request_type & LIBUSB_ENDPOINT_DIR_MASK == LIBUSB_ENDPOINT_IN {
+                buf[..transferred].copy_from_slice(&transfer_buf[setup_len..setup_len + transferred]);
+            }
+
+            Ok(transferred)
+        }
+
+        /// Performs an interrupt or bulk transfer.
+        fn transfer(&self, transfer_type: u8, endpoint: u8, ptr: *mut c_uchar, len: c_int, options: &TransferOptions) -> ::Result<usize> {
+            if needs_async(options) {
+                // The transfer gets its own copy of the data, which it can keep if it has to be
+                // abandoned.
+                let data = unsafe { slice::from_raw_parts(ptr, len as usize) }.to_vec();
+                let (data, transferred) = self.submit_and_wait(transfer_type, endpoint, data, options)?;
+
+                if endpoint & LIBUSB_ENDPOINT_DIR_MASK == LIBUSB_ENDPOINT_IN {
+                    unsafe { ptr::copy_nonoverlapping(data.as_ptr(), ptr, transferred) };
+                }
+
+                return Ok(transferred);
+            }
+
+            let mut transferred: c_int = 0;
+
+            let res = unsafe {
+                if transfer_type == LIBUSB_TRANSFER_TYPE_INTERRUPT {
+                    libusb_interrupt_transfer(self.handle, endpoint, ptr, len, &mut transferred, options.timeout_ms())
+                } else {
+                    libusb_bulk_transfer(self.handle, endpoint, ptr, len, &mut transferred, options.timeout_ms())
+                }
+            };
+
+            match res {
+                0 => {
+                    Ok(transferred as usize)
+                },
+                err => {
+                    if err == LIBUSB_ERROR_INTERRUPTED && transferred > 0 {
+                        Ok(transferred as usize)
+                    }
+                    else {
+                        Err(error::from_libusb(err))
+                    }
+                },
+            }
+        }
+    }
+
+    fn needs_async(options: &TransferOptions) -> bool {
+        options.libusb_flags() != 0 || options.stream_id().is_some()
+    }
+
     impl<'ctx> DeviceHandleSyncApi for DeviceHandle<'ctx, SyncIo> {
         /// Reads from an interrupt endpoint.
         ///
@@ -315,29 +737,7 @@ mod sync_io {
         /// * `NoDevice` if the device has been disconnected.
         /// * `Io` if the transfer encountered an I/O error.
         fn read_interrupt(&self, endpoint: u8, buf: &mut [u8], timeout: Duration) -> ::Result<usize> {
-            if endpoint & LIBUSB_ENDPOINT_DIR_MASK != LIBUSB_ENDPOINT_IN {
-                return Err(Error::InvalidParam);
-            }
-
-            let mut transferred: c_int = unsafe { mem::uninitialized() };
-
-            let ptr = buf.as_mut_ptr() as *mut c_uchar;
-            let len = buf.len() as c_int;
-            let timeout_ms = (timeout.as_secs() * 1000 + timeout.subsec_nanos() as u64 / 1_000_000) as c_uint;
-
-            match unsafe { libusb_interrupt_transfer(self.handle, endpoint, ptr, len, &mut transferred, timeout_ms) } {
-                0 => {
-                    Ok(transferred as usize)
-                },
-                err => {
-                    if err == LIBUSB_ERROR_INTERRUPTED && transferred > 0 {
-                        Ok(transferred as usize)
-                    }
-                    else {
-                        Err(error::from_libusb(err))
-                    }
-                },
-            }
+            self.read_interrupt_with_options(endpoint, buf, &TransferOptions::new(timeout))
         }
 
         /// Writes to an interrupt endpoint.
@@ -361,29 +761,7 @@ mod sync_io {
         /// * `NoDevice` if the device has been disconnected.
         /// * `Io` if the transfer encountered an I/O error.
         fn write_interrupt(&self, endpoint: u8, buf: &[u8], timeout: Duration) -> ::Result<usize> {
-            if endpoint & LIBUSB_ENDPOINT_DIR_MASK != LIBUSB_ENDPOINT_OUT {
-                return Err(Error::InvalidParam);
-            }
-
-            let mut transferred: c_int = unsafe { mem::uninitialized() };
-
-            let ptr = buf.as_ptr() as *mut c_uchar;
-            let len = buf.len() as c_int;
-            let timeout_ms = (timeout.as_secs() * 1000 + timeout.subsec_nanos() as u64 / 1_000_000) as c_uint;
-
-            match unsafe { libusb_interrupt_transfer(self.handle, endpoint, ptr, len, &mut transferred, timeout_ms) } {
-                0 => {
-                    Ok(transferred as usize)
-                },
-                err => {
-                    if err == LIBUSB_ERROR_INTERRUPTED && transferred > 0 {
-                        Ok(transferred as usize)
-                    }
-                    else {
-                        Err(error::from_libusb(err))
-                    }
-                },
-            }
+            self.write_interrupt_with_options(endpoint, buf, &TransferOptions::new(timeout))
         }
 
         /// Reads from a bulk endpoint.
@@ -409,29 +787,7 @@ mod sync_io {
         /// * `NoDevice` if the device has been disconnected.
         /// * `Io` if the transfer encountered an I/O error.
         fn read_bulk(&self, endpoint: u8, buf: &mut [u8], timeout: Duration) -> ::Result<usize> {
-            if endpoint & LIBUSB_ENDPOINT_DIR_MASK != LIBUSB_ENDPOINT_IN {
-                return Err(Error::InvalidParam);
-            }
-
-            let mut transferred: c_int = unsafe { mem::uninitialized() };
-
-            let ptr = buf.as_mut_ptr() as *mut c_uchar;
-            let len = buf.len() as c_int;
-            let timeout_ms = (timeout.as_secs() * 1000 + timeout.subsec_nanos() as u64 / 1_000_000) as c_uint;
-
-            match unsafe { libusb_bulk_transfer(self.handle, endpoint, ptr, len, &mut transferred, timeout_ms) } {
-                0 => {
-                    Ok(transferred as usize)
-                },
-                err => {
-                    if err == LIBUSB_ERROR_INTERRUPTED && transferred > 0 {
-                        Ok(transferred as usize)
-                    }
-                    else {
-                        Err(error::from_libusb(err))
-                    }
-                },
-            }
+            self.read_bulk_with_options(endpoint, buf, &TransferOptions::new(timeout))
         }
 
         /// Writes to a bulk endpoint.
@@ -455,29 +811,7 @@ mod sync_io {
         /// * `NoDevice` if the device has been disconnected.
         /// * `Io` if the transfer encountered an I/O error.
         fn write_bulk(&self, endpoint: u8, buf: &[u8], timeout: Duration) -> ::Result<usize> {
-            if endpoint & LIBUSB_ENDPOINT_DIR_MASK != LIBUSB_ENDPOINT_OUT {
-                return Err(Error::InvalidParam);
-            }
-
-            let mut transferred: c_int = unsafe { mem::uninitialized() };
-
-            let ptr = buf.as_ptr() as *mut c_uchar;
-            let len = buf.len() as c_int;
-            let timeout_ms = (timeout.as_secs() * 1000 + timeout.subsec_nanos() as u64 / 1_000_000) as c_uint;
-
-            match unsafe { libusb_bulk_transfer(self.handle, endpoint, ptr, len, &mut transferred, timeout_ms) } {
-                0 => {
-                    Ok(transferred as usize)
-                },
-                err => {
-                    if err == LIBUSB_ERROR_INTERRUPTED && transferred > 0 {
-                        Ok(transferred as usize)
-                    }
-                    else {
-                        Err(error::from_libusb(err))
-                    }
-                },
-            }
+            self.write_bulk_with_options(endpoint, buf, &TransferOptions::new(timeout))
         }
 
         /// Reads data using a control transfer.
@@ -508,23 +842,7 @@ mod sync_io {
         /// * `NoDevice` if the device has been disconnected.
         /// * `Io` if the transfer encountered an I/O error.
         fn read_control(&self, request_type: u8, request: u8, value: u16, index: u16, buf: &mut [u8], timeout: Duration) -> ::Result<usize> {
-            if request_type & LIBUSB_ENDPOINT_DIR_MASK != LIBUSB_ENDPOINT_IN {
-                return Err(Error::InvalidParam);
-            }
-
-            let ptr = buf.as_mut_ptr() as *mut c_uchar;
-            let len = buf.len() as u16;
-            let timeout_ms = (timeout.as_secs() * 1000 + timeout.subsec_nanos() as u64 / 1_000_000) as c_uint;
-
-            let res = unsafe {
-                libusb_control_transfer(self.handle, request_type, request, value, index, ptr, len, timeout_ms)
-            };
-
-            if res < 0 {
-                Err(error::from_libusb(res))
-            } else {
-                Ok(res as usize)
-            }
+            self.read_control_with_options(request_type, request, value, index, buf, &TransferOptions::new(timeout))
         }
 
         /// Writes data using a control transfer.
@@ -554,16 +872,103 @@ mod sync_io {
         /// * `NoDevice` if the device has been disconnected.
         /// * `Io` if the transfer encountered an I/O error.
         fn write_control(&self, request_type: u8, request: u8, value: u16, index: u16, buf: &[u8], timeout: Duration) -> ::Result<usize> {
+            self.write_control_with_options(request_type, request, value, index, buf, &TransferOptions::new(timeout))
+        }
+
+        /// Reads like [`read_interrupt`](#method.read_interrupt), with the timeout and transfer
+        /// flags taken from `options`.
+        fn read_interrupt_with_options(&self, endpoint: u8, buf: &mut [u8], options: &TransferOptions) -> ::Result<usize> {
+            if endpoint & LIBUSB_ENDPOINT_DIR_MASK != LIBUSB_ENDPOINT_IN {
+                return Err(Error::InvalidParam);
+            }
+
+            let ptr = buf.as_mut_ptr() as *mut c_uchar;
+            let len = buf.len() as c_int;
+
+            self.recover_stall(endpoint, || self.transfer(LIBUSB_TRANSFER_TYPE_INTERRUPT, endpoint, ptr, len, options))
+        }
+
+        /// Writes like [`write_interrupt`](#method.write_interrupt), with the timeout and transfer
+        /// flags taken from `options`.
+        fn write_interrupt_with_options(&self, endpoint: u8, buf: &[u8], options: &TransferOptions) -> ::Result<usize> {
+            if endpoint & LIBUSB_ENDPOINT_DIR_MASK != LIBUSB_ENDPOINT_OUT {
+                return Err(Error::InvalidParam);
+            }
+
+            let ptr = buf.as_ptr() as *mut c_uchar;
+            let len = buf.len() as c_int;
+
+            self.recover_stall(endpoint, || self.transfer(LIBUSB_TRANSFER_TYPE_INTERRUPT, endpoint, ptr, len, options))
+        }
+
+        /// Reads like [`read_bulk`](#method.read_bulk), with the timeout and transfer flags taken
+        /// from `options`. If `options` has a stream ID, the transfer uses that bulk stream.
+        fn read_bulk_with_options(&self, endpoint: u8, buf: &mut [u8], options: &TransferOptions) -> ::Result<usize> {
+            if endpoint & LIBUSB_ENDPOINT_DIR_MASK != LIBUSB_ENDPOINT_IN {
+                return Err(Error::InvalidParam);
+            }
+
+            let ptr = buf.as_mut_ptr() as *mut c_uchar;
+            let len = buf.len() as c_int;
+
+            self.recover_stall(endpoint, || self.transfer(LIBUSB_TRANSFER_TYPE_BULK, endpoint, ptr, len, options))
+        }
+
+        /// Writes like [`write_bulk`](#method.write_bulk), with the timeout and transfer flags
+        /// taken from `options`. If `options` has a stream ID, the transfer uses that bulk stream.
+        fn write_bulk_with_options(&self, endpoint: u8, buf: &[u8], options: &TransferOptions) -> ::Result<usize> {
+            if endpoint & LIBUSB_ENDPOINT_DIR_MASK != LIBUSB_ENDPOINT_OUT {
+                return Err(Error::InvalidParam);
+            }
+
+            let ptr = buf.as_ptr() as *mut c_uchar;
+            let len = buf.len() as c_int;
+
+            self.recover_stall(endpoint, || self.transfer(LIBUSB_TRANSFER_TYPE_BULK, endpoint, ptr, len, options))
+        }
+
+        /// Reads like [`read_control`](#method.read_control), with the timeout and transfer flags
+        /// taken from `options`.
+        fn read_control_with_options(&self, request_type: u8, request: u8, value: u16, index: u16, buf: &mut [u8], options: &TransferOptions) -> ::Result<usize> {
+            if request_type & LIBUSB_ENDPOINT_DIR_MASK != LIBUSB_ENDPOINT_IN {
+                return Err(Error::InvalidParam);
+            }
+
+            if needs_async(options) {
+                return self.control_with_options(request_type, request, value, index, buf, options);
+            }
+
+            let ptr = buf.as_mut_ptr() as *mut c_uchar;
+            let len = buf.len() as u16;
+
+            let res = unsafe {
+                libusb_control_transfer(self.handle, request_type, request, value, index, ptr, len, options.timeout_ms())
+            };
+
+            if res < 0 {
+                Err(error::from_libusb(res))
+            } else {
+                Ok(res as usize)
+            }
+        }
+
+        /// Writes like [`write_control`](#method.write_control), with the timeout and transfer
+        /// flags taken from `options`.
+        fn write_control_with_options(&self, request_type: u8, request: u8, value: u16, index: u16, buf: &[u8], options: &TransferOptions) -> ::Result<usize> {
             if request_type & LIBUSB_ENDPOINT_DIR_MASK != LIBUSB_ENDPOINT_OUT {
                 return Err(Error::InvalidParam);
             }
 
+            if needs_async(options) {
+                let mut data = buf.to_vec();
+                return self.control_with_options(request_type, request, value, index, &mut data, options);
+            }
+
             let ptr = buf.as_ptr() as *mut c_uchar;
             let len = buf.len() as u16;
-            let timeout_ms = (timeout.as_secs() * 1000 + timeout.subsec_nanos() as u64 / 1_000_000) as c_uint;
 
             let res = unsafe {
-                libusb_control_transfer(self.handle, request_type, request, value, index, ptr, len, timeout_ms)
+                libusb_control_transfer(self.handle, request_type, request, value, index, ptr, len, options.timeout_ms())
             };
 
             if res < 0 {
@@ -575,6 +980,63 @@ mod sync_io {
     }
 }
 
+#[doc(hidden)]
+pub fn as_raw<'ctx, Io>(handle: &DeviceHandle<'ctx, Io>) -> *mut libusb_device_handle
+    where Io: IoType<'ctx>,
+{
+    handle.handle
+}
+
+#[doc(hidden)]
+pub fn set_alternate_setting<'ctx, Io>(handle: &DeviceHandle<'ctx, Io>, iface: u8, setting: u8) -> ::Result<()>
+    where Io: IoType<'ctx>,
+{
+    try_unsafe!(libusb_set_interface_alt_setting(handle.handle, iface as c_int, setting as c_int));
+    Ok(())
+}
+
+#[doc(hidden)]
+pub fn active_config_descriptor<'ctx, Io>(handle: &DeviceHandle<'ctx, Io>) -> ::Result<ConfigDescriptor>
+    where Io: IoType<'ctx>,
+{
+    let mut config: *const libusb_config_descriptor = ptr::null();
+
+    try_unsafe!(libusb_get_active_config_descriptor(libusb_get_device(handle.handle), &mut config));
+
+    Ok(unsafe { config_descriptor::from_libusb(config) })
+}
+
+#[doc(hidden)]
+pub fn release_claimed<'ctx, Io>(handle: &DeviceHandle<'ctx, Io>, iface: u8, kernel_driver: KernelDriver) -> ::Result<()>
+    where Io: IoType<'ctx>,
+{
+    let mut interfaces = handle.interfaces.lock().expect("Could not lock claimed interfaces");
+
+    if kernel_driver == KernelDriver::AutoDetached {
+        unsafe { libusb_set_auto_detach_kernel_driver(handle.handle, 1) };
+    }
+
+    let res = unsafe { libusb_release_interface(handle.handle, iface as c_int) };
+    interfaces.remove(iface as usize);
+
+    match kernel_driver {
+        KernelDriver::AutoDetached => unsafe { libusb_set_auto_detach_kernel_driver(handle.handle, 0); },
+        KernelDriver::Detached => {
+            let res = unsafe { libusb_attach_kernel_driver(handle.handle, iface as c_int) };
+            if res < 0 {
+                warn!("Could not reattach kernel driver to interface {}: {}", iface, error::from_libusb(res));
+            }
+        },
+        KernelDriver::Untouched => (),
+    }
+
+    if res < 0 {
+        Err(error::from_libusb(res))
+    } else {
+        Ok(())
+    }
+}
+
 #[doc(hidden)]
 pub unsafe fn from_libusb<'ctx, Io>(context: PhantomData<&'ctx Context<Io>>, io_handle: <Io as IoType<'ctx>>::Handle, handle: *mut libusb_device_handle) -> DeviceHandle<'ctx, Io>
     where Io: IoType<'ctx>,
@@ -583,6 +1045,7 @@ pub unsafe fn from_libusb<'ctx, Io>(context: PhantomData<&'ctx Context<Io>>, io_
         context: context,
         io_handle: io_handle,
         handle: handle,
-        interfaces: BitSet::with_capacity(u8::max_value() as usize + 1),
+        interfaces: Mutex::new(BitSet::with_capacity(u8::max_value() as usize + 1)),
+        stall_recovery: StallRecovery::Disabled,
     }
 }
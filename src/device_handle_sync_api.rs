@@ -5,6 +5,12 @@ use device_descriptor::DeviceDescriptor;
 use config_descriptor::ConfigDescriptor;
 use interface_descriptor::InterfaceDescriptor;
 use fields::{Direction, RequestType, Recipient, request_type};
+use transfer_options::{TransferOptions, check_no_stream_id};
+use setup_packet::SetupPacket;
+use standard_request::{DeviceStatus, Feature};
+use device_qualifier::DeviceQualifier;
+use bos_descriptor::BosDescriptor;
+use descriptor_parser::{self, DT_DEVICE_QUALIFIER, DT_OTHER_SPEED_CONFIGURATION};
 use error::Error;
 use libusb::*;
 
@@ -16,6 +22,318 @@ pub trait DeviceHandleSyncApi {
     fn read_control(&self, request_type: u8, request: u8, value: u16, index: u16, buf: &mut [u8], timeout: Duration) -> ::Result<usize>;
     fn write_control(&self, request_type: u8, request: u8, value: u16, index: u16, buf: &[u8], timeout: Duration) -> ::Result<usize>;
 
+    /// Reads from an interrupt endpoint with transfer options.
+    ///
+    /// This behaves like [`read_interrupt`](#tymethod.read_interrupt), with the timeout and
+    /// transfer flags taken from `options`. The default implementation only supports a timeout.
+    ///
+    /// ## Errors
+    ///
+    /// * `InvalidParam` if `options` has a stream ID, which only bulk transfers use.
+    /// * `NotSupported` if `options` has flags that the implementation can't apply.
+    fn read_interrupt_with_options(&self, endpoint: u8, buf: &mut [u8], options: &TransferOptions) -> ::Result<usize> {
+        check_no_stream_id(options)?;
+        self.read_interrupt(endpoint, buf, timeout_only(options)?)
+    }
+
+    /// Writes to an interrupt endpoint with transfer options.
+    ///
+    /// This behaves like [`write_interrupt`](#tymethod.write_interrupt), with the timeout and
+    /// transfer flags taken from `options`. The default implementation only supports a timeout.
+    ///
+    /// ## Errors
+    ///
+    /// * `InvalidParam` if `options` has a stream ID, which only bulk transfers use.
+    /// * `NotSupported` if `options` has flags that the implementation can't apply.
+    fn write_interrupt_with_options(&self, endpoint: u8, buf: &[u8], options: &TransferOptions) -> ::Result<usize> {
+        check_no_stream_id(options)?;
+        self.write_interrupt(endpoint, buf, timeout_only(options)?)
+    }
+
+    /// Reads from a bulk endpoint with transfer options.
+    ///
+    /// This behaves like [`read_bulk`](#tymethod.read_bulk), with the timeout, transfer flags and
+    /// stream ID taken from `options`. The default implementation only supports a timeout.
+    ///
+    /// ## Errors
+    ///
+    /// * `NotSupported` if `options` has flags or a stream ID that the implementation can't apply.
+    fn read_bulk_with_options(&self, endpoint: u8, buf: &mut [u8], options: &TransferOptions) -> ::Result<usize> {
+        self.read_bulk(endpoint, buf, timeout_only(options)?)
+    }
+
+    /// Writes to a bulk endpoint with transfer options.
+    ///
+    /// This behaves like [`write_bulk`](#tymethod.write_bulk), with the timeout, transfer flags and
+    /// stream ID taken from `options`. The default implementation only supports a timeout.
+    ///
+    /// ## Errors
+    ///
+    /// * `NotSupported` if `options` has flags or a stream ID that the implementation can't apply.
+    fn write_bulk_with_options(&self, endpoint: u8, buf: &[u8], options: &TransferOptions) -> ::Result<usize> {
+        self.write_bulk(endpoint, buf, timeout_only(options)?)
+    }
+
+    /// Reads data using a control transfer with transfer options.
+    ///
+    /// This behaves like [`read_control`](#tymethod.read_control), with the timeout and transfer
+    /// flags taken from `options`. The default implementation only supports a timeout.
+    ///
+    /// ## Errors
+    ///
+    /// * `InvalidParam` if `options` has a stream ID, which only bulk transfers use.
+    /// * `NotSupported` if `options` has flags that the implementation can't apply.
+    fn read_control_with_options(&self, request_type: u8, request: u8, value: u16, index: u16, buf: &mut [u8], options: &TransferOptions) -> ::Result<usize> {
+        check_no_stream_id(options)?;
+        self.read_control(request_type, request, value, index, buf, timeout_only(options)?)
+    }
+
+    /// Writes data using a control transfer with transfer options.
+    ///
+    /// This behaves like [`write_control`](#tymethod.write_control), with the timeout and transfer
+    /// flags taken from `options`. The default implementation only supports a timeout.
+    ///
+    /// ## Errors
+    ///
+    /// * `InvalidParam` if `options` has a stream ID, which only bulk transfers use.
+    /// * `NotSupported` if `options` has flags that the implementation can't apply.
+    fn write_control_with_options(&self, request_type: u8, request: u8, value: u16, index: u16, buf: &[u8], options: &TransferOptions) -> ::Result<usize> {
+        check_no_stream_id(options)?;
+        self.write_control(request_type, request, value, index, buf, timeout_only(options)?)
+    }
+
+    /// Reads data with a control transfer described by a setup packet.
+    ///
+    /// The data stage is read into the first `setup.length()` bytes of `data`. Returns the number
+    /// of bytes read.
+    ///
+    /// ## Errors
+    ///
+    /// * `InvalidParam` if the setup packet isn't inbound or `data` is shorter than
+    ///   `setup.length()`.
+    ///
+    /// Other errors are the same as for [`read_control`](#tymethod.read_control).
+    fn control_in(&self, setup: SetupPacket, data: &mut [u8], timeout: Duration) -> ::Result<usize> {
+        let len = setup.length() as usize;
+
+        if setup.direction() != Direction::In || data.len() < len {
+            return Err(Error::InvalidParam);
+        }
+
+        self.read_control(setup.raw_request_type(), setup.request(), setup.value(), setup.index(), &mut data[..len], timeout)
+    }
+
+    /// Writes data with a control transfer described by a setup packet.
+    ///
+    /// The first `setup.length()` bytes of `data` are sent in the data stage. Returns the number of
+    /// bytes written.
+    ///
+    /// ## Errors
+    ///
+    /// * `InvalidParam` if the setup packet isn't outbound or `data` is shorter than
+    ///   `setup.length()`.
+    ///
+    /// Other errors are the same as for [`write_control`](#tymethod.write_control).
+    fn control_out(&self, setup: SetupPacket, data: &[u8], timeout: Duration) -> ::Result<usize> {
+        let len = setup.length() as usize;
+
+        if setup.direction() != Direction::Out || data.len() < len {
+            return Err(Error::InvalidParam);
+        }
+
+        self.write_control(setup.raw_request_type(), setup.request(), setup.value(), setup.index(), &data[..len], timeout)
+    }
+
+    /// Reads the status of the device.
+    ///
+    /// This function sends a standard `GET_STATUS` request to the device.
+    fn read_device_status(&self, timeout: Duration) -> ::Result<DeviceStatus> {
+        let raw = read_status(self, Recipient::Device, 0, timeout)?;
+        Ok(::standard_request::device_status_from_raw(raw))
+    }
+
+    /// Reads the status of an interface.
+    ///
+    /// This function sends a standard `GET_STATUS` request to the interface and returns the raw
+    /// status word. USB 2.0 reserves all of its bits, SuperSpeed devices report function remote
+    /// wakeup in its lowest two bits.
+    fn read_interface_status(&self, iface: u8, timeout: Duration) -> ::Result<u16> {
+        read_status(self, Recipient::Interface, iface as u16, timeout)
+    }
+
+    /// Indicates if an endpoint is halted.
+    ///
+    /// This function sends a standard `GET_STATUS` request to the endpoint and returns the state
+    /// of its halt feature. A halted endpoint can be recovered with
+    /// [`DeviceHandle::clear_halt`](struct.DeviceHandle.html#method.clear_halt).
+    fn endpoint_halted(&self, endpoint: u8, timeout: Duration) -> ::Result<bool> {
+        let status = read_status(self, Recipient::Endpoint, endpoint as u16, timeout)?;
+        Ok(status & 0x0001 != 0)
+    }
+
+    /// Enables a standard feature with a `SET_FEATURE` request.
+    fn set_feature(&self, feature: Feature, timeout: Duration) -> ::Result<()> {
+        let setup = SetupPacket::builder()
+            .direction(Direction::Out)
+            .recipient(feature.recipient())
+            .request(LIBUSB_REQUEST_SET_FEATURE)
+            .value(feature.selector())
+            .index(feature.index())
+            .build();
+
+        self.control_out(setup, &[], timeout)?;
+        Ok(())
+    }
+
+    /// Disables a standard feature with a `CLEAR_FEATURE` request.
+    ///
+    /// Clearing an endpoint's halt feature this way does not reset the host's data toggle; use
+    /// [`DeviceHandle::clear_halt`](struct.DeviceHandle.html#method.clear_halt) for that.
+    ///
+    /// ## Errors
+    ///
+    /// * `InvalidParam` if `feature` is a test mode, which can't be cleared.
+    fn clear_feature(&self, feature: Feature, timeout: Duration) -> ::Result<()> {
+        if let Feature::TestMode(_) = feature {
+            return Err(Error::InvalidParam);
+        }
+
+        let setup = SetupPacket::builder()
+            .direction(Direction::Out)
+            .recipient(feature.recipient())
+            .request(LIBUSB_REQUEST_CLEAR_FEATURE)
+            .value(feature.selector())
+            .index(feature.index())
+            .build();
+
+        self.control_out(setup, &[], timeout)?;
+        Ok(())
+    }
+
+    /// Reads the device's active configuration with a `GET_CONFIGURATION` request.
+    ///
+    /// Unlike [`DeviceHandle::active_configuration`](struct.DeviceHandle.html#method.active_configuration),
+    /// which may return a value cached by the operating system, this always asks the device. Zero
+    /// is returned if the device isn't configured.
+    fn read_configuration(&self, timeout: Duration) -> ::Result<u8> {
+        let setup = SetupPacket::builder()
+            .direction(Direction::In)
+            .recipient(Recipient::Device)
+            .request(LIBUSB_REQUEST_GET_CONFIGURATION)
+            .length(1)
+            .build();
+
+        let mut buf = [0u8; 1];
+
+        if self.control_in(setup, &mut buf, timeout)? < buf.len() {
+            return Err(Error::Other);
+        }
+
+        Ok(buf[0])
+    }
+
+    /// Reads an interface's active setting with a `GET_INTERFACE` request.
+    fn read_alternate_setting(&self, iface: u8, timeout: Duration) -> ::Result<u8> {
+        let setup = SetupPacket::builder()
+            .direction(Direction::In)
+            .recipient(Recipient::Interface)
+            .request(LIBUSB_REQUEST_GET_INTERFACE)
+            .index(iface as u16)
+            .length(1)
+            .build();
+
+        let mut buf = [0u8; 1];
+
+        if self.control_in(setup, &mut buf, timeout)? < buf.len() {
+            return Err(Error::Other);
+        }
+
+        Ok(buf[0])
+    }
+
+    /// Reads the frame number of an isochronous endpoint's synchronization frame with a
+    /// `SYNCH_FRAME` request.
+    fn synch_frame(&self, endpoint: u8, timeout: Duration) -> ::Result<u16> {
+        let setup = SetupPacket::builder()
+            .direction(Direction::In)
+            .recipient(Recipient::Endpoint)
+            .request(LIBUSB_REQUEST_SYNCH_FRAME)
+            .index(endpoint as u16)
+            .length(2)
+            .build();
+
+        let mut buf = [0u8; 2];
+
+        if self.control_in(setup, &mut buf, timeout)? < buf.len() {
+            return Err(Error::Other);
+        }
+
+        Ok(buf[0] as u16 | (buf[1] as u16) << 8)
+    }
+
+    /// Reads a descriptor with a `GET_DESCRIPTOR` request.
+    ///
+    /// `descriptor_type` and `index` select the descriptor. `language_id` is the language of
+    /// string descriptors and zero for other descriptors. At most `buf.len()` bytes are read, and
+    /// the number of bytes read is returned.
+    fn read_descriptor(&self, descriptor_type: u8, index: u8, language_id: u16, buf: &mut [u8], timeout: Duration) -> ::Result<usize> {
+        let setup = SetupPacket::builder()
+            .direction(Direction::In)
+            .recipient(Recipient::Device)
+            .request(LIBUSB_REQUEST_GET_DESCRIPTOR)
+            .value((descriptor_type as u16) << 8 | index as u16)
+            .index(language_id)
+            .length(buf.len() as u16)
+            .build();
+
+        self.control_in(setup, buf, timeout)
+    }
+
+    /// Reads the device qualifier descriptor.
+    ///
+    /// Only high-speed capable devices have a device qualifier descriptor. Other devices stall the
+    /// request, which is returned as `Pipe`.
+    ///
+    /// ## Errors
+    ///
+    /// * `Custom` if the device returns a malformed descriptor.
+    fn read_device_qualifier(&self, timeout: Duration) -> ::Result<DeviceQualifier> {
+        let mut buf = [0u8; 10];
+        let len = self.read_descriptor(DT_DEVICE_QUALIFIER, 0, 0, &mut buf, timeout)?;
+
+        descriptor_parser::parse_device_qualifier(&buf[..len])
+    }
+
+    /// Reads an other speed configuration descriptor.
+    ///
+    /// The other speed configuration describes how the configuration at `index` would look if
+    /// the device were operating at its other speed, full speed for a high-speed device and high
+    /// speed for a full-speed device. The number of configurations at the other speed is given by
+    /// [`read_device_qualifier`](#method.read_device_qualifier).
+    ///
+    /// ## Errors
+    ///
+    /// * `Pipe` if the device isn't high-speed capable.
+    /// * `Custom` if the device returns a malformed descriptor.
+    fn read_other_speed_config_descriptor(&self, index: u8, timeout: Duration) -> ::Result<ConfigDescriptor> {
+        read_descriptor_set(self, DT_OTHER_SPEED_CONFIGURATION, index, 9, timeout)
+            .and_then(|buf| descriptor_parser::parse_config(&buf, DT_OTHER_SPEED_CONFIGURATION))
+    }
+
+    /// Reads the device's Binary Object Store (BOS) descriptor with its device capabilities.
+    ///
+    /// Devices that report USB version 2.01 or later have a BOS descriptor. Older devices usually
+    /// stall the request, which is returned as `Pipe`.
+    ///
+    /// ## Errors
+    ///
+    /// * `Pipe` if the device doesn't have a BOS descriptor.
+    /// * `Custom` if the device returns a malformed descriptor.
+    fn read_bos_descriptor(&self, timeout: Duration) -> ::Result<BosDescriptor> {
+        read_descriptor_set(self, LIBUSB_DT_BOS, 0, 5, timeout)
+            .and_then(|buf| BosDescriptor::from_bytes(&buf))
+    }
+
     /// Reads the languages supported by the device's string descriptors.
     ///
     /// This function returns a list of languages that can be used to read the device's string
@@ -47,6 +365,8 @@ pub trait DeviceHandleSyncApi {
     /// Reads a string descriptor from the device.
     ///
     /// `language` should be one of the languages returned from [`read_languages`](#method.read_languages).
+    /// [`choose_language`](fn.choose_language.html) picks the best of them for a list of preferred
+    /// locales.
     fn read_string_descriptor(&self, language: Language, index: u8, timeout: Duration) -> ::Result<String> {
         let mut buf = Vec::<u8>::with_capacity(256);
 
@@ -112,3 +432,290 @@ pub trait DeviceHandleSyncApi {
         }
     }
 }
+
+
+/// Returns the timeout of transfer options that need nothing but a timeout.
+fn timeout_only(options: &TransferOptions) -> ::Result<Duration> {
+    if options.libusb_flags() != 0 || options.stream_id().is_some() {
+        return Err(Error::NotSupported);
+    }
+
+    Ok(options.timeout())
+}
+
+
+/// Sends a standard `GET_STATUS` request and returns the status word.
+fn read_status<T>(handle: &T, recipient: Recipient, index: u16, timeout: Duration) -> ::Result<u16>
+    where T: DeviceHandleSyncApi + ?Sized,
+{
+    let setup = SetupPacket::builder()
+        .direction(Direction::In)
+        .recipient(recipient)
+        .request(LIBUSB_REQUEST_GET_STATUS)
+        .index(index)
+        .length(2)
+        .build();
+
+    let mut buf = [0u8; 2];
+
+    if handle.control_in(setup, &mut buf, timeout)? < buf.len() {
+        return Err(Error::Other);
+    }
+
+    Ok(buf[0] as u16 | (buf[1] as u16) << 8)
+}
+
+
+/// Reads a descriptor that is followed by other descriptors, such as a configuration or BOS
+/// descriptor.
+///
+/// The `header_length` bytes of the header are read first to learn `wTotalLength`, then the whole
+/// set of descriptors is read.
+fn read_descriptor_set<T>(handle: &T, descriptor_type: u8, index: u8, header_length: usize, timeout: Duration) -> ::Result<Vec<u8>>
+    where T: DeviceHandleSyncApi + ?Sized,
+{
+    let mut header = vec![0u8; header_length];
+    let len = handle.read_descriptor(descriptor_type, index, 0, &mut header, timeout)?;
+
+    if len < header.len() {
+        return Err(format!("descriptor header needs {} bytes, got {}", header.len(), len).into());
+    }
+
+    let total_length = header[2] as usize | (header[3] as usize) << 8;
+    let mut buf = vec![0u8; total_length];
+    let len = handle.read_descriptor(descriptor_type, index, 0, &mut buf, timeout)?;
+
+    buf.truncate(len);
+    Ok(buf)
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+    use libusb::*;
+    use test_helpers::MockDevice;
+    use setup_packet::SetupPacket;
+    use transfer_options::{TransferOptions, TransferFlag};
+    use standard_request::{Feature, TestMode};
+    use error::Error;
+    use super::DeviceHandleSyncApi;
+
+    fn timeout() -> Duration {
+        Duration::from_secs(1)
+    }
+
+    #[test]
+    fn it_reads_device_status() {
+        let device = MockDevice::new(|_, _| Ok(vec![0x03, 0x00]));
+        let status = device.read_device_status(timeout()).unwrap();
+
+        assert!(status.self_powered());
+        assert!(status.remote_wakeup());
+        assert_eq!(vec![SetupPacket::new(0x80, LIBUSB_REQUEST_GET_STATUS, 0, 0, 2)], device.setups());
+    }
+
+    #[test]
+    fn it_reads_interface_status() {
+        let device = MockDevice::new(|_, _| Ok(vec![0x02, 0x00]));
+
+        assert_eq!(0x0002, device.read_interface_status(3, timeout()).unwrap());
+        assert_eq!(vec![SetupPacket::new(0x81, LIBUSB_REQUEST_GET_STATUS, 0, 3, 2)], device.setups());
+    }
+
+    #[test]
+    fn it_reads_endpoint_halt() {
+        let device = MockDevice::new(|_, _| Ok(vec![0x01, 0x00]));
+
+        assert!(device.endpoint_halted(0x81, timeout()).unwrap());
+        assert_eq!(vec![SetupPacket::new(0x82, LIBUSB_REQUEST_GET_STATUS, 0, 0x81, 2)], device.setups());
+    }
+
+    #[test]
+    fn it_rejects_short_status() {
+        let device = MockDevice::new(|_, _| Ok(vec![0x01]));
+
+        assert!(match device.read_device_status(timeout()) { Err(Error::Other) => true, _ => false });
+    }
+
+    #[test]
+    fn it_sets_features() {
+        let device = MockDevice::new(|_, _| Ok(vec![]));
+
+        device.set_feature(Feature::DeviceRemoteWakeup, timeout()).unwrap();
+        device.set_feature(Feature::EndpointHalt(0x02), timeout()).unwrap();
+        device.set_feature(Feature::TestMode(TestMode::TestK), timeout()).unwrap();
+
+        assert_eq!(vec![SetupPacket::new(0x00, LIBUSB_REQUEST_SET_FEATURE, 1, 0, 0),
+                        SetupPacket::new(0x02, LIBUSB_REQUEST_SET_FEATURE, 0, 0x02, 0),
+                        SetupPacket::new(0x00, LIBUSB_REQUEST_SET_FEATURE, 2, 0x0200, 0)], device.setups());
+    }
+
+    #[test]
+    fn it_clears_features() {
+        let device = MockDevice::new(|_, _| Ok(vec![]));
+
+        device.clear_feature(Feature::EndpointHalt(0x81), timeout()).unwrap();
+
+        assert_eq!(vec![SetupPacket::new(0x02, LIBUSB_REQUEST_CLEAR_FEATURE, 0, 0x81, 0)], device.setups());
+    }
+
+    #[test]
+    fn it_does_not_clear_test_mode() {
+        let device = MockDevice::new(|_, _| Ok(vec![]));
+
+        assert!(match device.clear_feature(Feature::TestMode(TestMode::TestJ), timeout()) { Err(Error::InvalidParam) => true, _ => false });
+        assert!(device.setups().is_empty());
+    }
+
+    #[test]
+    fn it_reads_configuration() {
+        let device = MockDevice::new(|_, _| Ok(vec![1]));
+
+        assert_eq!(1, device.read_configuration(timeout()).unwrap());
+        assert_eq!(vec![SetupPacket::new(0x80, LIBUSB_REQUEST_GET_CONFIGURATION, 0, 0, 1)], device.setups());
+    }
+
+    #[test]
+    fn it_reads_alternate_setting() {
+        let device = MockDevice::new(|_, _| Ok(vec![2]));
+
+        assert_eq!(2, device.read_alternate_setting(1, timeout()).unwrap());
+        assert_eq!(vec![SetupPacket::new(0x81, LIBUSB_REQUEST_GET_INTERFACE, 0, 1, 1)], device.setups());
+    }
+
+    #[test]
+    fn it_reads_synch_frame() {
+        let device = MockDevice::new(|_, _| Ok(vec![0x34, 0x12]));
+
+        assert_eq!(0x1234, device.synch_frame(0x83, timeout()).unwrap());
+        assert_eq!(vec![SetupPacket::new(0x82, LIBUSB_REQUEST_SYNCH_FRAME, 0, 0x83, 2)], device.setups());
+    }
+
+    #[test]
+    fn it_reads_raw_descriptor() {
+        let device = MockDevice::new(|_, _| Ok(vec![0x09, 0x02, 0x20, 0x00]));
+        let mut buf = [0u8; 9];
+
+        assert_eq!(4, device.read_descriptor(LIBUSB_DT_CONFIG, 1, 0, &mut buf, timeout()).unwrap());
+        assert_eq!([0x09, 0x02, 0x20, 0x00], buf[..4]);
+        assert_eq!(vec![SetupPacket::new(0x80, LIBUSB_REQUEST_GET_DESCRIPTOR, 0x0201, 0, 9)], device.setups());
+    }
+
+    #[test]
+    fn it_passes_control_errors_through() {
+        let device = MockDevice::new(|_, _| Err(Error::Pipe));
+
+        assert!(match device.read_alternate_setting(0, timeout()) { Err(Error::Pipe) => true, _ => false });
+    }
+
+    #[test]
+    fn it_sends_control_data_stage() {
+        let device = MockDevice::new(|_, _| Ok(vec![]));
+        let setup = SetupPacket::new(0x21, 0x09, 0x0200, 0, 2);
+
+        assert_eq!(2, device.control_out(setup, &[0xAA, 0xBB, 0xCC], timeout()).unwrap());
+        assert_eq!(vec![0xAA, 0xBB], device.requests.borrow()[0].1);
+    }
+
+    #[test]
+    fn it_reads_control_data_stage() {
+        let device = MockDevice::new(|_, _| Ok(vec![0x01, 0x02, 0x03]));
+        let setup = SetupPacket::new(0xA1, 0x01, 0x0100, 0, 2);
+        let mut buf = [0u8; 4];
+
+        assert_eq!(2, device.control_in(setup, &mut buf, timeout()).unwrap());
+        assert_eq!([0x01, 0x02, 0x00, 0x00], buf);
+    }
+
+    #[test]
+    fn it_rejects_short_control_data() {
+        let device = MockDevice::new(|_, _| Ok(vec![]));
+        let setup = SetupPacket::new(0x21, 0x09, 0x0200, 0, 4);
+
+        assert!(match device.control_out(setup, &[0xAA], timeout()) { Err(Error::InvalidParam) => true, _ => false });
+    }
+
+    #[test]
+    fn it_rejects_control_transfer_in_wrong_direction() {
+        let device = MockDevice::new(|_, _| Ok(vec![]));
+
+        assert!(match device.control_in(SetupPacket::new(0x21, 0x09, 0, 0, 0), &mut [], timeout()) { Err(Error::InvalidParam) => true, _ => false });
+        assert!(match device.control_out(SetupPacket::new(0xA1, 0x01, 0, 0, 0), &[], timeout()) { Err(Error::InvalidParam) => true, _ => false });
+        assert!(device.setups().is_empty());
+    }
+
+    #[test]
+    fn it_performs_transfers_with_plain_timeout_options() {
+        let device = MockDevice::new(|_, _| Ok(vec![0x01, 0x02]));
+        let mut buf = [0u8; 2];
+
+        assert_eq!(2, device.read_control_with_options(0xC0, 0x01, 0, 0, &mut buf, &TransferOptions::new(timeout())).unwrap());
+        assert_eq!([0x01, 0x02], buf);
+    }
+
+    #[test]
+    fn it_does_not_support_transfer_flags_by_default() {
+        let device = MockDevice::new(|_, _| Ok(vec![]));
+        let options = TransferOptions::new(timeout()).with_flag(TransferFlag::ShortNotOk);
+
+        assert!(match device.write_control_with_options(0x40, 0x01, 0, 0, &[], &options) { Err(Error::NotSupported) => true, _ => false });
+        assert!(match device.read_bulk_with_options(0x81, &mut [0u8; 4], &TransferOptions::new(timeout()).with_stream_id(1)) { Err(Error::NotSupported) => true, _ => false });
+        assert!(device.setups().is_empty());
+    }
+
+    #[test]
+    fn it_rejects_stream_ids_for_control_transfers() {
+        let device = MockDevice::new(|_, _| Ok(vec![]));
+        let options = TransferOptions::new(timeout()).with_stream_id(1);
+
+        assert!(match device.read_control_with_options(0xC0, 0x01, 0, 0, &mut [0u8; 2], &options) { Err(Error::InvalidParam) => true, _ => false });
+        assert!(match device.write_control_with_options(0x40, 0x01, 0, 0, &[], &options) { Err(Error::InvalidParam) => true, _ => false });
+        assert!(device.setups().is_empty());
+    }
+
+    #[test]
+    fn it_reads_device_qualifier() {
+        let device = MockDevice::new(|_, _| Ok(vec![0x0A, 0x06, 0x00, 0x02, 0x00, 0x00, 0x00, 0x40, 0x01, 0x00]));
+        let qualifier = device.read_device_qualifier(timeout()).unwrap();
+
+        assert_eq!(64, qualifier.max_packet_size());
+        assert_eq!(vec![SetupPacket::new(0x80, LIBUSB_REQUEST_GET_DESCRIPTOR, 0x0600, 0, 10)], device.setups());
+    }
+
+    #[test]
+    fn it_reads_other_speed_config_descriptor() {
+        let config = vec![
+            0x09, 0x07, 0x19, 0x00, 0x01, 0x01, 0x00, 0x80, 0x32,
+            0x09, 0x04, 0x00, 0x00, 0x01, 0x08, 0x06, 0x50, 0x00,
+            0x07, 0x05, 0x81, 0x02, 0x40, 0x00, 0x00,
+        ];
+        let device = MockDevice::new(move |_, _| Ok(config.clone()));
+        let other_speed = device.read_other_speed_config_descriptor(0, timeout()).unwrap();
+        let setting = other_speed.interfaces().next().unwrap().descriptors().next().unwrap();
+
+        assert_eq!(64, setting.endpoint_descriptors().next().unwrap().max_packet_size());
+        assert_eq!(vec![SetupPacket::new(0x80, LIBUSB_REQUEST_GET_DESCRIPTOR, 0x0700, 0, 9),
+                        SetupPacket::new(0x80, LIBUSB_REQUEST_GET_DESCRIPTOR, 0x0700, 0, 25)], device.setups());
+    }
+
+    #[test]
+    fn it_reads_bos_descriptor() {
+        let bos = vec![
+            0x05, 0x0F, 0x0C, 0x00, 0x01,
+            0x07, 0x10, 0x02, 0x02, 0x00, 0x00, 0x00,
+        ];
+        let device = MockDevice::new(move |_, _| Ok(bos.clone()));
+        let bos = device.read_bos_descriptor(timeout()).unwrap();
+
+        assert!(bos.usb_2_0_extension().unwrap().lpm_supported());
+        assert_eq!(vec![SetupPacket::new(0x80, LIBUSB_REQUEST_GET_DESCRIPTOR, 0x0F00, 0, 5),
+                        SetupPacket::new(0x80, LIBUSB_REQUEST_GET_DESCRIPTOR, 0x0F00, 0, 12)], device.setups());
+    }
+
+    #[test]
+    fn it_rejects_short_bos_header() {
+        let device = MockDevice::new(|_, _| Ok(vec![0x05, 0x0F, 0x0C]));
+
+        assert!(match device.read_bos_descriptor(timeout()) { Err(Error::Custom(_)) => true, _ => false });
+    }
+}
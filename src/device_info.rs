@@ -0,0 +1,766 @@
+use std::time::Duration;
+
+use io::IoType;
+use device::Device;
+use device_handle::DeviceHandle;
+use device_handle_sync_api::DeviceHandleSyncApi;
+use device_descriptor::DeviceDescriptor;
+use config_descriptor::ConfigDescriptor;
+use interface_descriptor::InterfaceDescriptor;
+use endpoint_descriptor::{self, EndpointDescriptor};
+use endpoint_companion::SuperSpeedEndpointCompanion;
+use bos_descriptor::BosDescriptor;
+use class_code::Class;
+use usb_ids::UsbIds;
+use fields::{Speed, Version, Direction, TransferType, SyncType, UsageType};
+use language::{self, Language};
+use error::Error;
+
+
+/// An owned snapshot of a device's descriptors.
+///
+/// Unlike [`DeviceDescriptor`](struct.DeviceDescriptor.html) and
+/// [`ConfigDescriptor`](struct.ConfigDescriptor.html), a `DeviceInfo` doesn't borrow `libusb`
+/// memory. It can be stored, sent to other threads and compared with the snapshot of another
+/// device. It holds the device descriptor, every configuration with its interfaces, alternate
+/// settings and endpoints, the BOS descriptor and the string descriptors that they refer to if
+/// they were read.
+#[derive(Debug,PartialEq,Eq,Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct DeviceInfo {
+    bus_number: Option<u8>,
+    address: Option<u8>,
+    speed: Speed,
+    usb_version: Version,
+    device_version: Version,
+    class_code: u8,
+    sub_class_code: u8,
+    protocol_code: u8,
+    vendor_id: u16,
+    product_id: u16,
+    max_packet_size: u8,
+    manufacturer_string_index: Option<u8>,
+    product_string_index: Option<u8>,
+    serial_number_string_index: Option<u8>,
+    manufacturer: Option<String>,
+    product: Option<String>,
+    serial_number: Option<String>,
+    configurations: Vec<ConfigInfo>,
+    bos: Option<BosDescriptor>,
+}
+
+impl DeviceInfo {
+    /// Takes a snapshot of a device.
+    ///
+    /// All of the device's configuration descriptors are read. If `handle` is an open handle for
+    /// the device, the string descriptors are read with it in the device's first language, as by
+    /// [`read_strings`](#method.read_strings), and so is the BOS descriptor of a device that
+    /// reports USB 2.01 or later, as by [`read_bos`](#method.read_bos).
+    pub fn from_device<'ctx, Io>(device: &Device<'ctx, Io>, handle: Option<&DeviceHandle<'ctx, Io>>, timeout: Duration) -> ::Result<Self>
+        where Io: IoType<'ctx>,
+              DeviceHandle<'ctx, Io>: DeviceHandleSyncApi,
+    {
+        let descriptor = device.device_descriptor()?;
+        let mut configs = Vec::with_capacity(descriptor.num_configurations() as usize);
+
+        for i in 0..descriptor.num_configurations() {
+            configs.push(device.config_descriptor(i)?);
+        }
+
+        let mut info = DeviceInfo::from_descriptors(&descriptor, &configs);
+        info.bus_number = Some(device.bus_number());
+        info.address = Some(device.address());
+        info.speed = device.speed();
+
+        if let Some(handle) = handle {
+            info.read_strings(handle, timeout)?;
+
+            let version = info.usb_version;
+            if (version.major(), version.minor(), version.sub_minor()) >= (2, 0, 1) {
+                info.read_bos(handle, timeout)?;
+            }
+        }
+
+        Ok(info)
+    }
+
+    /// Takes a snapshot of a device descriptor and its configurations.
+    ///
+    /// The device's location and speed are unknown, no strings are resolved and there is no BOS
+    /// descriptor.
+    pub fn from_descriptors(device: &DeviceDescriptor, configs: &[ConfigDescriptor]) -> Self {
+        DeviceInfo {
+            bus_number: None,
+            address: None,
+            speed: Speed::Unknown,
+            usb_version: device.usb_version(),
+            device_version: device.device_version(),
+            class_code: device.class_code(),
+            sub_class_code: device.sub_class_code(),
+            protocol_code: device.protocol_code(),
+            vendor_id: device.vendor_id(),
+            product_id: device.product_id(),
+            max_packet_size: device.max_packet_size(),
+            manufacturer_string_index: device.manufacturer_string_index(),
+            product_string_index: device.product_string_index(),
+            serial_number_string_index: device.serial_number_string_index(),
+            manufacturer: None,
+            product: None,
+            serial_number: None,
+            configurations: configs.iter().map(ConfigInfo::from_descriptor).collect(),
+            bos: None,
+        }
+    }
+
+    /// Reads the string descriptors that the snapshot refers to.
+    ///
+    /// Strings are read in the device's first language. Devices without string descriptors
+    /// usually stall the request for the list of languages, in which case no strings are read.
+    /// Strings that can't be read are left unresolved.
+    ///
+    /// ## Errors
+    ///
+    /// Errors other than `Pipe` that occur while reading the list of languages are returned.
+    pub fn read_strings<H>(&mut self, handle: &H, timeout: Duration) -> ::Result<()>
+        where H: DeviceHandleSyncApi + ?Sized,
+    {
+        self.read_strings_preferring(handle, &[], timeout)
+    }
+
+    /// Reads the string descriptors that the snapshot refers to in a preferred language.
+    ///
+    /// The language is chosen from the device's languages with
+    /// [`choose_language`](fn.choose_language.html), so `preferred` is a list of BCP 47 tags in
+    /// order of preference. Otherwise this behaves like [`read_strings`](#method.read_strings).
+    pub fn read_strings_preferring<H>(&mut self, handle: &H, preferred: &[&str], timeout: Duration) -> ::Result<()>
+        where H: DeviceHandleSyncApi + ?Sized,
+    {
+        let language = match handle.read_languages(timeout) {
+            Ok(languages) => match language::choose_language(preferred, &languages) {
+                Some(language) => language,
+                None => return Ok(()),
+            },
+            Err(Error::Pipe) => return Ok(()),
+            Err(e) => return Err(e),
+        };
+
+        let read = |index: Option<u8>| -> Option<String> {
+            index.and_then(|n| read_string(handle, language, n, timeout))
+        };
+
+        self.manufacturer = read(self.manufacturer_string_index);
+        self.product = read(self.product_string_index);
+        self.serial_number = read(self.serial_number_string_index);
+
+        for config in &mut self.configurations {
+            config.description = read(config.description_string_index);
+
+            for interface in &mut config.interfaces {
+                for setting in &mut interface.settings {
+                    setting.description = read(setting.description_string_index);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Reads the device's BOS descriptor.
+    ///
+    /// Devices without a BOS descriptor stall the request, in which case the snapshot is left
+    /// without one. So is the snapshot of a device whose BOS descriptor is malformed.
+    ///
+    /// ## Errors
+    ///
+    /// Errors other than `Pipe` that occur while reading the descriptor are returned.
+    pub fn read_bos<H>(&mut self, handle: &H, timeout: Duration) -> ::Result<()>
+        where H: DeviceHandleSyncApi + ?Sized,
+    {
+        self.bos = match handle.read_bos_descriptor(timeout) {
+            Ok(bos) => Some(bos),
+            Err(Error::Pipe) => None,
+            Err(Error::Custom(message)) => {
+                debug!("could not parse BOS descriptor: {}", message);
+                None
+            },
+            Err(e) => return Err(e),
+        };
+
+        Ok(())
+    }
+
+    /// Returns the number of the bus that the device was connected to.
+    pub fn bus_number(&self) -> Option<u8> {
+        self.bus_number
+    }
+
+    /// Returns the device's address on its bus.
+    pub fn address(&self) -> Option<u8> {
+        self.address
+    }
+
+    /// Returns the device's connection speed.
+    pub fn speed(&self) -> Speed {
+        self.speed
+    }
+
+    /// Returns the device's maximum supported USB version.
+    pub fn usb_version(&self) -> Version {
+        self.usb_version
+    }
+
+    /// Returns the manufacturer's version of the device.
+    pub fn device_version(&self) -> Version {
+        self.device_version
+    }
+
+    /// Returns the device's class code.
+    pub fn class_code(&self) -> u8 {
+        self.class_code
+    }
+
+    /// Returns the device's sub class code.
+    pub fn sub_class_code(&self) -> u8 {
+        self.sub_class_code
+    }
+
+    /// Returns the device's protocol code.
+    pub fn protocol_code(&self) -> u8 {
+        self.protocol_code
+    }
+
+    /// Returns the device's class, sub class and protocol codes as a [`Class`](struct.Class.html).
+    pub fn class(&self) -> Class {
+        Class::new(self.class_code, self.sub_class_code, self.protocol_code)
+    }
+
+    /// Returns the device's vendor ID.
+    pub fn vendor_id(&self) -> u16 {
+        self.vendor_id
+    }
+
+    /// Returns the device's product ID.
+    pub fn product_id(&self) -> u16 {
+        self.product_id
+    }
+
+    /// Looks up the name of the device's vendor in the USB ID database.
+    pub fn vendor_name<'a>(&self, ids: &'a UsbIds) -> Option<&'a str> {
+        ids.vendor_name(self.vendor_id())
+    }
+
+    /// Looks up the name of the device's product in the USB ID database.
+    ///
+    /// Unlike the product string descriptor, the name doesn't require opening the device.
+    pub fn product_name<'a>(&self, ids: &'a UsbIds) -> Option<&'a str> {
+        ids.product_name(self.vendor_id(), self.product_id())
+    }
+
+    /// Returns the maximum packet size for the device's control endpoint.
+    pub fn max_packet_size(&self) -> u8 {
+        self.max_packet_size
+    }
+
+    /// Returns the index of the string descriptor that contains the manufacturer name.
+    pub fn manufacturer_string_index(&self) -> Option<u8> {
+        self.manufacturer_string_index
+    }
+
+    /// Returns the index of the string descriptor that contains the product name.
+    pub fn product_string_index(&self) -> Option<u8> {
+        self.product_string_index
+    }
+
+    /// Returns the index of the string descriptor that contains the device's serial number.
+    pub fn serial_number_string_index(&self) -> Option<u8> {
+        self.serial_number_string_index
+    }
+
+    /// Returns the manufacturer name, if it was read.
+    pub fn manufacturer(&self) -> Option<&str> {
+        self.manufacturer.as_ref().map(|s| &s[..])
+    }
+
+    /// Returns the product name, if it was read.
+    pub fn product(&self) -> Option<&str> {
+        self.product.as_ref().map(|s| &s[..])
+    }
+
+    /// Returns the device's serial number, if it was read.
+    pub fn serial_number(&self) -> Option<&str> {
+        self.serial_number.as_ref().map(|s| &s[..])
+    }
+
+    /// Returns the device's configurations.
+    pub fn configurations(&self) -> &[ConfigInfo] {
+        &self.configurations
+    }
+
+    /// Returns the device's BOS descriptor, if it was read.
+    pub fn bos(&self) -> Option<&BosDescriptor> {
+        self.bos.as_ref()
+    }
+}
+
+/// An owned snapshot of a configuration descriptor.
+#[derive(Debug,PartialEq,Eq,Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ConfigInfo {
+    number: u8,
+    max_power: u16,
+    self_powered: bool,
+    remote_wakeup: bool,
+    description_string_index: Option<u8>,
+    description: Option<String>,
+    interfaces: Vec<InterfaceInfo>,
+    extra: Vec<u8>,
+}
+
+impl ConfigInfo {
+    /// Takes a snapshot of a configuration descriptor.
+    pub fn from_descriptor(config: &ConfigDescriptor) -> Self {
+        ConfigInfo {
+            number: config.number(),
+            max_power: config.max_power(),
+            self_powered: config.self_powered(),
+            remote_wakeup: config.remote_wakeup(),
+            description_string_index: config.description_string_index(),
+            description: None,
+            interfaces: config.interfaces().map(|interface| {
+                InterfaceInfo {
+                    number: interface.number(),
+                    settings: interface.descriptors().map(|setting| AltSettingInfo::from_descriptor(&setting)).collect(),
+                }
+            }).collect(),
+            extra: config.extra().to_vec(),
+        }
+    }
+
+    /// Returns the configuration number.
+    pub fn number(&self) -> u8 {
+        self.number
+    }
+
+    /// Returns the device's maximum power consumption (in milliwatts) in this configuration.
+    pub fn max_power(&self) -> u16 {
+        self.max_power
+    }
+
+    /// Indicates if the device is self-powered in this configuration.
+    pub fn self_powered(&self) -> bool {
+        self.self_powered
+    }
+
+    /// Indicates if the device has remote wakeup capability in this configuration.
+    pub fn remote_wakeup(&self) -> bool {
+        self.remote_wakeup
+    }
+
+    /// Returns the index of the string descriptor that describes the configuration.
+    pub fn description_string_index(&self) -> Option<u8> {
+        self.description_string_index
+    }
+
+    /// Returns the configuration's description, if it was read.
+    pub fn description(&self) -> Option<&str> {
+        self.description.as_ref().map(|s| &s[..])
+    }
+
+    /// Returns the configuration's interfaces.
+    pub fn interfaces(&self) -> &[InterfaceInfo] {
+        &self.interfaces
+    }
+
+    /// Returns the extra descriptors that follow the configuration descriptor.
+    pub fn extra(&self) -> &[u8] {
+        &self.extra
+    }
+}
+
+/// An owned snapshot of an interface and its alternate settings.
+#[derive(Debug,PartialEq,Eq,Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct InterfaceInfo {
+    number: u8,
+    settings: Vec<AltSettingInfo>,
+}
+
+impl InterfaceInfo {
+    /// Returns the interface's number.
+    pub fn number(&self) -> u8 {
+        self.number
+    }
+
+    /// Returns the interface's alternate settings.
+    pub fn settings(&self) -> &[AltSettingInfo] {
+        &self.settings
+    }
+}
+
+/// An owned snapshot of an interface descriptor, which describes an alternate setting.
+#[derive(Debug,PartialEq,Eq,Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct AltSettingInfo {
+    interface_number: u8,
+    setting_number: u8,
+    class_code: u8,
+    sub_class_code: u8,
+    protocol_code: u8,
+    description_string_index: Option<u8>,
+    description: Option<String>,
+    endpoints: Vec<EndpointInfo>,
+    extra: Vec<u8>,
+}
+
+impl AltSettingInfo {
+    /// Takes a snapshot of an interface descriptor.
+    pub fn from_descriptor(setting: &InterfaceDescriptor) -> Self {
+        AltSettingInfo {
+            interface_number: setting.interface_number(),
+            setting_number: setting.setting_number(),
+            class_code: setting.class_code(),
+            sub_class_code: setting.sub_class_code(),
+            protocol_code: setting.protocol_code(),
+            description_string_index: setting.description_string_index(),
+            description: None,
+            endpoints: setting.endpoint_descriptors().map(|endpoint| EndpointInfo::from_descriptor(&endpoint)).collect(),
+            extra: setting.extra().to_vec(),
+        }
+    }
+
+    /// Returns the interface's number.
+    pub fn interface_number(&self) -> u8 {
+        self.interface_number
+    }
+
+    /// Returns the alternate setting number.
+    pub fn setting_number(&self) -> u8 {
+        self.setting_number
+    }
+
+    /// Returns the interface's class code.
+    pub fn class_code(&self) -> u8 {
+        self.class_code
+    }
+
+    /// Returns the interface's sub class code.
+    pub fn sub_class_code(&self) -> u8 {
+        self.sub_class_code
+    }
+
+    /// Returns the interface's protocol code.
+    pub fn protocol_code(&self) -> u8 {
+        self.protocol_code
+    }
+
+    /// Returns the interface's class, sub class and protocol codes as a [`Class`](struct.Class.html).
+    pub fn class(&self) -> Class {
+        Class::new(self.class_code, self.sub_class_code, self.protocol_code)
+    }
+
+    /// Returns the index of the string descriptor that describes the interface.
+    pub fn description_string_index(&self) -> Option<u8> {
+        self.description_string_index
+    }
+
+    /// Returns the interface's description, if it was read.
+    pub fn description(&self) -> Option<&str> {
+        self.description.as_ref().map(|s| &s[..])
+    }
+
+    /// Returns the alternate setting's endpoints.
+    pub fn endpoints(&self) -> &[EndpointInfo] {
+        &self.endpoints
+    }
+
+    /// Returns the extra descriptors that follow the interface descriptor.
+    pub fn extra(&self) -> &[u8] {
+        &self.extra
+    }
+}
+
+/// An owned snapshot of an endpoint descriptor.
+#[derive(Debug,PartialEq,Eq,Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct EndpointInfo {
+    address: u8,
+    direction: Direction,
+    transfer_type: TransferType,
+    sync_type: SyncType,
+    usage_type: UsageType,
+    max_packet_size: u16,
+    interval: u8,
+    ss_companion: Option<SuperSpeedEndpointCompanion>,
+    extra: Vec<u8>,
+}
+
+impl EndpointInfo {
+    /// Takes a snapshot of an endpoint descriptor.
+    pub fn from_descriptor(endpoint: &EndpointDescriptor) -> Self {
+        EndpointInfo {
+            address: endpoint.address(),
+            direction: endpoint.direction(),
+            transfer_type: endpoint.transfer_type(),
+            sync_type: endpoint.sync_type(),
+            usage_type: endpoint.usage_type(),
+            max_packet_size: endpoint.max_packet_size(),
+            interval: endpoint.interval(),
+            ss_companion: endpoint.ss_companion(),
+            extra: endpoint.extra().to_vec(),
+        }
+    }
+
+    /// Returns the endpoint's address.
+    pub fn address(&self) -> u8 {
+        self.address
+    }
+
+    /// Returns the endpoint number.
+    pub fn number(&self) -> u8 {
+        self.address & 0x07
+    }
+
+    /// Returns the endpoint's direction.
+    pub fn direction(&self) -> Direction {
+        self.direction
+    }
+
+    /// Returns the endpoint's transfer type.
+    pub fn transfer_type(&self) -> TransferType {
+        self.transfer_type
+    }
+
+    /// Returns the endpoint's synchronisation mode.
+    ///
+    /// The return value of this method is only valid for isochronous endpoints.
+    pub fn sync_type(&self) -> SyncType {
+        self.sync_type
+    }
+
+    /// Returns the endpoint's usage type.
+    ///
+    /// The return value of this method is only valid for isochronous endpoints.
+    pub fn usage_type(&self) -> UsageType {
+        self.usage_type
+    }
+
+    /// Returns the endpoint's maximum packet size.
+    pub fn max_packet_size(&self) -> u16 {
+        self.max_packet_size
+    }
+
+    /// Returns the endpoint's polling interval.
+    pub fn interval(&self) -> u8 {
+        self.interval
+    }
+
+    /// Returns the interval at which the host services the endpoint when the device operates at
+    /// `speed`.
+    ///
+    /// See [`EndpointDescriptor::service_interval`](struct.EndpointDescriptor.html#method.service_interval).
+    pub fn service_interval(&self, speed: Speed) -> Option<Duration> {
+        endpoint_descriptor::service_interval(self.transfer_type, self.interval, speed)
+    }
+
+    /// Returns the endpoint's SuperSpeed endpoint companion descriptor.
+    pub fn ss_companion(&self) -> Option<SuperSpeedEndpointCompanion> {
+        self.ss_companion
+    }
+
+    /// Returns the extra descriptors that follow the endpoint descriptor.
+    pub fn extra(&self) -> &[u8] {
+        &self.extra
+    }
+}
+
+
+fn read_string<H>(handle: &H, language: Language, index: u8, timeout: Duration) -> Option<String>
+    where H: DeviceHandleSyncApi + ?Sized,
+{
+    match handle.read_string_descriptor(language, index, timeout) {
+        Ok(s) => Some(s),
+        Err(e) => {
+            debug!("could not read string descriptor {}: {}", index, e);
+            None
+        },
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use std::thread;
+    use std::time::Duration;
+    use libusb::*;
+    use error::Error;
+    use descriptor_parser::parse_descriptors;
+    use fields::{Direction, TransferType, Version};
+    use descriptor_builder::string_descriptor;
+    use test_helpers::MockDevice;
+    use super::DeviceInfo;
+
+    const DESCRIPTORS: &'static [u8] = &[
+        0x12, 0x01, 0x00, 0x02, 0x00, 0x00, 0x00, 0x40, 0x81, 0x07, 0x51, 0x55, 0x00, 0x01, 0x01, 0x02, 0x00, 0x01,
+        0x09, 0x02, 0x20, 0x00, 0x01, 0x01, 0x03, 0x80, 0x32,
+        0x09, 0x04, 0x00, 0x00, 0x02, 0x08, 0x06, 0x50, 0x00,
+        0x07, 0x05, 0x81, 0x02, 0x00, 0x02, 0x00,
+        0x07, 0x05, 0x02, 0x02, 0x00, 0x02, 0x00,
+    ];
+
+    fn info() -> DeviceInfo {
+        let (device, configs) = parse_descriptors(DESCRIPTORS).unwrap();
+        DeviceInfo::from_descriptors(&device, &configs)
+    }
+
+    fn timeout() -> Duration {
+        Duration::from_secs(1)
+    }
+
+    #[test]
+    fn it_copies_device_fields() {
+        let info = info();
+
+        assert_eq!(Version(2, 0, 0), info.usb_version());
+        assert_eq!(0x0781, info.vendor_id());
+        assert_eq!(0x5551, info.product_id());
+        assert_eq!(None, info.bus_number());
+        assert_eq!(None, info.serial_number_string_index());
+        assert_eq!(None, info.product());
+    }
+
+    #[test]
+    fn it_copies_configuration_tree() {
+        let info = info();
+        let config = &info.configurations()[0];
+        let setting = &config.interfaces()[0].settings()[0];
+        let endpoints: Vec<(u8, Direction, TransferType, u16)> = setting.endpoints().iter().map(|endpoint| {
+            (endpoint.address(), endpoint.direction(), endpoint.transfer_type(), endpoint.max_packet_size())
+        }).collect();
+
+        assert_eq!(1, config.number());
+        assert_eq!(Some(3), config.description_string_index());
+        assert_eq!(0x08, setting.class_code());
+        assert_eq!(vec![(0x81, Direction::In, TransferType::Bulk, 512),
+                        (0x02, Direction::Out, TransferType::Bulk, 512)], endpoints);
+    }
+
+    #[test]
+    fn it_compares_snapshots() {
+        let mut other = info();
+        assert_eq!(info(), other.clone());
+
+        other.configurations[0].interfaces[0].settings[0].endpoints[0].max_packet_size = 64;
+        assert!(info() != other);
+    }
+
+    #[test]
+    fn it_can_be_sent_to_other_threads() {
+        let info = info();
+        let product_id = thread::spawn(move || info.product_id()).join().unwrap();
+
+        assert_eq!(0x5551, product_id);
+    }
+
+    #[test]
+    fn it_reads_strings_in_first_language() {
+        let device = MockDevice::new(|setup, _| {
+            match (setup.value() as u8, setup.index()) {
+                (0, 0) => Ok(vec![0x06, LIBUSB_DT_STRING, 0x09, 0x04, 0x07, 0x04]),
+                (1, 0x0409) => Ok(string_descriptor("SanDisk")),
+                (2, 0x0409) => Ok(string_descriptor("Cruzer")),
+                _ => Err(Error::Pipe),
+            }
+        });
+
+        let mut info = info();
+        info.read_strings(&device, timeout()).unwrap();
+
+        assert_eq!(Some("SanDisk"), info.manufacturer());
+        assert_eq!(Some("Cruzer"), info.product());
+        assert_eq!(None, info.serial_number());
+        assert_eq!(None, info.configurations()[0].description());
+    }
+
+    #[test]
+    fn it_reads_strings_in_preferred_language() {
+        let device = MockDevice::new(|setup, _| {
+            match (setup.value() as u8, setup.index()) {
+                (0, 0) => Ok(vec![0x06, LIBUSB_DT_STRING, 0x09, 0x04, 0x07, 0x04]),
+                (1, 0x0407) => Ok(string_descriptor("SanDisk")),
+                (2, 0x0407) => Ok(string_descriptor("Cruzer Speicherstick")),
+                _ => Err(Error::Pipe),
+            }
+        });
+
+        let mut info = info();
+        info.read_strings_preferring(&device, &["de-AT", "en"], timeout()).unwrap();
+
+        assert_eq!(Some("SanDisk"), info.manufacturer());
+        assert_eq!(Some("Cruzer Speicherstick"), info.product());
+    }
+
+    #[test]
+    fn it_skips_strings_when_device_has_none() {
+        let device = MockDevice::new(|_, _| Err(Error::Pipe));
+        let mut snapshot = info();
+
+        snapshot.read_strings(&device, timeout()).unwrap();
+
+        assert_eq!(info(), snapshot);
+    }
+
+    #[test]
+    fn it_returns_language_errors() {
+        let device = MockDevice::new(|_, _| Err(Error::NoDevice));
+
+        assert!(match info().read_strings(&device, timeout()) { Err(Error::NoDevice) => true, _ => false });
+    }
+
+    #[test]
+    fn it_reads_bos_descriptor() {
+        let device = MockDevice::new(|setup, _| {
+            match setup.value() >> 8 {
+                0x0F => Ok(vec![0x05, 0x0F, 0x0C, 0x00, 0x01, 0x07, 0x10, 0x02, 0x02, 0x00, 0x00, 0x00]),
+                _ => Err(Error::Pipe),
+            }
+        });
+
+        let mut info = info();
+        info.read_bos(&device, timeout()).unwrap();
+
+        assert!(info.bos().unwrap().usb_2_0_extension().unwrap().lpm_supported());
+    }
+
+    #[test]
+    fn it_skips_missing_or_malformed_bos_descriptor() {
+        let stalled = MockDevice::new(|_, _| Err(Error::Pipe));
+        let malformed = MockDevice::new(|_, _| Ok(vec![0x05, 0x0F, 0x0C, 0x00, 0x01, 0x07, 0x10, 0x02]));
+
+        let mut info = info();
+        info.read_bos(&stalled, timeout()).unwrap();
+        assert_eq!(None, info.bos());
+
+        info.read_bos(&malformed, timeout()).unwrap();
+        assert_eq!(None, info.bos());
+    }
+
+    #[test]
+    fn it_returns_bos_errors() {
+        let device = MockDevice::new(|_, _| Err(Error::Timeout));
+
+        assert!(match info().read_bos(&device, timeout()) { Err(Error::Timeout) => true, _ => false });
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn it_round_trips_through_serde() {
+        use serde_json;
+
+        let mut snapshot = info();
+        snapshot.manufacturer = Some("Manufacturer".to_string());
+
+        let json = serde_json::to_string(&snapshot).unwrap();
+
+        assert_eq!(snapshot, serde_json::from_str(&json).unwrap());
+    }
+}
@@ -0,0 +1,72 @@
+use fields::Version;
+use descriptor_parser;
+
+/// Describes how a high-speed capable device would operate at its other speed.
+///
+/// A device that supports high speed returns the device qualifier descriptor to report the fields
+/// of its device descriptor that would change if it were operating at full speed, or at high
+/// speed while it's operating at full speed.
+#[derive(Debug,PartialEq,Eq,Clone,Copy,Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct DeviceQualifier {
+    bcd_usb: u16,
+    class_code: u8,
+    sub_class_code: u8,
+    protocol_code: u8,
+    max_packet_size: u8,
+    num_configurations: u8,
+}
+
+impl DeviceQualifier {
+    /// Parses a device qualifier descriptor from its raw bytes.
+    ///
+    /// ## Errors
+    ///
+    /// * `Custom` if the bytes don't hold a device qualifier descriptor.
+    pub fn from_bytes(bytes: &[u8]) -> ::Result<Self> {
+        descriptor_parser::parse_device_qualifier(bytes)
+    }
+
+    /// Returns the USB version that the device supports at the other speed.
+    pub fn usb_version(&self) -> Version {
+        Version::from_bcd(self.bcd_usb)
+    }
+
+    /// Returns the device's class code at the other speed.
+    pub fn class_code(&self) -> u8 {
+        self.class_code
+    }
+
+    /// Returns the device's sub class code at the other speed.
+    pub fn sub_class_code(&self) -> u8 {
+        self.sub_class_code
+    }
+
+    /// Returns the device's protocol code at the other speed.
+    pub fn protocol_code(&self) -> u8 {
+        self.protocol_code
+    }
+
+    /// Returns the maximum packet size of the device's control endpoint at the other speed.
+    pub fn max_packet_size(&self) -> u8 {
+        self.max_packet_size
+    }
+
+    /// Returns the number of configurations that the device has at the other speed.
+    pub fn num_configurations(&self) -> u8 {
+        self.num_configurations
+    }
+}
+
+
+#[doc(hidden)]
+pub fn from_raw(bytes: &[u8]) -> DeviceQualifier {
+    DeviceQualifier {
+        bcd_usb: bytes[2] as u16 | (bytes[3] as u16) << 8,
+        class_code: bytes[4],
+        sub_class_code: bytes[5],
+        protocol_code: bytes[6],
+        max_packet_size: bytes[7],
+        num_configurations: bytes[8],
+    }
+}
@@ -0,0 +1,260 @@
+use std::fmt;
+use std::marker::PhantomData;
+use std::time::Duration;
+
+use io::IoType;
+use device_handle::DeviceHandle;
+use device_handle_sync_api::DeviceHandleSyncApi;
+use endpoint_descriptor::EndpointDescriptor;
+use fields::{Direction, TransferType};
+use transfer_options::TransferOptions;
+
+
+/// The direction of an [`Endpoint`](struct.Endpoint.html), either [`In`](enum.In.html) or
+/// [`Out`](enum.Out.html).
+pub trait EndpointDirection {
+    /// Returns the direction.
+    fn direction() -> Direction;
+}
+
+/// The transfer type of an [`Endpoint`](struct.Endpoint.html), either [`Bulk`](enum.Bulk.html) or
+/// [`Interrupt`](enum.Interrupt.html).
+pub trait EndpointKind {
+    /// Returns the transfer type.
+    fn transfer_type() -> TransferType;
+}
+
+/// Marks an endpoint that transfers data from the device to the host.
+#[derive(Debug)]
+pub enum In {}
+
+/// Marks an endpoint that transfers data from the host to the device.
+#[derive(Debug)]
+pub enum Out {}
+
+/// Marks a bulk endpoint.
+#[derive(Debug)]
+pub enum Bulk {}
+
+/// Marks an interrupt endpoint.
+#[derive(Debug)]
+pub enum Interrupt {}
+
+impl EndpointDirection for In {
+    fn direction() -> Direction { Direction::In }
+}
+
+impl EndpointDirection for Out {
+    fn direction() -> Direction { Direction::Out }
+}
+
+impl EndpointKind for Bulk {
+    fn transfer_type() -> TransferType { TransferType::Bulk }
+}
+
+impl EndpointKind for Interrupt {
+    fn transfer_type() -> TransferType { TransferType::Interrupt }
+}
+
+/// An endpoint of a claimed interface.
+///
+/// Endpoints are obtained with
+/// [`ClaimedInterface::endpoint`](struct.ClaimedInterface.html#method.endpoint), which checks the
+/// endpoint's direction and transfer type against its descriptor. The direction and transfer type
+/// are part of the endpoint's type, so only the matching transfers can be made: an `In` endpoint
+/// can only be read and an `Interrupt` endpoint only uses interrupt transfers.
+///
+/// Endpoints borrow the claimed interface and can be copied and sent to other threads, so
+/// transfers on different endpoints of an interface can run in parallel.
+pub struct Endpoint<'i, 'ctx: 'i, Io, Dir, Kind>
+    where Io: IoType<'ctx>,
+{
+    handle: &'i DeviceHandle<'ctx, Io>,
+    address: u8,
+    max_packet_size: u16,
+    interval: u8,
+    _marker: PhantomData<(Dir, Kind)>,
+}
+
+impl<'i, 'ctx, Io, Dir, Kind> Clone for Endpoint<'i, 'ctx, Io, Dir, Kind>
+    where Io: IoType<'ctx>,
+{
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'i, 'ctx, Io, Dir, Kind> Copy for Endpoint<'i, 'ctx, Io, Dir, Kind>
+    where Io: IoType<'ctx>,
+{}
+
+impl<'i, 'ctx, Io, Dir, Kind> fmt::Debug for Endpoint<'i, 'ctx, Io, Dir, Kind>
+    where Io: IoType<'ctx>,
+          Dir: EndpointDirection,
+          Kind: EndpointKind,
+{
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        fmt.debug_struct("Endpoint")
+            .field("address", &self.address)
+            .field("direction", &Dir::direction())
+            .field("transfer_type", &Kind::transfer_type())
+            .field("max_packet_size", &self.max_packet_size)
+            .field("interval", &self.interval)
+            .finish()
+    }
+}
+
+impl<'i, 'ctx, Io, Dir, Kind> Endpoint<'i, 'ctx, Io, Dir, Kind>
+    where Io: IoType<'ctx>,
+          Dir: EndpointDirection,
+          Kind: EndpointKind,
+{
+    /// Returns the endpoint's address.
+    pub fn address(&self) -> u8 {
+        self.address
+    }
+
+    /// Returns the endpoint's direction.
+    pub fn direction(&self) -> Direction {
+        Dir::direction()
+    }
+
+    /// Returns the endpoint's transfer type.
+    pub fn transfer_type(&self) -> TransferType {
+        Kind::transfer_type()
+    }
+
+    /// Returns the endpoint's maximum packet size.
+    pub fn max_packet_size(&self) -> u16 {
+        self.max_packet_size
+    }
+
+    /// Returns the endpoint's polling interval.
+    pub fn interval(&self) -> u8 {
+        self.interval
+    }
+
+    /// Clears the endpoint's halt condition.
+    pub fn clear_halt(&self) -> ::Result<()> {
+        self.handle.clear_halt(self.address)
+    }
+}
+
+impl<'i, 'ctx, Io, Dir, Kind> Endpoint<'i, 'ctx, Io, Dir, Kind>
+    where Io: IoType<'ctx>,
+          Dir: EndpointDirection,
+          Kind: EndpointKind,
+          DeviceHandle<'ctx, Io>: DeviceHandleSyncApi,
+{
+    /// Indicates if the endpoint is halted.
+    pub fn halted(&self, timeout: Duration) -> ::Result<bool> {
+        self.handle.endpoint_halted(self.address, timeout)
+    }
+}
+
+impl<'i, 'ctx, Io, Kind> Endpoint<'i, 'ctx, Io, In, Kind>
+    where Io: IoType<'ctx>,
+          Kind: EndpointKind,
+          DeviceHandle<'ctx, Io>: DeviceHandleSyncApi,
+{
+    /// Reads from the endpoint.
+    ///
+    /// This uses a bulk or an interrupt transfer depending on the endpoint's transfer type, and
+    /// otherwise behaves like
+    /// [`read_bulk_with_options`](trait.DeviceHandleSyncApi.html#method.read_bulk_with_options).
+    pub fn read<O: Into<TransferOptions>>(&self, buf: &mut [u8], options: O) -> ::Result<usize> {
+        match Kind::transfer_type() {
+            TransferType::Interrupt => self.handle.read_interrupt_with_options(self.address, buf, &options.into()),
+            _                       => self.handle.read_bulk_with_options(self.address, buf, &options.into()),
+        }
+    }
+}
+
+impl<'i, 'ctx, Io, Kind> Endpoint<'i, 'ctx, Io, Out, Kind>
+    where Io: IoType<'ctx>,
+          Kind: EndpointKind,
+          DeviceHandle<'ctx, Io>: DeviceHandleSyncApi,
+{
+    /// Writes to the endpoint.
+    ///
+    /// This uses a bulk or an interrupt transfer depending on the endpoint's transfer type, and
+    /// otherwise behaves like
+    /// [`write_bulk_with_options`](trait.DeviceHandleSyncApi.html#method.write_bulk_with_options).
+    pub fn write<O: Into<TransferOptions>>(&self, buf: &[u8], options: O) -> ::Result<usize> {
+        match Kind::transfer_type() {
+            TransferType::Interrupt => self.handle.write_interrupt_with_options(self.address, buf, &options.into()),
+            _                       => self.handle.write_bulk_with_options(self.address, buf, &options.into()),
+        }
+    }
+}
+
+
+/// Checks that an endpoint descriptor matches an endpoint's direction and transfer type.
+fn check<Dir, Kind>(descriptor: &EndpointDescriptor) -> ::Result<()>
+    where Dir: EndpointDirection,
+          Kind: EndpointKind,
+{
+    if descriptor.direction() != Dir::direction() || descriptor.transfer_type() != Kind::transfer_type() {
+        return Err(::Error::InvalidParam);
+    }
+
+    Ok(())
+}
+
+#[doc(hidden)]
+pub fn from_descriptor<'i, 'ctx, Io, Dir, Kind>(handle: &'i DeviceHandle<'ctx, Io>, descriptor: &EndpointDescriptor) -> ::Result<Endpoint<'i, 'ctx, Io, Dir, Kind>>
+    where Io: IoType<'ctx>,
+          Dir: EndpointDirection,
+          Kind: EndpointKind,
+{
+    check::<Dir, Kind>(descriptor)?;
+
+    Ok(Endpoint {
+        handle: handle,
+        address: descriptor.address(),
+        max_packet_size: descriptor.max_packet_size(),
+        interval: descriptor.interval(),
+        _marker: PhantomData,
+    })
+}
+
+
+#[cfg(test)]
+mod test {
+    use io::sync::SyncIo;
+    use endpoint_descriptor;
+    use error::Error;
+    use super::{Endpoint, In, Out, Bulk, Interrupt, check};
+
+    #[test]
+    fn it_accepts_matching_endpoint() {
+        let bulk_in = endpoint_descriptor!(bEndpointAddress: 0x81, bmAttributes: 0x02);
+        let interrupt_out = endpoint_descriptor!(bEndpointAddress: 0x02, bmAttributes: 0x03);
+
+        assert!(check::<In, Bulk>(&endpoint_descriptor::from_libusb(&bulk_in)).is_ok());
+        assert!(check::<Out, Interrupt>(&endpoint_descriptor::from_libusb(&interrupt_out)).is_ok());
+    }
+
+    #[test]
+    fn it_rejects_wrong_direction() {
+        let bulk_in = endpoint_descriptor!(bEndpointAddress: 0x81, bmAttributes: 0x02);
+
+        assert!(match check::<Out, Bulk>(&endpoint_descriptor::from_libusb(&bulk_in)) { Err(Error::InvalidParam) => true, _ => false });
+    }
+
+    #[test]
+    fn it_rejects_wrong_transfer_type() {
+        let interrupt_in = endpoint_descriptor!(bEndpointAddress: 0x81, bmAttributes: 0x03);
+        let iso_in = endpoint_descriptor!(bEndpointAddress: 0x81, bmAttributes: 0x01);
+
+        assert!(match check::<In, Bulk>(&endpoint_descriptor::from_libusb(&interrupt_in)) { Err(Error::InvalidParam) => true, _ => false });
+        assert!(match check::<In, Interrupt>(&endpoint_descriptor::from_libusb(&iso_in)) { Err(Error::InvalidParam) => true, _ => false });
+    }
+
+    #[test]
+    fn it_is_send_and_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+
+        assert_send_sync::<Endpoint<'static, 'static, SyncIo, In, Bulk>>();
+    }
+}
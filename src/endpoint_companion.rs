@@ -0,0 +1,178 @@
+use libusb::*;
+
+use fields::TransferType;
+use extra_descriptors::ExtraDescriptors;
+use descriptor_parser::DT_SSP_ISOCHRONOUS_ENDPOINT_COMPANION;
+
+
+/// Describes the SuperSpeed capabilities of an endpoint.
+///
+/// Every endpoint of a device operating at SuperSpeed or faster is followed by a SuperSpeed
+/// endpoint companion descriptor. Isochronous endpoints that need more bandwidth than it can
+/// describe are additionally followed by a SuperSpeedPlus isochronous endpoint companion
+/// descriptor, which is folded into this struct.
+#[derive(Debug,PartialEq,Eq,Clone,Copy,Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SuperSpeedEndpointCompanion {
+    transfer_type: TransferType,
+    max_burst: u8,
+    attributes: u8,
+    bytes_per_interval: u16,
+    ssp_bytes_per_interval: Option<u32>,
+}
+
+impl SuperSpeedEndpointCompanion {
+    /// Returns the maximum number of packets that the endpoint can send or receive as part of a
+    /// burst, minus one (`bMaxBurst`).
+    pub fn max_burst(&self) -> u8 {
+        self.max_burst
+    }
+
+    /// Returns the number of bulk streams that the endpoint supports.
+    ///
+    /// Only bulk endpoints support streams. Zero is returned for all other endpoints.
+    pub fn max_streams(&self) -> u32 {
+        match self.transfer_type {
+            TransferType::Bulk => match self.attributes & 0x1F {
+                0 => 0,
+                n => 1 << n,
+            },
+            _ => 0,
+        }
+    }
+
+    /// Returns the maximum number of bursts within a service interval, minus one (`Mult`).
+    ///
+    /// Only isochronous endpoints have a `Mult` field. Zero is returned for all other endpoints.
+    pub fn mult(&self) -> u8 {
+        match self.transfer_type {
+            TransferType::Isochronous => self.attributes & 0x03,
+            _ => 0,
+        }
+    }
+
+    /// Returns the total number of bytes that a periodic endpoint transfers every service
+    /// interval.
+    ///
+    /// The value comes from the SuperSpeedPlus isochronous endpoint companion descriptor if there
+    /// is one, and from `wBytesPerInterval` otherwise. It's only meaningful for isochronous and
+    /// interrupt endpoints.
+    pub fn bytes_per_interval(&self) -> u32 {
+        match self.ssp_bytes_per_interval {
+            Some(n) => n,
+            None => self.bytes_per_interval as u32,
+        }
+    }
+
+    /// Returns `dwBytesPerInterval` of the SuperSpeedPlus isochronous endpoint companion
+    /// descriptor, if the endpoint has one.
+    pub fn ssp_bytes_per_interval(&self) -> Option<u32> {
+        self.ssp_bytes_per_interval
+    }
+
+    /// Returns the raw `bmAttributes` field.
+    pub fn attributes(&self) -> u8 {
+        self.attributes
+    }
+}
+
+
+/// Finds the SuperSpeed endpoint companion among an endpoint's extra descriptors.
+#[doc(hidden)]
+pub fn from_extra(transfer_type: TransferType, extra: &[u8]) -> Option<SuperSpeedEndpointCompanion> {
+    let mut descriptors = ExtraDescriptors::new(extra);
+
+    let companion = match descriptors.find(|d| d.descriptor_type() == LIBUSB_DT_SS_ENDPOINT_COMPANION && d.length() >= 6) {
+        Some(descriptor) => descriptor.as_bytes(),
+        None => return None,
+    };
+
+    let attributes = companion[3];
+
+    // The SuperSpeedPlus companion immediately follows when bit 7 of an isochronous endpoint's
+    // attributes is set
+    let ssp_bytes_per_interval = if transfer_type == TransferType::Isochronous && attributes & 0x80 != 0 {
+        descriptors.next()
+            .map(|d| d.as_bytes())
+            .filter(|d| d[1] == DT_SSP_ISOCHRONOUS_ENDPOINT_COMPANION && d.len() >= 8)
+            .map(|d| d[4] as u32 | (d[5] as u32) << 8 | (d[6] as u32) << 16 | (d[7] as u32) << 24)
+    } else {
+        None
+    };
+
+    Some(SuperSpeedEndpointCompanion {
+        transfer_type: transfer_type,
+        max_burst: companion[2],
+        attributes: attributes,
+        bytes_per_interval: companion[4] as u16 | (companion[5] as u16) << 8,
+        ssp_bytes_per_interval: ssp_bytes_per_interval,
+    })
+}
+
+
+#[cfg(test)]
+mod test {
+    use fields::TransferType;
+    use super::from_extra;
+
+    #[test]
+    fn it_has_no_companion_without_descriptor() {
+        assert_eq!(None, from_extra(TransferType::Bulk, &[]));
+        assert_eq!(None, from_extra(TransferType::Bulk, &[0x07, 0x25, 0x01, 0x00, 0x00, 0x00, 0x00]));
+    }
+
+    #[test]
+    fn it_ignores_truncated_companion() {
+        assert_eq!(None, from_extra(TransferType::Bulk, &[0x05, 0x30, 0x0F, 0x04, 0x00]));
+    }
+
+    #[test]
+    fn it_reads_bulk_companion() {
+        let companion = from_extra(TransferType::Bulk, &[0x06, 0x30, 0x0F, 0x04, 0x00, 0x00]).unwrap();
+
+        assert_eq!(15, companion.max_burst());
+        assert_eq!(16, companion.max_streams());
+        assert_eq!(0, companion.mult());
+    }
+
+    #[test]
+    fn it_reads_isochronous_companion() {
+        let companion = from_extra(TransferType::Isochronous, &[0x06, 0x30, 0x03, 0x02, 0x00, 0x30]).unwrap();
+
+        assert_eq!(3, companion.max_burst());
+        assert_eq!(2, companion.mult());
+        assert_eq!(0, companion.max_streams());
+        assert_eq!(12288, companion.bytes_per_interval());
+        assert_eq!(None, companion.ssp_bytes_per_interval());
+    }
+
+    #[test]
+    fn it_reads_ssp_isochronous_companion() {
+        let extra = [
+            0x06, 0x30, 0x0F, 0x80, 0x00, 0x00,
+            0x08, 0x31, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00,
+        ];
+        let companion = from_extra(TransferType::Isochronous, &extra).unwrap();
+
+        assert_eq!(Some(65536), companion.ssp_bytes_per_interval());
+        assert_eq!(65536, companion.bytes_per_interval());
+    }
+
+    #[test]
+    fn it_ignores_ssp_companion_when_not_announced() {
+        let extra = [
+            0x06, 0x30, 0x0F, 0x00, 0x00, 0x04,
+            0x08, 0x31, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00,
+        ];
+
+        assert_eq!(1024, from_extra(TransferType::Isochronous, &extra).unwrap().bytes_per_interval());
+    }
+
+    #[test]
+    fn it_ignores_streams_of_interrupt_endpoints() {
+        let companion = from_extra(TransferType::Interrupt, &[0x06, 0x30, 0x00, 0x04, 0x00, 0x04]).unwrap();
+
+        assert_eq!(0, companion.max_streams());
+        assert_eq!(1024, companion.bytes_per_interval());
+    }
+}
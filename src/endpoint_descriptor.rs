@@ -1,8 +1,11 @@
 use std::fmt;
+use std::time::Duration;
 
 use libusb::*;
 
-use fields::{Direction, TransferType, SyncType, UsageType};
+use fields::{Direction, Speed, TransferType, SyncType, UsageType};
+use extra_descriptors::{self, ExtraDescriptors};
+use endpoint_companion::{self, SuperSpeedEndpointCompanion};
 
 /// Describes an endpoint.
 pub struct EndpointDescriptor<'a> {
@@ -71,6 +74,61 @@ impl<'a> EndpointDescriptor<'a> {
     pub fn interval(&self) -> u8 {
         self.descriptor.bInterval
     }
+
+    /// Returns the interval at which the host services the endpoint when the device operates at
+    /// `speed`.
+    ///
+    /// `bInterval` is a number of frames for full- and low-speed interrupt endpoints. For
+    /// full-speed isochronous endpoints it's the exponent of a power-of-two number of frames, and
+    /// for periodic endpoints of high-speed and faster devices the exponent of a power-of-two
+    /// number of microframes. `None` is returned for bulk and control endpoints, for an unknown
+    /// speed and for an out-of-range `bInterval`.
+    pub fn service_interval(&self, speed: Speed) -> Option<Duration> {
+        service_interval(self.transfer_type(), self.interval(), speed)
+    }
+
+    /// Returns the endpoint's SuperSpeed endpoint companion descriptor.
+    ///
+    /// Endpoints of devices operating at SuperSpeed or faster have a companion descriptor, which
+    /// `libusb` leaves among the endpoint's [`extra`](#method.extra) descriptors. `None` is
+    /// returned for endpoints without one.
+    pub fn ss_companion(&self) -> Option<SuperSpeedEndpointCompanion> {
+        endpoint_companion::from_extra(self.transfer_type(), self.extra())
+    }
+
+    /// Returns the maximum number of bytes that the endpoint can transfer in a service interval.
+    ///
+    /// For periodic endpoints with a SuperSpeed companion descriptor this is the companion's
+    /// [`bytes_per_interval`](struct.SuperSpeedEndpointCompanion.html#method.bytes_per_interval).
+    /// Other endpoints with a companion descriptor transfer a burst of up to `bMaxBurst + 1`
+    /// packets. Without a companion descriptor, the packet size is multiplied by the number of
+    /// transactions per microframe that high-speed, high-bandwidth endpoints encode in bits 11
+    /// and 12 of `wMaxPacketSize`.
+    pub fn max_bytes_per_interval(&self) -> u32 {
+        let packet_size = (self.descriptor.wMaxPacketSize & 0x07FF) as u32;
+
+        match (self.ss_companion(), self.transfer_type()) {
+            (Some(companion), TransferType::Isochronous) |
+            (Some(companion), TransferType::Interrupt) => companion.bytes_per_interval(),
+            (Some(companion), _) => packet_size * (companion.max_burst() as u32 + 1),
+            (None, _) => packet_size * (((self.descriptor.wMaxPacketSize >> 11) & 0x03) as u32 + 1),
+        }
+    }
+
+    /// Returns the extra descriptors that follow the endpoint descriptor.
+    ///
+    /// These are class- or vendor-specific descriptors, as well as standard descriptors that
+    /// `libusb` doesn't parse, such as the SuperSpeed endpoint companion descriptor.
+    pub fn extra(&self) -> &'a [u8] {
+        unsafe {
+            extra_descriptors::from_libusb(self.descriptor.extra, self.descriptor.extra_length)
+        }
+    }
+
+    /// Returns an iterator over the extra descriptors that follow the endpoint descriptor.
+    pub fn extra_descriptors(&self) -> ExtraDescriptors<'a> {
+        ExtraDescriptors::new(self.extra())
+    }
 }
 
 impl<'a> fmt::Debug for EndpointDescriptor<'a> {
@@ -93,10 +151,36 @@ pub fn from_libusb(endpoint: &libusb_endpoint_descriptor) -> EndpointDescriptor
     EndpointDescriptor { descriptor: endpoint }
 }
 
+/// Returns the number of bulk streams supported by the endpoint.
+///
+/// Endpoints without a SuperSpeed endpoint companion descriptor support no streams.
+#[doc(hidden)]
+pub fn max_streams(endpoint: &EndpointDescriptor) -> u32 {
+    endpoint.ss_companion().map_or(0, |companion| companion.max_streams())
+}
+
+/// Decodes `bInterval` into a service interval for an endpoint of a device operating at `speed`.
+#[doc(hidden)]
+pub fn service_interval(transfer_type: TransferType, interval: u8, speed: Speed) -> Option<Duration> {
+    match (transfer_type, speed) {
+        (TransferType::Control, _) | (TransferType::Bulk, _) | (_, Speed::Unknown) => None,
+        _ if interval == 0 => None,
+        (TransferType::Interrupt, Speed::Low) |
+        (TransferType::Interrupt, Speed::Full) => Some(Duration::from_millis(interval as u64)),
+        _ if interval > 16 => None,
+        (_, Speed::Low) | (_, Speed::Full) => Some(Duration::from_millis(1 << (interval - 1))),
+        _ => {
+            let nanos = 125_000u64 << (interval - 1);
+            Some(Duration::new(nanos / 1_000_000_000, (nanos % 1_000_000_000) as u32))
+        },
+    }
+}
+
 
 #[cfg(test)]
 mod test {
-    use ::fields::{Direction,TransferType,SyncType,UsageType};
+    use std::time::Duration;
+    use ::fields::{Direction,Speed,TransferType,SyncType,UsageType};
 
     #[test]
     fn it_interprets_number_for_output_endpoints() {
@@ -156,10 +240,118 @@ mod test {
         assert_eq!(65535, super::from_libusb(&endpoint_descriptor!(wMaxPacketSize: 65535)).max_packet_size());
     }
 
+    #[test]
+    fn it_has_no_streams_without_companion_descriptor() {
+        assert_eq!(0, super::max_streams(&super::from_libusb(&endpoint_descriptor!(bmAttributes: 0b0000_0010))));
+    }
+
+    #[test]
+    fn it_reads_max_streams_from_companion_descriptor() {
+        let extra: [u8; 6] = [6, 0x30, 0, 0x04, 0, 0];
+        let endpoint = endpoint_descriptor!(bmAttributes: 0b0000_0010, extra: extra.as_ptr(), extra_length: 6);
+
+        assert_eq!(16, super::max_streams(&super::from_libusb(&endpoint)));
+    }
+
+    #[test]
+    fn it_ignores_truncated_companion_descriptor() {
+        let extra: [u8; 4] = [6, 0x30, 0, 0x04];
+        let endpoint = endpoint_descriptor!(bmAttributes: 0b0000_0010, extra: extra.as_ptr(), extra_length: 4);
+
+        assert_eq!(0, super::max_streams(&super::from_libusb(&endpoint)));
+    }
+
+    #[test]
+    fn it_has_ss_companion() {
+        let extra: [u8; 6] = [6, 0x30, 0x03, 0x01, 0x00, 0x18];
+        let endpoint = endpoint_descriptor!(bmAttributes: 0b0000_0001, wMaxPacketSize: 1024, extra: extra.as_ptr(), extra_length: 6);
+        let companion = super::from_libusb(&endpoint).ss_companion().unwrap();
+
+        assert_eq!(3, companion.max_burst());
+        assert_eq!(1, companion.mult());
+        assert_eq!(6144, companion.bytes_per_interval());
+    }
+
+    #[test]
+    fn it_has_no_ss_companion_without_descriptor() {
+        assert_eq!(None, super::from_libusb(&endpoint_descriptor!(bmAttributes: 0b0000_0010)).ss_companion());
+    }
+
+    #[test]
+    fn it_takes_periodic_bytes_per_interval_from_ss_companion() {
+        let extra: [u8; 6] = [6, 0x30, 0x03, 0x01, 0x00, 0x18];
+        let endpoint = endpoint_descriptor!(bmAttributes: 0b0000_0001, wMaxPacketSize: 1024, extra: extra.as_ptr(), extra_length: 6);
+
+        assert_eq!(6144, super::from_libusb(&endpoint).max_bytes_per_interval());
+    }
+
+    #[test]
+    fn it_multiplies_bulk_packet_size_by_burst() {
+        let extra: [u8; 6] = [6, 0x30, 0x0F, 0x00, 0x00, 0x00];
+        let endpoint = endpoint_descriptor!(bmAttributes: 0b0000_0010, wMaxPacketSize: 1024, extra: extra.as_ptr(), extra_length: 6);
+
+        assert_eq!(16384, super::from_libusb(&endpoint).max_bytes_per_interval());
+    }
+
+    #[test]
+    fn it_includes_high_bandwidth_transactions_in_bytes_per_interval() {
+        assert_eq!(3072, super::from_libusb(&endpoint_descriptor!(bmAttributes: 0b0000_0001, wMaxPacketSize: 0x1400)).max_bytes_per_interval());
+        assert_eq!(64,   super::from_libusb(&endpoint_descriptor!(bmAttributes: 0b0000_0011, wMaxPacketSize: 64)).max_bytes_per_interval());
+    }
+
+    #[test]
+    fn it_has_no_extra_descriptors_by_default() {
+        let endpoint = endpoint_descriptor!(bmAttributes: 0b0000_0011);
+
+        assert!(super::from_libusb(&endpoint).extra().is_empty());
+        assert_eq!(0, super::from_libusb(&endpoint).extra_descriptors().count());
+    }
+
+    #[test]
+    fn it_has_extra_descriptors() {
+        let extra: [u8; 13] = [6, 0x30, 0, 0x04, 0, 0, 7, 0x25, 0x01, 0x00, 0x00, 0x00, 0x00];
+        let endpoint = endpoint_descriptor!(bmAttributes: 0b0000_0001, extra: extra.as_ptr(), extra_length: 13);
+        let descriptor = super::from_libusb(&endpoint);
+
+        assert_eq!(&extra[..], descriptor.extra());
+        assert_eq!(vec![0x30, 0x25], descriptor.extra_descriptors().map(|d| d.descriptor_type()).collect::<Vec<_>>());
+    }
+
     #[test]
     fn it_has_interval() {
         assert_eq!(1,   super::from_libusb(&endpoint_descriptor!(bInterval: 1)).interval());
         assert_eq!(20,  super::from_libusb(&endpoint_descriptor!(bInterval: 20)).interval());
         assert_eq!(255, super::from_libusb(&endpoint_descriptor!(bInterval: 255)).interval());
     }
+
+    #[test]
+    fn it_decodes_interrupt_service_interval_in_frames_at_full_and_low_speed() {
+        let endpoint = endpoint_descriptor!(bmAttributes: 0b0000_0011, bInterval: 10);
+
+        assert_eq!(Some(Duration::from_millis(10)), super::from_libusb(&endpoint).service_interval(Speed::Full));
+        assert_eq!(Some(Duration::from_millis(10)), super::from_libusb(&endpoint).service_interval(Speed::Low));
+        assert_eq!(Some(Duration::from_millis(255)), super::from_libusb(&endpoint_descriptor!(bmAttributes: 0b0000_0011, bInterval: 255)).service_interval(Speed::Full));
+    }
+
+    #[test]
+    fn it_decodes_isochronous_service_interval_as_power_of_two_frames_at_full_speed() {
+        assert_eq!(Some(Duration::from_millis(1)), super::from_libusb(&endpoint_descriptor!(bmAttributes: 0b0000_0001, bInterval: 1)).service_interval(Speed::Full));
+        assert_eq!(Some(Duration::from_millis(8)), super::from_libusb(&endpoint_descriptor!(bmAttributes: 0b0000_0001, bInterval: 4)).service_interval(Speed::Full));
+    }
+
+    #[test]
+    fn it_decodes_periodic_service_interval_as_power_of_two_microframes_at_high_speed() {
+        assert_eq!(Some(Duration::new(0, 125_000)), super::from_libusb(&endpoint_descriptor!(bmAttributes: 0b0000_0011, bInterval: 1)).service_interval(Speed::High));
+        assert_eq!(Some(Duration::from_millis(1)), super::from_libusb(&endpoint_descriptor!(bmAttributes: 0b0000_0001, bInterval: 4)).service_interval(Speed::Super));
+        assert_eq!(Some(Duration::new(4, 96_000_000)), super::from_libusb(&endpoint_descriptor!(bmAttributes: 0b0000_0011, bInterval: 16)).service_interval(Speed::High));
+    }
+
+    #[test]
+    fn it_has_no_service_interval_for_aperiodic_endpoints_or_invalid_intervals() {
+        assert_eq!(None, super::from_libusb(&endpoint_descriptor!(bmAttributes: 0b0000_0010, bInterval: 1)).service_interval(Speed::High));
+        assert_eq!(None, super::from_libusb(&endpoint_descriptor!(bmAttributes: 0b0000_0000, bInterval: 1)).service_interval(Speed::Full));
+        assert_eq!(None, super::from_libusb(&endpoint_descriptor!(bmAttributes: 0b0000_0011, bInterval: 0)).service_interval(Speed::Full));
+        assert_eq!(None, super::from_libusb(&endpoint_descriptor!(bmAttributes: 0b0000_0011, bInterval: 17)).service_interval(Speed::High));
+        assert_eq!(None, super::from_libusb(&endpoint_descriptor!(bmAttributes: 0b0000_0011, bInterval: 1)).service_interval(Speed::Unknown));
+    }
 }
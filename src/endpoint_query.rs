@@ -0,0 +1,292 @@
+use io::IoType;
+use device::Device;
+use config_descriptor::ConfigDescriptor;
+use interface_descriptor::InterfaceDescriptor;
+use endpoint_descriptor::EndpointDescriptor;
+use fields::{Direction, TransferType};
+
+
+/// Where to find an endpoint: the configuration, interface and alternate setting to select before
+/// using the endpoint's address.
+#[derive(Debug,PartialEq,Eq,Clone,Copy,Hash)]
+pub struct EndpointLocation {
+    config: u8,
+    interface: u8,
+    setting: u8,
+    address: u8,
+    transfer_type: TransferType,
+    max_packet_size: u16,
+}
+
+impl EndpointLocation {
+    /// Returns the configuration number to pass to
+    /// [`set_active_configuration`](struct.DeviceHandle.html#method.set_active_configuration).
+    pub fn config(&self) -> u8 {
+        self.config
+    }
+
+    /// Returns the number of the interface to claim.
+    pub fn interface(&self) -> u8 {
+        self.interface
+    }
+
+    /// Returns the alternate setting to activate.
+    pub fn setting(&self) -> u8 {
+        self.setting
+    }
+
+    /// Returns the endpoint's address.
+    pub fn address(&self) -> u8 {
+        self.address
+    }
+
+    /// Returns the endpoint's transfer type.
+    pub fn transfer_type(&self) -> TransferType {
+        self.transfer_type
+    }
+
+    /// Returns the endpoint's maximum packet size.
+    pub fn max_packet_size(&self) -> u16 {
+        self.max_packet_size
+    }
+}
+
+
+/// A query for a device's endpoints.
+///
+/// A query is created with [`Device::find_endpoints`](struct.Device.html#method.find_endpoints).
+/// Criteria that aren't set match every endpoint. Endpoints are returned in descriptor order:
+/// by configuration, interface, alternate setting and endpoint.
+///
+/// ## Examples
+///
+/// ```no_run
+/// # use libusb::{Direction, TransferType};
+/// # use libusb::io::sync::Context;
+/// # fn main() { run().unwrap(); }
+/// # fn run() -> libusb::Result<()> {
+/// let context = Context::new()?;
+///
+/// for device in context.devices()?.iter() {
+///     let found = device.find_endpoints()
+///         .transfer_type(TransferType::Bulk)
+///         .direction(Direction::In)
+///         .interface_class(0xFF)
+///         .first()?;
+///
+///     if let Some(endpoint) = found {
+///         let mut handle = device.open()?;
+///         handle.set_active_configuration(endpoint.config())?;
+///
+///         let iface = handle.claim_detaching_kernel_driver(endpoint.interface())?;
+///         iface.set_alternate_setting(endpoint.setting())?;
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct EndpointQuery<'d, 'ctx: 'd, Io>
+    where Io: IoType<'ctx> + 'd,
+{
+    device: &'d Device<'ctx, Io>,
+    filter: EndpointFilter,
+}
+
+impl<'d, 'ctx, Io> EndpointQuery<'d, 'ctx, Io>
+    where Io: IoType<'ctx>,
+{
+    /// Matches endpoints with the given transfer type.
+    pub fn transfer_type(mut self, transfer_type: TransferType) -> Self {
+        self.filter.transfer_type = Some(transfer_type);
+        self
+    }
+
+    /// Matches endpoints with the given direction.
+    pub fn direction(mut self, direction: Direction) -> Self {
+        self.filter.direction = Some(direction);
+        self
+    }
+
+    /// Matches endpoints of alternate settings with the given interface class code.
+    pub fn interface_class(mut self, class_code: u8) -> Self {
+        self.filter.interface_class = Some(class_code);
+        self
+    }
+
+    /// Matches endpoints of alternate settings with the given interface sub class code.
+    pub fn interface_sub_class(mut self, sub_class_code: u8) -> Self {
+        self.filter.interface_sub_class = Some(sub_class_code);
+        self
+    }
+
+    /// Matches endpoints of alternate settings with the given interface protocol code.
+    pub fn interface_protocol(mut self, protocol_code: u8) -> Self {
+        self.filter.interface_protocol = Some(protocol_code);
+        self
+    }
+
+    /// Returns the matching endpoints of every configuration.
+    ///
+    /// ## Errors
+    ///
+    /// Returns an error if the device or configuration descriptors can't be read.
+    pub fn all(&self) -> ::Result<Vec<EndpointLocation>> {
+        let mut locations = Vec::new();
+
+        for index in 0..self.device.device_descriptor()?.num_configurations() {
+            let config = self.device.config_descriptor(index)?;
+            locations.extend(self.filter.search(&config));
+        }
+
+        Ok(locations)
+    }
+
+    /// Returns the first matching endpoint.
+    ///
+    /// ## Errors
+    ///
+    /// Returns an error if the device or configuration descriptors can't be read.
+    pub fn first(&self) -> ::Result<Option<EndpointLocation>> {
+        for index in 0..self.device.device_descriptor()?.num_configurations() {
+            let config = self.device.config_descriptor(index)?;
+
+            if let Some(location) = self.filter.search(&config).into_iter().next() {
+                return Ok(Some(location));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Returns the matching endpoints of a single configuration, such as the active one.
+    pub fn in_config(&self, config: &ConfigDescriptor) -> Vec<EndpointLocation> {
+        self.filter.search(config)
+    }
+}
+
+
+#[derive(Debug,Default,Clone,Copy)]
+struct EndpointFilter {
+    transfer_type: Option<TransferType>,
+    direction: Option<Direction>,
+    interface_class: Option<u8>,
+    interface_sub_class: Option<u8>,
+    interface_protocol: Option<u8>,
+}
+
+impl EndpointFilter {
+    fn matches(&self, setting: &InterfaceDescriptor, endpoint: &EndpointDescriptor) -> bool {
+        self.transfer_type.map_or(true, |t| t == endpoint.transfer_type())
+            && self.direction.map_or(true, |d| d == endpoint.direction())
+            && self.interface_class.map_or(true, |c| c == setting.class_code())
+            && self.interface_sub_class.map_or(true, |c| c == setting.sub_class_code())
+            && self.interface_protocol.map_or(true, |c| c == setting.protocol_code())
+    }
+
+    fn search(&self, config: &ConfigDescriptor) -> Vec<EndpointLocation> {
+        let mut locations = Vec::new();
+
+        for interface in config.interfaces() {
+            for setting in interface.descriptors() {
+                for endpoint in setting.endpoint_descriptors() {
+                    if self.matches(&setting, &endpoint) {
+                        locations.push(EndpointLocation {
+                            config: config.number(),
+                            interface: setting.interface_number(),
+                            setting: setting.setting_number(),
+                            address: endpoint.address(),
+                            transfer_type: endpoint.transfer_type(),
+                            max_packet_size: endpoint.max_packet_size(),
+                        });
+                    }
+                }
+            }
+        }
+
+        locations
+    }
+}
+
+#[doc(hidden)]
+pub fn from_device<'d, 'ctx, Io>(device: &'d Device<'ctx, Io>) -> EndpointQuery<'d, 'ctx, Io>
+    where Io: IoType<'ctx>,
+{
+    EndpointQuery {
+        device: device,
+        filter: EndpointFilter::default(),
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use descriptor_builder::{ConfigDescriptorBuilder, InterfaceDescriptorBuilder, EndpointDescriptorBuilder};
+    use config_descriptor::ConfigDescriptor;
+    use fields::{Direction, TransferType};
+    use test_helpers::build_config;
+    use super::{EndpointFilter, EndpointLocation};
+
+    fn config() -> ConfigDescriptor {
+        build_config(ConfigDescriptorBuilder::new(2)
+            .interface(InterfaceDescriptorBuilder::new(0)
+                .class_code(0x03)
+                .endpoint(EndpointDescriptorBuilder::new(0x81, TransferType::Interrupt).max_packet_size(8).interval(10)))
+            .interface(InterfaceDescriptorBuilder::new(1)
+                .class_code(0xFF)
+                .endpoint(EndpointDescriptorBuilder::new(0x02, TransferType::Bulk).max_packet_size(512))
+                .endpoint(EndpointDescriptorBuilder::new(0x82, TransferType::Bulk).max_packet_size(512)))
+            .interface(InterfaceDescriptorBuilder::new(1).setting_number(1)
+                .class_code(0xFF)
+                .protocol_code(0x01)
+                .endpoint(EndpointDescriptorBuilder::new(0x83, TransferType::Bulk).max_packet_size(512))))
+    }
+
+    fn addresses(filter: EndpointFilter) -> Vec<u8> {
+        filter.search(&config()).iter().map(|location| location.address()).collect()
+    }
+
+    #[test]
+    fn it_matches_every_endpoint_without_criteria() {
+        assert_eq!(vec![0x81, 0x02, 0x82, 0x83], addresses(EndpointFilter::default()));
+    }
+
+    #[test]
+    fn it_returns_location_of_matching_endpoint() {
+        let filter = EndpointFilter {
+            transfer_type: Some(TransferType::Bulk),
+            direction: Some(Direction::In),
+            ..EndpointFilter::default()
+        };
+
+        let expected = EndpointLocation {
+            config: 2,
+            interface: 1,
+            setting: 0,
+            address: 0x82,
+            transfer_type: TransferType::Bulk,
+            max_packet_size: 512,
+        };
+
+        assert_eq!(Some(&expected), filter.search(&config()).first());
+    }
+
+    #[test]
+    fn it_matches_interface_codes() {
+        assert_eq!(vec![0x81], addresses(EndpointFilter { interface_class: Some(0x03), ..EndpointFilter::default() }));
+        assert_eq!(vec![0x02, 0x82, 0x83], addresses(EndpointFilter { interface_class: Some(0xFF), ..EndpointFilter::default() }));
+        assert_eq!(vec![0x83], addresses(EndpointFilter { interface_protocol: Some(0x01), ..EndpointFilter::default() }));
+        assert_eq!(Vec::<u8>::new(), addresses(EndpointFilter { interface_sub_class: Some(0x01), ..EndpointFilter::default() }));
+    }
+
+    #[test]
+    fn it_combines_criteria() {
+        let filter = EndpointFilter {
+            transfer_type: Some(TransferType::Bulk),
+            direction: Some(Direction::Out),
+            interface_class: Some(0xFF),
+            ..EndpointFilter::default()
+        };
+
+        assert_eq!(vec![0x02], addresses(filter));
+    }
+}
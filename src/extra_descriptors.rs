@@ -0,0 +1,175 @@
+use std::slice;
+
+use libc::{c_int, c_uchar};
+
+
+/// A class- or vendor-specific descriptor.
+///
+/// `libusb` keeps descriptors that it doesn't parse itself, such as HID, CDC, audio and video
+/// class descriptors, as "extra" bytes of the configuration, interface or endpoint descriptor
+/// that they follow.
+#[derive(Debug,PartialEq,Eq,Clone,Copy,Hash)]
+pub struct ExtraDescriptor<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> ExtraDescriptor<'a> {
+    /// Returns the descriptor's length (`bLength`).
+    pub fn length(&self) -> u8 {
+        self.bytes[0]
+    }
+
+    /// Returns the descriptor's type (`bDescriptorType`).
+    pub fn descriptor_type(&self) -> u8 {
+        self.bytes[1]
+    }
+
+    /// Returns the descriptor's body, the bytes after `bLength` and `bDescriptorType`.
+    pub fn body(&self) -> &'a [u8] {
+        &self.bytes[2..]
+    }
+
+    /// Returns all of the descriptor's bytes, including `bLength` and `bDescriptorType`.
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.bytes
+    }
+}
+
+/// Iterator over the descriptors in a buffer of extra descriptors.
+///
+/// Iteration stops at the first malformed descriptor, one whose `bLength` is less than 2 or runs
+/// past the end of the buffer. [`remainder`](#method.remainder) returns the bytes that were not
+/// iterated over.
+#[derive(Debug,Clone)]
+pub struct ExtraDescriptors<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> ExtraDescriptors<'a> {
+    /// Creates an iterator over the descriptors in `bytes`.
+    pub fn new(bytes: &'a [u8]) -> Self {
+        ExtraDescriptors { bytes: bytes }
+    }
+
+    /// Returns the bytes that haven't been iterated over.
+    ///
+    /// After iteration has finished, this is empty unless the buffer ended with a malformed
+    /// descriptor.
+    pub fn remainder(&self) -> &'a [u8] {
+        self.bytes
+    }
+}
+
+impl<'a> Iterator for ExtraDescriptors<'a> {
+    type Item = ExtraDescriptor<'a>;
+
+    fn next(&mut self) -> Option<ExtraDescriptor<'a>> {
+        if self.bytes.len() < 2 {
+            return None;
+        }
+
+        let length = self.bytes[0] as usize;
+
+        if length < 2 || length > self.bytes.len() {
+            return None;
+        }
+
+        let (descriptor, rest) = self.bytes.split_at(length);
+        self.bytes = rest;
+
+        Some(ExtraDescriptor { bytes: descriptor })
+    }
+}
+
+
+/// Returns the extra bytes of a `libusb` descriptor struct.
+#[doc(hidden)]
+pub unsafe fn from_libusb<'a>(extra: *const c_uchar, extra_length: c_int) -> &'a [u8] {
+    if extra.is_null() || extra_length <= 0 {
+        &[]
+    } else {
+        slice::from_raw_parts(extra, extra_length as usize)
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use std::ptr;
+    use super::{ExtraDescriptors, from_libusb};
+
+    #[test]
+    fn it_iterates_descriptors() {
+        let bytes = [0x05, 0x24, 0x00, 0x10, 0x01, 0x04, 0x24, 0x02, 0x02];
+        let descriptors: Vec<_> = ExtraDescriptors::new(&bytes).collect();
+
+        assert_eq!(2, descriptors.len());
+        assert_eq!(5, descriptors[0].length());
+        assert_eq!(0x24, descriptors[0].descriptor_type());
+        assert_eq!(&[0x00, 0x10, 0x01], descriptors[0].body());
+        assert_eq!(&bytes[5..], descriptors[1].as_bytes());
+    }
+
+    #[test]
+    fn it_handles_empty_buffer() {
+        assert_eq!(0, ExtraDescriptors::new(&[]).count());
+    }
+
+    #[test]
+    fn it_accepts_descriptor_without_body() {
+        let descriptors: Vec<_> = ExtraDescriptors::new(&[0x02, 0x30]).collect();
+
+        assert_eq!(1, descriptors.len());
+        assert!(descriptors[0].body().is_empty());
+    }
+
+    #[test]
+    fn it_stops_at_zero_length() {
+        let bytes = [0x03, 0x24, 0x01, 0x00, 0x24, 0x03, 0x24, 0x01];
+        let mut descriptors = ExtraDescriptors::new(&bytes);
+
+        assert!(descriptors.next().is_some());
+        assert!(descriptors.next().is_none());
+        assert_eq!(&bytes[3..], descriptors.remainder());
+    }
+
+    #[test]
+    fn it_stops_at_overrun() {
+        let bytes = [0x03, 0x24, 0x01, 0x09, 0x21, 0x11];
+        let mut descriptors = ExtraDescriptors::new(&bytes);
+
+        assert!(descriptors.next().is_some());
+        assert!(descriptors.next().is_none());
+        assert_eq!(&bytes[3..], descriptors.remainder());
+    }
+
+    #[test]
+    fn it_stops_at_trailing_byte() {
+        let mut descriptors = ExtraDescriptors::new(&[0x02, 0x24, 0x05]);
+
+        assert!(descriptors.next().is_some());
+        assert!(descriptors.next().is_none());
+        assert_eq!(&[0x05], descriptors.remainder());
+    }
+
+    #[test]
+    fn it_has_empty_remainder_after_complete_iteration() {
+        let mut descriptors = ExtraDescriptors::new(&[0x02, 0x24, 0x03, 0x25, 0x01]);
+
+        assert_eq!(2, descriptors.by_ref().count());
+        assert!(descriptors.remainder().is_empty());
+    }
+
+    #[test]
+    fn it_handles_null_extra() {
+        assert!(unsafe { from_libusb(ptr::null(), 0) }.is_empty());
+        assert!(unsafe { from_libusb(ptr::null(), 5) }.is_empty());
+    }
+
+    #[test]
+    fn it_handles_negative_extra_length() {
+        let bytes = [0x02, 0x24];
+
+        assert!(unsafe { from_libusb(bytes.as_ptr(), -1) }.is_empty());
+    }
+}
@@ -3,6 +3,7 @@ use libusb::*;
 
 /// Device speeds. Indicates the speed at which a device is operating.
 #[derive(Debug,PartialEq,Eq,Clone,Copy,Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Speed {
     /// The operating system doesn't know the device speed.
     Unknown,
@@ -34,6 +35,7 @@ pub fn speed_from_libusb(n: c_int) -> Speed {
 
 /// Transfer and endpoint directions.
 #[derive(Debug,PartialEq,Eq,Clone,Copy,Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Direction {
     /// Direction for read (device to host) transfers.
     In,
@@ -44,6 +46,7 @@ pub enum Direction {
 
 /// An endpoint's transfer type.
 #[derive(Debug,PartialEq,Eq,Clone,Copy,Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum TransferType {
     /// Control endpoint.
     Control,
@@ -61,6 +64,7 @@ pub enum TransferType {
 
 /// Isochronous synchronization mode.
 #[derive(Debug,PartialEq,Eq,Clone,Copy,Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum SyncType {
     /// No synchronisation.
     NoSync,
@@ -78,6 +82,7 @@ pub enum SyncType {
 
 /// Isochronous usage type.
 #[derive(Debug,PartialEq,Eq,Clone,Copy,Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum UsageType {
     /// Data endpoint.
     Data,
@@ -95,6 +100,7 @@ pub enum UsageType {
 
 /// Types of control transfers.
 #[derive(Debug,PartialEq,Eq,Clone,Copy,Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum RequestType {
     /// Requests that are defined by the USB standard.
     Standard,
@@ -111,6 +117,7 @@ pub enum RequestType {
 
 /// Recipients of control transfers.
 #[derive(Debug,PartialEq,Eq,Clone,Copy,Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Recipient {
     /// The recipient is a device.
     Device,
@@ -140,6 +147,7 @@ pub enum Recipient {
 /// The intended use case of `Version` is to extract meaning from the version fields in USB
 /// descriptors, such as `bcdUSB` and `bcdDevice` in device descriptors.
 #[derive(Debug,PartialEq,Eq,Clone,Copy,Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Version(pub u8, pub u8, pub u8);
 
 impl Version {
@@ -162,6 +170,16 @@ impl Version {
         Version(major, minor, sub_minor)
     }
 
+    /// Encodes the version as a binary coded decimal (BCD) field, the inverse of
+    /// [`from_bcd`](#method.from_bcd).
+    ///
+    /// The minor and sub minor versions are truncated to one digit, and the major version to two.
+    pub fn to_bcd(&self) -> u16 {
+        let Version(major, minor, sub_minor) = *self;
+
+        ((major / 10 % 10) as u16) << 12 | ((major % 10) as u16) << 8 | ((minor % 10) as u16) << 4 | (sub_minor % 10) as u16
+    }
+
     /// Returns the major version.
     pub fn major(&self) -> u8 {
         let Version(major, _, _) = *self;
@@ -219,6 +237,56 @@ pub fn request_type(direction: Direction, request_type: RequestType, recipient:
     value
 }
 
+/// Builds the `bmAttributes` field of an endpoint descriptor.
+#[doc(hidden)]
+pub fn endpoint_attributes(transfer_type: TransferType, sync_type: SyncType, usage_type: UsageType) -> u8 {
+    let mut value: u8 = match transfer_type {
+        TransferType::Control     => LIBUSB_TRANSFER_TYPE_CONTROL,
+        TransferType::Isochronous => LIBUSB_TRANSFER_TYPE_ISOCHRONOUS,
+        TransferType::Bulk        => LIBUSB_TRANSFER_TYPE_BULK,
+        TransferType::Interrupt   => LIBUSB_TRANSFER_TYPE_INTERRUPT,
+    };
+
+    value |= match sync_type {
+        SyncType::NoSync       => LIBUSB_ISO_SYNC_TYPE_NONE,
+        SyncType::Asynchronous => LIBUSB_ISO_SYNC_TYPE_ASYNC,
+        SyncType::Adaptive     => LIBUSB_ISO_SYNC_TYPE_ADAPTIVE,
+        SyncType::Synchronous  => LIBUSB_ISO_SYNC_TYPE_SYNC,
+    } << 2;
+
+    value |= match usage_type {
+        UsageType::Data         => LIBUSB_ISO_USAGE_TYPE_DATA,
+        UsageType::Feedback     => LIBUSB_ISO_USAGE_TYPE_FEEDBACK,
+        UsageType::FeedbackData => LIBUSB_ISO_USAGE_TYPE_IMPLICIT,
+        UsageType::Reserved     => 0x03,
+    } << 4;
+
+    value
+}
+
+/// Returns the name of a transfer type as it is used in sentences.
+#[doc(hidden)]
+pub fn transfer_type_name(transfer_type: TransferType) -> &'static str {
+    match transfer_type {
+        TransferType::Control     => "control",
+        TransferType::Isochronous => "isochronous",
+        TransferType::Bulk        => "bulk",
+        TransferType::Interrupt   => "interrupt",
+    }
+}
+
+/// Returns the name of a speed as it is used in sentences.
+#[doc(hidden)]
+pub fn speed_name(speed: Speed) -> &'static str {
+    match speed {
+        Speed::Unknown => "unknown speed",
+        Speed::Low     => "low speed",
+        Speed::Full    => "full speed",
+        Speed::High    => "high speed",
+        Speed::Super   => "super speed",
+    }
+}
+
 
 #[cfg(test)]
 mod test {
@@ -271,6 +339,33 @@ mod test {
         assert_eq!(Version(12, 3, 4), Version::from_bcd(0x1234));
     }
 
+    #[test]
+    fn version_encodes_bcd() {
+        assert_eq!(0x0200, Version(2, 0, 0).to_bcd());
+        assert_eq!(0x0110, Version(1, 1, 0).to_bcd());
+        assert_eq!(0x1234, Version(12, 3, 4).to_bcd());
+    }
+
+    #[test]
+    fn version_round_trips_through_bcd() {
+        assert_eq!(Version(2, 0, 1), Version::from_bcd(Version(2, 0, 1).to_bcd()));
+    }
+
+    // endpoint_attributes
+
+    #[test]
+    fn endpoint_attributes_builds_transfer_type() {
+        assert_eq!(0x02, endpoint_attributes(TransferType::Bulk, SyncType::NoSync, UsageType::Data));
+        assert_eq!(0x03, endpoint_attributes(TransferType::Interrupt, SyncType::NoSync, UsageType::Data));
+    }
+
+    #[test]
+    fn endpoint_attributes_builds_isochronous_attributes() {
+        assert_eq!(0x05, endpoint_attributes(TransferType::Isochronous, SyncType::Asynchronous, UsageType::Data));
+        assert_eq!(0x11, endpoint_attributes(TransferType::Isochronous, SyncType::NoSync, UsageType::Feedback));
+        assert_eq!(0x3D, endpoint_attributes(TransferType::Isochronous, SyncType::Synchronous, UsageType::Reserved));
+    }
+
     // request_type for direction
 
     #[test]
@@ -326,4 +421,27 @@ mod test {
     fn request_type_builds_value_for_other_recipient() {
         assert_eq!(request_type(Direction::Out, RequestType::Standard, Recipient::Other) & 0x0F, 0x03);
     }
+
+    // serde
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn fields_round_trip_through_serde() {
+        use serde_json;
+
+        assert_eq!(Speed::Super, serde_json::from_str(&serde_json::to_string(&Speed::Super).unwrap()).unwrap());
+        assert_eq!(Direction::In, serde_json::from_str(&serde_json::to_string(&Direction::In).unwrap()).unwrap());
+        assert_eq!(TransferType::Isochronous, serde_json::from_str(&serde_json::to_string(&TransferType::Isochronous).unwrap()).unwrap());
+        assert_eq!(SyncType::Adaptive, serde_json::from_str(&serde_json::to_string(&SyncType::Adaptive).unwrap()).unwrap());
+        assert_eq!(UsageType::Feedback, serde_json::from_str(&serde_json::to_string(&UsageType::Feedback).unwrap()).unwrap());
+        assert_eq!(Version(2, 1, 0), serde_json::from_str(&serde_json::to_string(&Version(2, 1, 0)).unwrap()).unwrap());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn version_serializes_as_sequence() {
+        use serde_json;
+
+        assert_eq!("[3,1,0]", serde_json::to_string(&Version(3, 1, 0)).unwrap());
+    }
 }
@@ -0,0 +1,380 @@
+use std::vec;
+
+use config_descriptor::ConfigDescriptor;
+use interface_descriptor::{Interface, InterfaceDescriptor};
+use extra_descriptors::ExtraDescriptors;
+use descriptor_parser::{DT_INTERFACE_ASSOCIATION, DT_CS_INTERFACE};
+use class_code::ClassCode;
+
+
+/// Describes an interface association.
+///
+/// Composite devices use interface association descriptors (IADs) to mark the interfaces that
+/// belong to one function, such as the control and data interfaces of a CDC ACM serial port.
+#[derive(Debug,PartialEq,Eq,Clone,Copy,Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct InterfaceAssociation {
+    first_interface: u8,
+    interface_count: u8,
+    class_code: u8,
+    sub_class_code: u8,
+    protocol_code: u8,
+    string_index: u8,
+}
+
+impl InterfaceAssociation {
+    /// Returns the number of the first interface of the function.
+    pub fn first_interface(&self) -> u8 {
+        self.first_interface
+    }
+
+    /// Returns the number of contiguous interfaces that belong to the function.
+    pub fn interface_count(&self) -> u8 {
+        self.interface_count
+    }
+
+    /// Indicates if the interface with the given number belongs to the function.
+    pub fn contains(&self, interface_number: u8) -> bool {
+        interface_number >= self.first_interface
+            && (interface_number as u16) < self.first_interface as u16 + self.interface_count as u16
+    }
+
+    /// Returns the function's class code.
+    pub fn class_code(&self) -> u8 {
+        self.class_code
+    }
+
+    /// Returns the function's sub class code.
+    pub fn sub_class_code(&self) -> u8 {
+        self.sub_class_code
+    }
+
+    /// Returns the function's protocol code.
+    pub fn protocol_code(&self) -> u8 {
+        self.protocol_code
+    }
+
+    /// Returns the index of the string descriptor that describes the function.
+    pub fn description_string_index(&self) -> Option<u8> {
+        match self.string_index {
+            0 => None,
+            n => Some(n),
+        }
+    }
+}
+
+/// A function of a device, one or more interfaces that together implement one feature.
+pub struct Function<'a> {
+    association: Option<InterfaceAssociation>,
+    interfaces: Vec<Interface<'a>>,
+}
+
+impl<'a> Function<'a> {
+    /// Returns the interface association descriptor that defines the function.
+    ///
+    /// `None` is returned for functions that were grouped without an IAD.
+    pub fn association(&self) -> Option<InterfaceAssociation> {
+        self.association
+    }
+
+    /// Returns the function's interfaces, ordered by interface number.
+    pub fn interfaces(&self) -> &[Interface<'a>] {
+        &self.interfaces
+    }
+
+    /// Returns the number of the function's first interface.
+    pub fn first_interface(&self) -> u8 {
+        match self.association {
+            Some(association) => association.first_interface(),
+            None => self.interfaces[0].number(),
+        }
+    }
+
+    /// Returns the function's class code.
+    ///
+    /// This is the class code of the interface association if there is one, and the class code of
+    /// the first interface otherwise.
+    pub fn class_code(&self) -> u8 {
+        match self.association {
+            Some(association) => association.class_code(),
+            None => self.first_setting().map_or(0, |setting| setting.class_code()),
+        }
+    }
+
+    /// Returns the function's sub class code.
+    pub fn sub_class_code(&self) -> u8 {
+        match self.association {
+            Some(association) => association.sub_class_code(),
+            None => self.first_setting().map_or(0, |setting| setting.sub_class_code()),
+        }
+    }
+
+    /// Returns the function's protocol code.
+    pub fn protocol_code(&self) -> u8 {
+        match self.association {
+            Some(association) => association.protocol_code(),
+            None => self.first_setting().map_or(0, |setting| setting.protocol_code()),
+        }
+    }
+
+    fn first_setting(&self) -> Option<InterfaceDescriptor<'a>> {
+        self.interfaces.first().and_then(|interface| interface.descriptors().next())
+    }
+}
+
+/// Iterator over a configuration's functions.
+pub struct Functions<'a> {
+    iter: vec::IntoIter<Function<'a>>,
+}
+
+impl<'a> Iterator for Functions<'a> {
+    type Item = Function<'a>;
+
+    fn next(&mut self) -> Option<Function<'a>> {
+        self.iter.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+
+fn associations_in(extra: &[u8], associations: &mut Vec<InterfaceAssociation>) {
+    for descriptor in ExtraDescriptors::new(extra) {
+        if descriptor.descriptor_type() == DT_INTERFACE_ASSOCIATION && descriptor.length() >= 8 {
+            let bytes = descriptor.as_bytes();
+
+            associations.push(InterfaceAssociation {
+                first_interface: bytes[2],
+                interface_count: bytes[3],
+                class_code: bytes[4],
+                sub_class_code: bytes[5],
+                protocol_code: bytes[6],
+                string_index: bytes[7],
+            });
+        }
+    }
+}
+
+/// Returns the configuration's interface association descriptors.
+///
+/// `libusb` keeps an IAD with the descriptor that precedes it, so IADs after the first are found
+/// among the extra descriptors of interfaces and endpoints.
+#[doc(hidden)]
+pub fn associations(config: &ConfigDescriptor) -> Vec<InterfaceAssociation> {
+    let mut associations = Vec::new();
+
+    associations_in(config.extra(), &mut associations);
+
+    for interface in config.interfaces() {
+        for setting in interface.descriptors() {
+            associations_in(setting.extra(), &mut associations);
+
+            for endpoint in setting.endpoint_descriptors() {
+                associations_in(endpoint.extra(), &mut associations);
+            }
+        }
+    }
+
+    associations.sort_by_key(|association| association.first_interface);
+    associations.dedup();
+    associations
+}
+
+/// Returns the interfaces listed in the CDC union functional descriptor of a control interface.
+fn cdc_union(setting: &InterfaceDescriptor) -> Vec<u8> {
+    ExtraDescriptors::new(setting.extra())
+        .find(|d| d.descriptor_type() == DT_CS_INTERFACE && d.length() >= 5 && d.body()[0] == 0x06)
+        .map_or(Vec::new(), |d| d.body()[2..].to_vec())
+}
+
+/// Indicates if `follower` is an interface that belongs to the function started by `leader` when
+/// the device has no IADs.
+fn follows(leader: &InterfaceDescriptor, follower: &InterfaceDescriptor) -> bool {
+    let follower_class = follower.class().class_code();
+
+    match (leader.class().class_code(), leader.sub_class_code()) {
+        (ClassCode::Communications, _) => follower_class == ClassCode::CdcData,
+        (ClassCode::Audio, 0x01) => follower_class == ClassCode::Audio && follower.sub_class_code() != 0x01,
+        (ClassCode::Video, 0x01) => follower_class == ClassCode::Video && follower.sub_class_code() == 0x02,
+        _ => false,
+    }
+}
+
+/// Groups a configuration's interfaces into functions.
+#[doc(hidden)]
+pub fn functions(config: &ConfigDescriptor) -> Functions {
+    let associations = associations(config);
+    let mut interfaces: Vec<Option<Interface>> = config.interfaces().map(Some).collect();
+    let mut functions = Vec::new();
+
+    for association in associations {
+        let members: Vec<Interface> = interfaces.iter_mut()
+            .filter(|slot| slot.as_ref().map_or(false, |interface| association.contains(interface.number())))
+            .filter_map(|slot| slot.take())
+            .collect();
+
+        if !members.is_empty() {
+            functions.push(Function { association: Some(association), interfaces: members });
+        }
+    }
+
+    for i in 0..interfaces.len() {
+        let leader = match interfaces[i].take() {
+            Some(interface) => interface,
+            None => continue,
+        };
+
+        let mut members = vec![];
+
+        if let Some(setting) = leader.descriptors().next() {
+            let union = match setting.class().class_code() {
+                ClassCode::Communications => cdc_union(&setting),
+                _ => Vec::new(),
+            };
+
+            if !union.is_empty() {
+                for slot in interfaces.iter_mut() {
+                    if slot.as_ref().map_or(false, |interface| union.contains(&interface.number())) {
+                        members.extend(slot.take());
+                    }
+                }
+            } else {
+                for slot in interfaces[i + 1..].iter_mut() {
+                    let is_follower = match *slot {
+                        Some(ref interface) => interface.descriptors().next().map_or(false, |follower| follows(&setting, &follower)),
+                        None => continue,
+                    };
+
+                    if !is_follower {
+                        break;
+                    }
+
+                    members.extend(slot.take());
+                }
+            }
+        }
+
+        members.insert(0, leader);
+        members.sort_by_key(|interface| interface.number());
+        functions.push(Function { association: None, interfaces: members });
+    }
+
+    functions.sort_by_key(|function| function.first_interface());
+
+    Functions { iter: functions.into_iter() }
+}
+
+
+#[cfg(test)]
+mod test {
+    use descriptor_builder::{ConfigDescriptorBuilder, InterfaceDescriptorBuilder, EndpointDescriptorBuilder, InterfaceAssociationBuilder};
+    use descriptor_parser::DT_CS_INTERFACE;
+    use config_descriptor::ConfigDescriptor;
+    use fields::TransferType;
+    use test_helpers::build_config;
+
+    fn groups(config: &ConfigDescriptor) -> Vec<(Vec<u8>, u8, bool)> {
+        config.functions().map(|function| {
+            (function.interfaces().iter().map(|interface| interface.number()).collect(), function.class_code(), function.association().is_some())
+        }).collect()
+    }
+
+    fn interface(number: u8, class_code: u8, sub_class_code: u8) -> InterfaceDescriptorBuilder {
+        InterfaceDescriptorBuilder::new(number).class_code(class_code).sub_class_code(sub_class_code)
+    }
+
+    // A CDC ACM serial port and a mass storage interface. The second IAD follows the last
+    // endpoint of the CDC function, so libusb keeps it with that endpoint. `mass_storage_count`
+    // is the number of interfaces in the second IAD.
+    fn cdc_and_mass_storage(mass_storage_count: u8) -> ConfigDescriptor {
+        build_config(ConfigDescriptorBuilder::new(1)
+            .association(InterfaceAssociationBuilder::new(0, 2).class_code(0x02).sub_class_code(0x02).protocol_code(0x01).description_string_index(4))
+            .interface(interface(0, 0x02, 0x02).protocol_code(0x01)
+                .descriptor(DT_CS_INTERFACE, &[0x00, 0x10, 0x01])
+                .descriptor(DT_CS_INTERFACE, &[0x06, 0x00, 0x01])
+                .endpoint(EndpointDescriptorBuilder::new(0x83, TransferType::Interrupt).max_packet_size(8).interval(16)))
+            .interface(interface(1, 0x0A, 0x00)
+                .endpoint(EndpointDescriptorBuilder::new(0x81, TransferType::Bulk).max_packet_size(64))
+                .endpoint(EndpointDescriptorBuilder::new(0x02, TransferType::Bulk).max_packet_size(64)))
+            .association(InterfaceAssociationBuilder::new(2, mass_storage_count).class_code(0x08).sub_class_code(0x06).protocol_code(0x50))
+            .interface(interface(2, 0x08, 0x06).protocol_code(0x50)
+                .endpoint(EndpointDescriptorBuilder::new(0x84, TransferType::Bulk).max_packet_size(64))
+                .endpoint(EndpointDescriptorBuilder::new(0x05, TransferType::Bulk).max_packet_size(64))))
+    }
+
+    // Two CDC ACM ports without IADs. The first port's data interface is found through its union
+    // descriptor, the second port's through its class.
+    fn dual_cdc() -> ConfigDescriptor {
+        build_config(ConfigDescriptorBuilder::new(1)
+            .interface(interface(0, 0x02, 0x02).protocol_code(0x01).descriptor(DT_CS_INTERFACE, &[0x06, 0x00, 0x03]))
+            .interface(interface(1, 0x02, 0x02).protocol_code(0x01))
+            .interface(interface(2, 0x0A, 0x00))
+            .interface(interface(3, 0x0A, 0x00)))
+    }
+
+    // A webcam without IADs: video control and streaming, then audio control and streaming.
+    fn webcam() -> ConfigDescriptor {
+        build_config(ConfigDescriptorBuilder::new(1)
+            .interface(interface(0, 0x0E, 0x01))
+            .interface(interface(1, 0x0E, 0x02))
+            .interface(interface(2, 0x01, 0x01))
+            .interface(interface(3, 0x01, 0x02)))
+    }
+
+    #[test]
+    fn it_finds_associations_after_endpoints() {
+        let config = cdc_and_mass_storage(1);
+        let associations: Vec<(u8, u8, u8)> = config.interface_associations().iter().map(|a| {
+            (a.first_interface(), a.interface_count(), a.class_code())
+        }).collect();
+
+        assert_eq!(vec![(0, 2, 0x02), (2, 1, 0x08)], associations);
+        assert_eq!(Some(4), config.interface_associations()[0].description_string_index());
+    }
+
+    #[test]
+    fn it_groups_interfaces_by_association() {
+        let config = cdc_and_mass_storage(1);
+
+        assert_eq!(vec![(vec![0, 1], 0x02, true), (vec![2], 0x08, true)], groups(&config));
+    }
+
+    #[test]
+    fn it_groups_cdc_interfaces_without_associations() {
+        let config = dual_cdc();
+
+        assert_eq!(vec![(vec![0, 3], 0x02, false), (vec![1, 2], 0x02, false)], groups(&config));
+    }
+
+    #[test]
+    fn it_groups_video_and_audio_interfaces_by_class() {
+        let config = webcam();
+
+        assert_eq!(vec![(vec![0, 1], 0x0E, false), (vec![2, 3], 0x01, false)], groups(&config));
+    }
+
+    #[test]
+    fn it_keeps_unrelated_interfaces_apart() {
+        let config = build_config(ConfigDescriptorBuilder::new(1)
+            .interface(interface(0, 0xFF, 0x00))
+            .interface(interface(1, 0x03, 0x00)));
+
+        assert_eq!(vec![(vec![0], 0xFF, false), (vec![1], 0x03, false)], groups(&config));
+    }
+
+    #[test]
+    fn it_leaves_interfaces_outside_associations_on_their_own() {
+        let config = cdc_and_mass_storage(0);
+
+        assert_eq!(vec![(vec![0, 1], 0x02, true), (vec![2], 0x08, false)], groups(&config));
+    }
+
+    #[test]
+    fn it_has_no_functions_without_interfaces() {
+        let config = build_config(ConfigDescriptorBuilder::new(1));
+
+        assert_eq!(0, config.functions().count());
+    }
+}
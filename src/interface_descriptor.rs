@@ -4,6 +4,9 @@ use std::slice;
 use libusb::*;
 
 use endpoint_descriptor::{self, EndpointDescriptor};
+use fields::{Direction, TransferType, UsageType};
+use extra_descriptors::{self, ExtraDescriptors};
+use class_code::Class;
 
 /// A device interface.
 ///
@@ -25,6 +28,35 @@ impl<'a> Interface<'a> {
             iter: self.descriptors.iter(),
         }
     }
+
+    /// Returns the alternate setting with the least periodic bandwidth that can transfer at least
+    /// `bytes_per_interval` bytes per service interval in `direction`.
+    ///
+    /// A setting's bandwidth is the largest
+    /// [`max_bytes_per_interval`](struct.EndpointDescriptor.html#method.max_bytes_per_interval) of
+    /// its isochronous and interrupt endpoints in `direction`, not counting isochronous feedback
+    /// endpoints. Of settings with the same bandwidth, the lowest-numbered one is returned. `None`
+    /// is returned if no setting provides enough bandwidth.
+    ///
+    /// This picks a streaming setting of audio and video class devices, which offer alternate
+    /// settings with increasing isochronous packet sizes.
+    pub fn setting_for_bandwidth(&self, direction: Direction, bytes_per_interval: u32) -> Option<InterfaceDescriptor<'a>> {
+        let mut best: Option<(u32, InterfaceDescriptor<'a>)> = None;
+
+        for setting in self.descriptors() {
+            let bandwidth = periodic_bytes_per_interval(&setting, direction);
+
+            if bandwidth < bytes_per_interval {
+                continue;
+            }
+
+            if best.as_ref().map_or(true, |&(b, _)| bandwidth < b) {
+                best = Some((bandwidth, setting));
+            }
+        }
+
+        best.map(|(_, setting)| setting)
+    }
 }
 
 /// Iterator over an interface's descriptors.
@@ -78,6 +110,11 @@ impl<'a> InterfaceDescriptor<'a> {
         self.descriptor.bInterfaceProtocol
     }
 
+    /// Returns the interface's class, sub class and protocol codes as a [`Class`](struct.Class.html).
+    pub fn class(&self) -> Class {
+        Class::new(self.class_code(), self.sub_class_code(), self.protocol_code())
+    }
+
     /// Returns the index of the string descriptor that describes the interface.
     pub fn description_string_index(&self) -> Option<u8> {
         match self.descriptor.iInterface {
@@ -93,15 +130,36 @@ impl<'a> InterfaceDescriptor<'a> {
 
     /// Returns an iterator over the interface's endpoint descriptors.
     pub fn endpoint_descriptors(&self) -> EndpointDescriptors {
-        let endpoints = unsafe {
-            slice::from_raw_parts(
-                self.descriptor.endpoint,
-                self.descriptor.bNumEndpoints as usize
-            )
+        // libusb leaves the pointer null for interfaces without endpoints
+        let endpoints = if self.descriptor.endpoint.is_null() {
+            &[]
+        } else {
+            unsafe {
+                slice::from_raw_parts(
+                    self.descriptor.endpoint,
+                    self.descriptor.bNumEndpoints as usize
+                )
+            }
         };
 
         EndpointDescriptors { iter: endpoints.iter() }
     }
+
+    /// Returns the extra descriptors that follow the interface descriptor.
+    ///
+    /// These are class- or vendor-specific descriptors, such as HID, CDC functional, audio or
+    /// video class descriptors. Descriptors that follow one of the interface's endpoints belong to
+    /// that endpoint instead.
+    pub fn extra(&self) -> &'a [u8] {
+        unsafe {
+            extra_descriptors::from_libusb(self.descriptor.extra, self.descriptor.extra_length)
+        }
+    }
+
+    /// Returns an iterator over the extra descriptors that follow the interface descriptor.
+    pub fn extra_descriptors(&self) -> ExtraDescriptors<'a> {
+        ExtraDescriptors::new(self.extra())
+    }
 }
 
 impl<'a> fmt::Debug for InterfaceDescriptor<'a> {
@@ -142,6 +200,21 @@ impl<'a> Iterator for EndpointDescriptors<'a> {
 }
 
 
+/// Returns the largest number of bytes per service interval of a setting's periodic data endpoints
+/// in `direction`.
+fn periodic_bytes_per_interval(setting: &InterfaceDescriptor, direction: Direction) -> u32 {
+    setting.endpoint_descriptors()
+        .filter(|endpoint| endpoint.direction() == direction)
+        .filter(|endpoint| match endpoint.transfer_type() {
+            TransferType::Isochronous => endpoint.usage_type() != UsageType::Feedback,
+            TransferType::Interrupt => true,
+            _ => false,
+        })
+        .map(|endpoint| endpoint.max_bytes_per_interval())
+        .max()
+        .unwrap_or(0)
+}
+
 #[doc(hidden)]
 pub unsafe fn from_libusb(interface: &libusb_interface) -> Interface {
     let descriptors = slice::from_raw_parts(interface.altsetting, interface.num_altsetting as usize);
@@ -153,6 +226,8 @@ pub unsafe fn from_libusb(interface: &libusb_interface) -> Interface {
 
 #[cfg(test)]
 mod test {
+    use fields::Direction;
+
     #[test]
     fn it_has_interface_number() {
         assert_eq!(42, unsafe { super::from_libusb(&interface!(interface_descriptor!(bInterfaceNumber: 42))) }.number());
@@ -212,4 +287,73 @@ mod test {
 
         assert_eq!(vec![0x87], endpoint_addresses);
     }
+
+    #[test]
+    fn it_handles_missing_endpoints() {
+        let libusb_interface = interface!(interface_descriptor!(bNumEndpoints: 0));
+        let interface = unsafe { super::from_libusb(&libusb_interface) };
+
+        assert_eq!(0, interface.descriptors().next().unwrap().endpoint_descriptors().count());
+    }
+
+    #[test]
+    fn it_has_extra_descriptors() {
+        let extra: [u8; 9] = [0x09, 0x21, 0x11, 0x01, 0x00, 0x01, 0x22, 0x3F, 0x00];
+        let libusb_interface = interface!(interface_descriptor!(extra: extra.as_ptr(), extra_length: 9));
+        let interface = unsafe { super::from_libusb(&libusb_interface) };
+        let setting = interface.descriptors().next().unwrap();
+
+        assert_eq!(&extra[..], setting.extra());
+        assert_eq!(vec![0x21], setting.extra_descriptors().map(|d| d.descriptor_type()).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn it_handles_missing_extra_descriptors() {
+        let libusb_interface = interface!(interface_descriptor!(bInterfaceNumber: 1));
+        let interface = unsafe { super::from_libusb(&libusb_interface) };
+
+        assert!(interface.descriptors().next().unwrap().extra().is_empty());
+    }
+
+    fn streaming_setting(number: u8, max_packet_size: u16) -> ::libusb::libusb_interface_descriptor {
+        merge!(
+            interface_descriptor!(
+                endpoint_descriptor!(bEndpointAddress: 0x81, bmAttributes: 0x01, wMaxPacketSize: max_packet_size, bInterval: 1),
+                endpoint_descriptor!(bEndpointAddress: 0x02, bmAttributes: 0x11, wMaxPacketSize: 1024, bInterval: 1)
+            ) => bInterfaceNumber: 1, bAlternateSetting: number
+        )
+    }
+
+    fn streaming_interface() -> ::libusb::libusb_interface {
+        interface!(
+            interface_descriptor!(bInterfaceNumber: 1),
+            streaming_setting(1, 0x0400),
+            streaming_setting(2, 0x0200),
+            streaming_setting(3, 0x1400),
+            streaming_setting(4, 0x0200)
+        )
+    }
+
+    #[test]
+    fn it_selects_smallest_setting_with_enough_bandwidth() {
+        let libusb_interface = streaming_interface();
+        let interface = unsafe { super::from_libusb(&libusb_interface) };
+        let select = |bytes| interface.setting_for_bandwidth(Direction::In, bytes).map(|setting| setting.setting_number());
+
+        assert_eq!(Some(0), select(0));
+        assert_eq!(Some(2), select(1));
+        assert_eq!(Some(2), select(512));
+        assert_eq!(Some(1), select(513));
+        assert_eq!(Some(3), select(1025));
+        assert_eq!(Some(3), select(3072));
+        assert_eq!(None, select(3073));
+    }
+
+    #[test]
+    fn it_ignores_feedback_endpoints_when_selecting_by_bandwidth() {
+        let libusb_interface = streaming_interface();
+        let interface = unsafe { super::from_libusb(&libusb_interface) };
+
+        assert_eq!(None, interface.setting_for_bandwidth(Direction::Out, 1).map(|setting| setting.setting_number()));
+    }
 }
@@ -81,17 +81,22 @@ pub mod sync {
     pub type Devices<'ctx, 'dl> = ::device_list::Devices<'ctx, 'dl, SyncIo>;
     pub type Device<'ctx>       = ::device::Device<'ctx, SyncIo>;
     pub type DeviceHandle<'ctx> = ::device_handle::DeviceHandle<'ctx, SyncIo>;
+    pub type ClaimedInterface<'dh, 'ctx> = ::claimed_interface::ClaimedInterface<'dh, 'ctx, SyncIo>;
+    pub type Endpoint<'i, 'ctx, Dir, Kind> = ::endpoint::Endpoint<'i, 'ctx, SyncIo, Dir, Kind>;
 
     use super::IoType;
     use libusb::libusb_context;
 
     #[derive(Debug)]
-    pub struct SyncIo;
+    pub struct SyncIo {
+        context: *mut libusb_context,
+    }
 
     impl<'ctx> IoType<'ctx> for SyncIo {
-        type Handle = ();
-        fn new(_ctx: *mut libusb_context) -> Self { SyncIo }
-        fn handle(&'ctx self) -> Self::Handle { }
+        // The context is needed to wait for transfers that libusb's synchronous api can't express
+        type Handle = *mut libusb_context;
+        fn new(ctx: *mut libusb_context) -> Self { SyncIo { context: ctx } }
+        fn handle(&'ctx self) -> Self::Handle { self.context }
     }
 }
 
@@ -102,6 +107,8 @@ pub mod unix_async {
     pub type Devices<'ctx, 'dl> = ::device_list::Devices<'ctx, 'dl, UnixAsyncIo>;
     pub type Device<'ctx>       = ::device::Device<'ctx, UnixAsyncIo>;
     pub type DeviceHandle<'ctx> = ::device_handle::DeviceHandle<'ctx, UnixAsyncIo>;
+    pub type ClaimedInterface<'dh, 'ctx> = ::claimed_interface::ClaimedInterface<'dh, 'ctx, UnixAsyncIo>;
+    pub type Endpoint<'i, 'ctx, Dir, Kind> = ::endpoint::Endpoint<'i, 'ctx, UnixAsyncIo, Dir, Kind>;
 
     use std::ptr;
     use std::sync::Mutex;
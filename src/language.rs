@@ -1,6 +1,157 @@
+use std::fmt;
+use std::str::FromStr;
+
 const PRIMARY_LANGUAGE_MASK: u16 = 0x03FF;
 const SUB_LANGUAGE_MASK:     u16 = 0xFC00;
 
+// BCP 47 tags of the languages listed by the USB forum. Where two LANGIDs share a locale, the
+// first one listed is the one that plain tag resolves to, and the other gets an extension.
+const TAGS: &'static [(u16, &'static str)] = &[
+    (0x0436, "af-ZA"),
+    (0x041C, "sq-AL"),
+    (0x0401, "ar-SA"),
+    (0x0801, "ar-IQ"),
+    (0x0C01, "ar-EG"),
+    (0x1001, "ar-LY"),
+    (0x1401, "ar-DZ"),
+    (0x1801, "ar-MA"),
+    (0x1C01, "ar-TN"),
+    (0x2001, "ar-OM"),
+    (0x2401, "ar-YE"),
+    (0x2801, "ar-SY"),
+    (0x2C01, "ar-JO"),
+    (0x3001, "ar-LB"),
+    (0x3401, "ar-KW"),
+    (0x3801, "ar-AE"),
+    (0x3C01, "ar-BH"),
+    (0x4001, "ar-QA"),
+    (0x042B, "hy-AM"),
+    (0x044D, "as-IN"),
+    (0x042C, "az-Latn-AZ"),
+    (0x082C, "az-Cyrl-AZ"),
+    (0x042D, "eu-ES"),
+    (0x0423, "be-BY"),
+    (0x0445, "bn-IN"),
+    (0x0402, "bg-BG"),
+    (0x0455, "my-MM"),
+    (0x0403, "ca-ES"),
+    (0x0404, "zh-TW"),
+    (0x0804, "zh-CN"),
+    (0x0C04, "zh-HK"),
+    (0x1004, "zh-SG"),
+    (0x1404, "zh-MO"),
+    (0x041A, "hr-HR"),
+    (0x0405, "cs-CZ"),
+    (0x0406, "da-DK"),
+    (0x0413, "nl-NL"),
+    (0x0813, "nl-BE"),
+    (0x0409, "en-US"),
+    (0x0809, "en-GB"),
+    (0x0C09, "en-AU"),
+    (0x1009, "en-CA"),
+    (0x1409, "en-NZ"),
+    (0x1809, "en-IE"),
+    (0x1C09, "en-ZA"),
+    (0x2009, "en-JM"),
+    (0x2409, "en-029"),
+    (0x2809, "en-BZ"),
+    (0x2C09, "en-TT"),
+    (0x3009, "en-ZW"),
+    (0x3409, "en-PH"),
+    (0x0425, "et-EE"),
+    (0x0438, "fo-FO"),
+    (0x0429, "fa-IR"),
+    (0x040B, "fi-FI"),
+    (0x040C, "fr-FR"),
+    (0x080C, "fr-BE"),
+    (0x0C0C, "fr-CA"),
+    (0x100C, "fr-CH"),
+    (0x140C, "fr-LU"),
+    (0x180C, "fr-MC"),
+    (0x0437, "ka-GE"),
+    (0x0407, "de-DE"),
+    (0x0807, "de-CH"),
+    (0x0C07, "de-AT"),
+    (0x1007, "de-LU"),
+    (0x1407, "de-LI"),
+    (0x0408, "el-GR"),
+    (0x0447, "gu-IN"),
+    (0x040D, "he-IL"),
+    (0x0439, "hi-IN"),
+    (0x040E, "hu-HU"),
+    (0x040F, "is-IS"),
+    (0x0421, "id-ID"),
+    (0x0410, "it-IT"),
+    (0x0810, "it-CH"),
+    (0x0411, "ja-JP"),
+    (0x044B, "kn-IN"),
+    (0x0860, "ks-IN"),
+    (0x043F, "kk-KZ"),
+    (0x0457, "kok-IN"),
+    (0x0412, "ko-KR"),
+    (0x0812, "ko-KR-x-johab"),
+    (0x0426, "lv-LV"),
+    (0x0427, "lt-LT"),
+    (0x0827, "lt-LT-x-classic"),
+    (0x042F, "mk-MK"),
+    (0x043E, "ms-MY"),
+    (0x083E, "ms-BN"),
+    (0x044C, "ml-IN"),
+    (0x0458, "mni-IN"),
+    (0x044E, "mr-IN"),
+    (0x0861, "ne-IN"),
+    (0x0414, "nb-NO"),
+    (0x0814, "nn-NO"),
+    (0x0448, "or-IN"),
+    (0x0415, "pl-PL"),
+    (0x0416, "pt-BR"),
+    (0x0816, "pt-PT"),
+    (0x0446, "pa-IN"),
+    (0x0418, "ro-RO"),
+    (0x0419, "ru-RU"),
+    (0x044F, "sa-IN"),
+    (0x0C1A, "sr-Cyrl-CS"),
+    (0x081A, "sr-Latn-CS"),
+    (0x0459, "sd-IN"),
+    (0x041B, "sk-SK"),
+    (0x0424, "sl-SI"),
+    (0x040A, "es-ES-u-co-trad"),
+    (0x080A, "es-MX"),
+    (0x0C0A, "es-ES"),
+    (0x100A, "es-GT"),
+    (0x140A, "es-CR"),
+    (0x180A, "es-PA"),
+    (0x1C0A, "es-DO"),
+    (0x200A, "es-VE"),
+    (0x240A, "es-CO"),
+    (0x280A, "es-PE"),
+    (0x2C0A, "es-AR"),
+    (0x300A, "es-EC"),
+    (0x340A, "es-CL"),
+    (0x380A, "es-UY"),
+    (0x3C0A, "es-PY"),
+    (0x400A, "es-BO"),
+    (0x440A, "es-SV"),
+    (0x480A, "es-HN"),
+    (0x4C0A, "es-NI"),
+    (0x500A, "es-PR"),
+    (0x0430, "st-ZA"),
+    (0x0441, "sw-KE"),
+    (0x041D, "sv-SE"),
+    (0x081D, "sv-FI"),
+    (0x0449, "ta-IN"),
+    (0x0444, "tt-RU"),
+    (0x044A, "te-IN"),
+    (0x041E, "th-TH"),
+    (0x041F, "tr-TR"),
+    (0x0422, "uk-UA"),
+    (0x0420, "ur-PK"),
+    (0x0820, "ur-IN"),
+    (0x0443, "uz-Latn-UZ"),
+    (0x0843, "uz-Cyrl-UZ"),
+    (0x042A, "vi-VN"),
+];
+
 
 /// A language used to read string descriptors from USB devices.
 ///
@@ -10,6 +161,8 @@ const SUB_LANGUAGE_MASK:     u16 = 0xFC00;
 /// Kindgdom English), writing systems (Cyrillic compared to Latin), or age (Modern compared to
 /// Traditional). Each primary language has its own set of sub languages.
 #[derive(Debug,Copy,Clone,PartialEq,Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
 pub struct Language {
     raw: u16,
 }
@@ -32,6 +185,47 @@ impl Language {
     pub fn sub_language(&self) -> SubLanguage {
         SubLanguage::from_raw(self.primary_language(), self.raw)
     }
+
+    /// Returns the language's BCP 47 tag, such as `en-US`.
+    ///
+    /// Returns `None` for languages that don't have a locale, such as the HID languages, and for
+    /// unknown `LANGID`s.
+    pub fn tag(&self) -> Option<&'static str> {
+        TAGS.iter().find(|&&(lang_id, _)| lang_id == self.raw).map(|&(_, tag)| tag)
+    }
+
+    /// Looks up a language by its BCP 47 tag.
+    ///
+    /// Tags are compared case-insensitively, and `_` is accepted as a separator, so `en-US`,
+    /// `en_us` and `EN-us` all return United States English. Returns `None` if the tag isn't the
+    /// tag of a known language.
+    pub fn from_tag(tag: &str) -> Option<Language> {
+        let tag = tag.replace('_', "-");
+
+        TAGS.iter()
+            .find(|&&(_, t)| t.eq_ignore_ascii_case(&tag))
+            .map(|&(lang_id, _)| from_lang_id(lang_id))
+    }
+}
+
+impl fmt::Display for Language {
+    /// Formats the language as its English name, such as "English (United States)".
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match (self.primary_language(), self.sub_language()) {
+            (PrimaryLanguage::Other(_), _) => write!(fmt, "Unknown language (0x{:04x})", self.raw),
+            (primary, SubLanguage::Standard) => write!(fmt, "{}", primary),
+            (primary, sub) => write!(fmt, "{} ({})", primary, sub),
+        }
+    }
+}
+
+impl FromStr for Language {
+    type Err = ::Error;
+
+    /// Parses a BCP 47 tag with [`from_tag`](#method.from_tag).
+    fn from_str(s: &str) -> ::Result<Language> {
+        Language::from_tag(s).ok_or_else(|| format!("unknown language tag {:?}", s).into())
+    }
 }
 
 #[doc(hidden)]
@@ -39,6 +233,59 @@ pub fn from_lang_id(raw: u16) -> Language {
     Language { raw: raw }
 }
 
+/// Chooses the language to read string descriptors in.
+///
+/// `preferred` is a list of BCP 47 tags in order of preference, such as `["de-CH", "en"]`, and
+/// `available` is usually the list returned by
+/// [`read_languages`](trait.DeviceHandleSyncApi.html#method.read_languages). Each preferred tag
+/// is tried in turn, and is matched by an available language with the same tag or, failing that,
+/// with a longer tag that starts with it, so `en` matches `en-US`. If neither is available, the
+/// tag is shortened one subtag at a time and tried again, so `de-CH` falls back to `de` and then
+/// to any German. This is the lookup scheme of RFC 4647 extended with the prefix match. If no
+/// preferred tag matches, the first available language is returned, as devices list their
+/// default language first.
+///
+/// Returns `None` only if `available` is empty.
+///
+/// ## Examples
+///
+/// ```
+/// use libusb::{choose_language, Language};
+///
+/// let available = ["en-US".parse::<Language>().unwrap(), "de-DE".parse().unwrap()];
+///
+/// assert_eq!(Some(available[1]), choose_language(&["de-CH", "en"], &available));
+/// assert_eq!(Some(available[0]), choose_language(&["fr-FR"], &available));
+/// ```
+pub fn choose_language(preferred: &[&str], available: &[Language]) -> Option<Language> {
+    for tag in preferred {
+        let mut range = tag.replace('_', "-");
+
+        while !range.is_empty() {
+            let exact = available.iter().find(|language| {
+                language.tag().map_or(false, |t| t.eq_ignore_ascii_case(&range))
+            });
+
+            let prefixed = || available.iter().find(|language| {
+                language.tag().map_or(false, |t| {
+                    t.len() > range.len()
+                        && t.as_bytes()[range.len()] == b'-'
+                        && t[..range.len()].eq_ignore_ascii_case(&range)
+                })
+            });
+
+            if let Some(&language) = exact.or_else(prefixed) {
+                return Some(language);
+            }
+
+            let len = range.rfind('-').unwrap_or(0);
+            range.truncate(len);
+        }
+    }
+
+    available.first().cloned()
+}
+
 
 /// Primary language families.
 #[derive(Debug,Copy,Clone,PartialEq,Eq)]
@@ -205,6 +452,90 @@ impl PrimaryLanguage {
     }
 }
 
+impl fmt::Display for PrimaryLanguage {
+    /// Formats the primary language as its English name, or as its raw value if it is unknown.
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        let name = match *self {
+            PrimaryLanguage::Afrikaans   => "Afrikaans",
+            PrimaryLanguage::Albanian    => "Albanian",
+            PrimaryLanguage::Arabic      => "Arabic",
+            PrimaryLanguage::Armenian    => "Armenian",
+            PrimaryLanguage::Assamese    => "Assamese",
+            PrimaryLanguage::Azeri       => "Azeri",
+            PrimaryLanguage::Basque      => "Basque",
+            PrimaryLanguage::Belarussian => "Belarussian",
+            PrimaryLanguage::Bengali     => "Bengali",
+            PrimaryLanguage::Bulgarian   => "Bulgarian",
+            PrimaryLanguage::Burmese     => "Burmese",
+            PrimaryLanguage::Catalan     => "Catalan",
+            PrimaryLanguage::Chinese     => "Chinese",
+            PrimaryLanguage::Croatian    => "Croatian",
+            PrimaryLanguage::Czech       => "Czech",
+            PrimaryLanguage::Danish      => "Danish",
+            PrimaryLanguage::Dutch       => "Dutch",
+            PrimaryLanguage::English     => "English",
+            PrimaryLanguage::Estonian    => "Estonian",
+            PrimaryLanguage::Faeroese    => "Faeroese",
+            PrimaryLanguage::Farsi       => "Farsi",
+            PrimaryLanguage::Finnish     => "Finnish",
+            PrimaryLanguage::French      => "French",
+            PrimaryLanguage::Georgian    => "Georgian",
+            PrimaryLanguage::German      => "German",
+            PrimaryLanguage::Greek       => "Greek",
+            PrimaryLanguage::Gujarati    => "Gujarati",
+            PrimaryLanguage::Hebrew      => "Hebrew",
+            PrimaryLanguage::Hindi       => "Hindi",
+            PrimaryLanguage::Hungarian   => "Hungarian",
+            PrimaryLanguage::Icelandic   => "Icelandic",
+            PrimaryLanguage::Indonesian  => "Indonesian",
+            PrimaryLanguage::Italian     => "Italian",
+            PrimaryLanguage::Japanese    => "Japanese",
+            PrimaryLanguage::Kannada     => "Kannada",
+            PrimaryLanguage::Kashmiri    => "Kashmiri",
+            PrimaryLanguage::Kazakh      => "Kazakh",
+            PrimaryLanguage::Konkani     => "Konkani",
+            PrimaryLanguage::Korean      => "Korean",
+            PrimaryLanguage::Latvian     => "Latvian",
+            PrimaryLanguage::Lithuanian  => "Lithuanian",
+            PrimaryLanguage::Macedonian  => "Macedonian",
+            PrimaryLanguage::Malay       => "Malay",
+            PrimaryLanguage::Malayalam   => "Malayalam",
+            PrimaryLanguage::Manipuri    => "Manipuri",
+            PrimaryLanguage::Marathi     => "Marathi",
+            PrimaryLanguage::Nepali      => "Nepali",
+            PrimaryLanguage::Norwegian   => "Norwegian",
+            PrimaryLanguage::Oriya       => "Oriya",
+            PrimaryLanguage::Polish      => "Polish",
+            PrimaryLanguage::Portuguese  => "Portuguese",
+            PrimaryLanguage::Punjabi     => "Punjabi",
+            PrimaryLanguage::Romanian    => "Romanian",
+            PrimaryLanguage::Russian     => "Russian",
+            PrimaryLanguage::Sanskrit    => "Sanskrit",
+            PrimaryLanguage::Serbian     => "Serbian",
+            PrimaryLanguage::Sindhi      => "Sindhi",
+            PrimaryLanguage::Slovak      => "Slovak",
+            PrimaryLanguage::Slovenian   => "Slovenian",
+            PrimaryLanguage::Spanish     => "Spanish",
+            PrimaryLanguage::Sutu        => "Sutu",
+            PrimaryLanguage::Swahili     => "Swahili",
+            PrimaryLanguage::Swedish     => "Swedish",
+            PrimaryLanguage::Tamil       => "Tamil",
+            PrimaryLanguage::Tatar       => "Tatar",
+            PrimaryLanguage::Telugu      => "Telugu",
+            PrimaryLanguage::Thai        => "Thai",
+            PrimaryLanguage::Turkish     => "Turkish",
+            PrimaryLanguage::Ukrainian   => "Ukrainian",
+            PrimaryLanguage::Urdu        => "Urdu",
+            PrimaryLanguage::Uzbek       => "Uzbek",
+            PrimaryLanguage::Vietnamese  => "Vietnamese",
+            PrimaryLanguage::HID         => "HID",
+            PrimaryLanguage::Other(n) => return write!(fmt, "0x{:04x}", n),
+        };
+
+        fmt.write_str(name)
+    }
+}
+
 
 /// Language dialects and writing systems.
 #[derive(Debug,Copy,Clone,PartialEq,Eq)]
@@ -454,10 +785,100 @@ impl SubLanguage {
     }
 }
 
+impl fmt::Display for SubLanguage {
+    /// Formats the sub language as its English name, or as its raw value if it is unknown.
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        let name = match *self {
+            SubLanguage::Standard            => "Standard",
+            SubLanguage::Classic             => "Classic",
+            SubLanguage::Traditional         => "Traditional",
+            SubLanguage::Modern              => "Modern",
+            SubLanguage::Algeria             => "Algeria",
+            SubLanguage::Argentina           => "Argentina",
+            SubLanguage::Australia           => "Australia",
+            SubLanguage::Austria             => "Austria",
+            SubLanguage::Bahrain             => "Bahrain",
+            SubLanguage::Belgium             => "Belgium",
+            SubLanguage::Belize              => "Belize",
+            SubLanguage::Bokmal              => "Bokmal",
+            SubLanguage::Bolivia             => "Bolivia",
+            SubLanguage::Brazil              => "Brazil",
+            SubLanguage::BruneiDarussalam    => "Brunei Darussalam",
+            SubLanguage::Canada              => "Canada",
+            SubLanguage::Caribbean           => "Caribbean",
+            SubLanguage::Chile               => "Chile",
+            SubLanguage::China               => "China",
+            SubLanguage::Colombia            => "Colombia",
+            SubLanguage::CostaRica           => "Costa Rica",
+            SubLanguage::Cyrillic            => "Cyrillic",
+            SubLanguage::DominicanRepublic   => "Dominican Republic",
+            SubLanguage::Ecuador             => "Ecuador",
+            SubLanguage::Egypt               => "Egypt",
+            SubLanguage::ElSalvador          => "El Salvador",
+            SubLanguage::Finland             => "Finland",
+            SubLanguage::Guatemala           => "Guatemala",
+            SubLanguage::Honduras            => "Honduras",
+            SubLanguage::HongKong            => "Hong Kong",
+            SubLanguage::India               => "India",
+            SubLanguage::Iraq                => "Iraq",
+            SubLanguage::Ireland             => "Ireland",
+            SubLanguage::Jamaica             => "Jamaica",
+            SubLanguage::Johab               => "Johab",
+            SubLanguage::Jordan              => "Jordan",
+            SubLanguage::Kuwait              => "Kuwait",
+            SubLanguage::Latin               => "Latin",
+            SubLanguage::Lebanon             => "Lebanon",
+            SubLanguage::Libya               => "Libya",
+            SubLanguage::Liechtenstein       => "Liechtenstein",
+            SubLanguage::Luxembourg          => "Luxembourg",
+            SubLanguage::Macau               => "Macau",
+            SubLanguage::Malaysia            => "Malaysia",
+            SubLanguage::Mexico              => "Mexico",
+            SubLanguage::Monaco              => "Monaco",
+            SubLanguage::Morocco             => "Morocco",
+            SubLanguage::Netherlands         => "Netherlands",
+            SubLanguage::NewZealand          => "New Zealand",
+            SubLanguage::Nicaragua           => "Nicaragua",
+            SubLanguage::Nynorsk             => "Nynorsk",
+            SubLanguage::Oman                => "Oman",
+            SubLanguage::Pakistan            => "Pakistan",
+            SubLanguage::Panama              => "Panama",
+            SubLanguage::Paraguay            => "Paraguay",
+            SubLanguage::Peru                => "Peru",
+            SubLanguage::Philippines         => "Philippines",
+            SubLanguage::PuertoRico          => "Puerto Rico",
+            SubLanguage::Qatar               => "Qatar",
+            SubLanguage::SaudiArabia         => "Saudi Arabia",
+            SubLanguage::Singapore           => "Singapore",
+            SubLanguage::SouthAfrica         => "South Africa",
+            SubLanguage::Switzerland         => "Switzerland",
+            SubLanguage::Syria               => "Syria",
+            SubLanguage::Taiwan              => "Taiwan",
+            SubLanguage::Trinidad            => "Trinidad",
+            SubLanguage::Tunisia             => "Tunisia",
+            SubLanguage::UnitedArabEmirates  => "United Arab Emirates",
+            SubLanguage::UnitedKingdom       => "United Kingdom",
+            SubLanguage::UnitedStates        => "United States",
+            SubLanguage::Uruguay             => "Uruguay",
+            SubLanguage::Venezuela           => "Venezuela",
+            SubLanguage::Yemen               => "Yemen",
+            SubLanguage::Zimbabwe            => "Zimbabwe",
+            SubLanguage::UsageDataDescriptor => "Usage Data Descriptor",
+            SubLanguage::VendorDefined1      => "Vendor Defined 1",
+            SubLanguage::VendorDefined2      => "Vendor Defined 2",
+            SubLanguage::VendorDefined3      => "Vendor Defined 3",
+            SubLanguage::VendorDefined4      => "Vendor Defined 4",
+            SubLanguage::Other(n) => return write!(fmt, "0x{:04x}", n),
+        };
+
+        fmt.write_str(name)
+    }
+}
+
 
 #[cfg(test)]
 mod test {
-    use super::{PrimaryLanguage, SubLanguage};
+    use super::{Language, PrimaryLanguage, SubLanguage, TAGS};
     use super::{PRIMARY_LANGUAGE_MASK, SUB_LANGUAGE_MASK};
 
     // language ids defined in http://www.usb.org/developers/docs/USB_LANGIDs.pdf
@@ -1830,4 +2251,119 @@ mod test {
     fn it_recognizes_other_as_other_sub_language() {
         assert_eq!(super::from_lang_id(0xFFFF).sub_language(), SubLanguage::Other(SUB_LANGUAGE_MASK));
     }
+
+    #[test]
+    fn it_formats_language_name() {
+        assert_eq!("English (United States)", super::from_lang_id(ENGLISH_UNITED_STATES).to_string());
+        assert_eq!("Spanish (Mexico)", super::from_lang_id(SPANISH_MEXICAN).to_string());
+        assert_eq!("Serbian (Cyrillic)", super::from_lang_id(SERBIAN_CYRILLIC).to_string());
+        assert_eq!("HID (Vendor Defined 1)", super::from_lang_id(HID_VENDOR_DEFINED_1).to_string());
+    }
+
+    #[test]
+    fn it_formats_standard_language_without_sub_language() {
+        assert_eq!("French", super::from_lang_id(FRENCH_STANDARD).to_string());
+        assert_eq!("Japanese", super::from_lang_id(JAPANESE).to_string());
+    }
+
+    #[test]
+    fn it_formats_unknown_languages_with_raw_values() {
+        assert_eq!("Unknown language (0xffff)", super::from_lang_id(0xFFFF).to_string());
+        assert_eq!("English (0x7c00)", super::from_lang_id(0x7C09).to_string());
+    }
+
+    #[test]
+    fn it_returns_tag_of_language() {
+        assert_eq!(Some("en-US"), super::from_lang_id(ENGLISH_UNITED_STATES).tag());
+        assert_eq!(Some("zh-TW"), super::from_lang_id(CHINESE_TAIWAN).tag());
+        assert_eq!(Some("sr-Latn-CS"), super::from_lang_id(SERBIAN_LATIN).tag());
+        assert_eq!(None, super::from_lang_id(HID_USAGE_DATA_DESCRIPTOR).tag());
+        assert_eq!(None, super::from_lang_id(0xFFFF).tag());
+    }
+
+    #[test]
+    fn it_looks_up_language_by_tag() {
+        assert_eq!(Some(ENGLISH_UNITED_STATES), Language::from_tag("en-US").map(|l| l.lang_id()));
+        assert_eq!(Some(ENGLISH_UNITED_KINGDOM), Language::from_tag("en_gb").map(|l| l.lang_id()));
+        assert_eq!(Some(SPANISH_MODERN_SORT), Language::from_tag("ES-es").map(|l| l.lang_id()));
+        assert_eq!(Some(SPANISH_TRADITIONAL_SORT), Language::from_tag("es-ES-u-co-trad").map(|l| l.lang_id()));
+        assert_eq!(Some(KOREAN), Language::from_tag("ko-KR").map(|l| l.lang_id()));
+        assert_eq!(None, Language::from_tag("en"));
+        assert_eq!(None, Language::from_tag("xx-XX"));
+    }
+
+    #[test]
+    fn it_round_trips_every_tag() {
+        for &(lang_id, tag) in TAGS {
+            let language = super::from_lang_id(lang_id);
+
+            assert_eq!(Some(tag), language.tag());
+            assert_eq!(Some(language), Language::from_tag(tag));
+            assert!(match language.primary_language() { PrimaryLanguage::Other(_) => false, _ => true });
+        }
+    }
+
+    #[test]
+    fn it_parses_language_from_str() {
+        assert_eq!(super::from_lang_id(GERMAN_AUSTRIA), "de-AT".parse::<Language>().unwrap());
+
+        match "klingon".parse::<Language>() {
+            Err(::Error::Custom(message)) => assert_eq!("unknown language tag \"klingon\"", message),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    fn languages(lang_ids: &[u16]) -> Vec<Language> {
+        lang_ids.iter().map(|&lang_id| super::from_lang_id(lang_id)).collect()
+    }
+
+    #[test]
+    fn it_chooses_exact_match() {
+        let available = languages(&[ENGLISH_UNITED_STATES, ENGLISH_UNITED_KINGDOM, GERMAN_STANDARD]);
+
+        assert_eq!(Some(available[1]), super::choose_language(&["en-GB"], &available));
+        assert_eq!(Some(available[2]), super::choose_language(&["de_de", "en-GB"], &available));
+    }
+
+    #[test]
+    fn it_chooses_language_with_shorter_tag() {
+        let available = languages(&[ENGLISH_UNITED_STATES, GERMAN_AUSTRIA, GERMAN_STANDARD]);
+
+        assert_eq!(Some(available[1]), super::choose_language(&["de-CH"], &available));
+        assert_eq!(Some(available[1]), super::choose_language(&["de"], &available));
+    }
+
+    #[test]
+    fn it_prefers_exact_match_over_extended_tag() {
+        let available = languages(&[SPANISH_TRADITIONAL_SORT, SPANISH_MODERN_SORT]);
+
+        assert_eq!(Some(available[1]), super::choose_language(&["es-ES"], &available));
+    }
+
+    #[test]
+    fn it_chooses_earlier_preference_over_closer_match() {
+        let available = languages(&[ENGLISH_UNITED_STATES, FRENCH_CANADIAN]);
+
+        assert_eq!(Some(available[1]), super::choose_language(&["fr-FR", "en-US"], &available));
+    }
+
+    #[test]
+    fn it_falls_back_to_first_available_language() {
+        let available = languages(&[JAPANESE, ENGLISH_UNITED_STATES]);
+
+        assert_eq!(Some(available[0]), super::choose_language(&["de-DE"], &available));
+        assert_eq!(Some(available[0]), super::choose_language(&[], &available));
+        assert_eq!(None, super::choose_language(&["en-US"], &[]));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn it_serializes_language_as_lang_id() {
+        use serde_json;
+
+        let language = super::from_lang_id(ENGLISH_UNITED_STATES);
+
+        assert_eq!("1033", serde_json::to_string(&language).unwrap());
+        assert_eq!(language, serde_json::from_str::<Language>("1033").unwrap());
+    }
 }
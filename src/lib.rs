@@ -5,22 +5,48 @@ extern crate bit_set;
 extern crate libusb_sys as libusb;
 extern crate libc;
 extern crate mio;
+#[cfg(feature = "serde")] #[macro_use] extern crate serde;
+#[cfg(all(test, feature = "serde"))] extern crate serde_json;
 
 pub use version::{LibraryVersion, version};
 pub use error::{Result, Error};
 
+pub use class_code::{ClassCode, Class};
 pub use fields::{Speed, TransferType, SyncType, UsageType, Direction, RequestType, Recipient, Version, request_type};
 pub use device_descriptor::DeviceDescriptor;
+pub use device_qualifier::DeviceQualifier;
 pub use config_descriptor::{ConfigDescriptor, Interfaces};
+pub use interface_association::{InterfaceAssociation, Function, Functions};
 pub use interface_descriptor::{Interface, InterfaceDescriptors, InterfaceDescriptor, EndpointDescriptors};
 pub use endpoint_descriptor::EndpointDescriptor;
-pub use language::{Language, PrimaryLanguage, SubLanguage};
+pub use endpoint_query::{EndpointQuery, EndpointLocation};
+pub use endpoint_companion::SuperSpeedEndpointCompanion;
+pub use extra_descriptors::{ExtraDescriptors, ExtraDescriptor};
+pub use bos_descriptor::{BosDescriptor, DeviceCapability, Usb2ExtensionCapability, SuperSpeedCapability,
+                         SuperSpeedPlusCapability, SublinkSpeed, Uuid, PlatformCapability, BillboardCapability,
+                         AlternateMode, AlternateModeState, UnknownCapability};
+pub use descriptor_parser::parse_descriptors;
+pub use descriptor_builder::{DeviceDescriptorBuilder, ConfigDescriptorBuilder, InterfaceDescriptorBuilder, EndpointDescriptorBuilder,
+                             InterfaceAssociationBuilder, string_descriptor, language_descriptor};
+pub use descriptor_lint::{lint_descriptors, lint_descriptor_bytes, Finding, FindingKind, FindingLocation, Severity};
+pub use language::{Language, PrimaryLanguage, SubLanguage, choose_language};
+pub use usb_ids::UsbIds;
+pub use transfer_options::{TransferOptions, TransferFlag};
+pub use setup_packet::{SetupPacket, SetupPacketBuilder};
+pub use standard_request::{DeviceStatus, Feature, TestMode};
 
 pub use context::{Context, LogLevel};
 pub use device_list::{DeviceList, Devices};
 pub use device::Device;
-pub use device_handle::DeviceHandle;
+pub use device_handle::{DeviceHandle, StallRecovery};
 pub use device_handle_sync_api::DeviceHandleSyncApi;
+pub use device_info::{DeviceInfo, ConfigInfo, InterfaceInfo, AltSettingInfo, EndpointInfo};
+pub use descriptor_dump::DescriptorDump;
+pub use bandwidth::{BandwidthUsage, EndpointBandwidth};
+pub use chapter9::{Chapter9Device, Chapter9Test, Chapter9Report, Chapter9Check, CheckKind, CheckOutcome};
+pub use stream_set::{StreamSet, Stream};
+pub use claimed_interface::ClaimedInterface;
+pub use endpoint::{Endpoint, EndpointDirection, EndpointKind, In, Out, Bulk, Interrupt};
 
 
 #[cfg(test)]
@@ -36,11 +62,32 @@ mod device_list;
 mod device;
 mod device_handle;
 mod device_handle_sync_api;
+mod device_info;
+mod descriptor_dump;
+mod chapter9;
+mod stream_set;
+mod claimed_interface;
+mod endpoint;
 
 mod fields;
+mod class_code;
 mod device_descriptor;
+mod device_qualifier;
+mod descriptor_parser;
+mod descriptor_builder;
+mod descriptor_lint;
+mod bandwidth;
 mod config_descriptor;
 mod interface_descriptor;
+mod interface_association;
 mod endpoint_descriptor;
+mod endpoint_query;
+mod endpoint_companion;
+mod extra_descriptors;
+mod bos_descriptor;
 mod language;
+mod usb_ids;
+mod transfer_options;
+mod setup_packet;
+mod standard_request;
 pub mod io;
@@ -0,0 +1,292 @@
+use libusb::*;
+
+use fields::{Direction, RequestType, Recipient, request_type};
+
+
+/// The setup packet of a control transfer.
+///
+/// A setup packet holds the `bmRequestType`, `bRequest`, `wValue`, `wIndex` and `wLength` fields
+/// that start every control transfer. Setup packets can be created from their raw fields with
+/// [`new`](#method.new), field by field with a [`SetupPacketBuilder`](struct.SetupPacketBuilder.html),
+/// or parsed from the 8 bytes sent over the bus with [`from_bytes`](#method.from_bytes).
+///
+/// The asynchronous `control` method of `DeviceHandle` takes a setup packet and the data stage
+/// separately. The buffer passed to its callback still starts with the 8-byte setup packet, so
+/// received data begins at offset [`SIZE`](#associatedconstant.SIZE).
+///
+/// ## Examples
+///
+/// A standard request that reads a device's descriptor:
+///
+/// ```
+/// use libusb::{SetupPacket, Direction, RequestType, Recipient};
+///
+/// let setup = SetupPacket::builder()
+///     .direction(Direction::In)
+///     .request_type(RequestType::Standard)
+///     .recipient(Recipient::Device)
+///     .request(0x06)
+///     .value(0x0100)
+///     .length(18)
+///     .build();
+///
+/// assert_eq!([0x80, 0x06, 0x00, 0x01, 0x00, 0x00, 0x12, 0x00], setup.to_bytes());
+/// ```
+#[derive(Debug,PartialEq,Eq,Clone,Copy,Hash)]
+pub struct SetupPacket {
+    request_type: u8,
+    request: u8,
+    value: u16,
+    index: u16,
+    length: u16,
+}
+
+impl SetupPacket {
+    /// The size of a setup packet in bytes.
+    pub const SIZE: usize = 8;
+
+    /// Creates a setup packet from its raw fields.
+    pub fn new(request_type: u8, request: u8, value: u16, index: u16, length: u16) -> Self {
+        SetupPacket {
+            request_type: request_type,
+            request: request,
+            value: value,
+            index: index,
+            length: length,
+        }
+    }
+
+    /// Returns a builder for a setup packet.
+    ///
+    /// The builder starts with a standard, outbound request to the device with all other fields
+    /// set to zero.
+    pub fn builder() -> SetupPacketBuilder {
+        SetupPacketBuilder {
+            direction: Direction::Out,
+            request_type: RequestType::Standard,
+            recipient: Recipient::Device,
+            packet: SetupPacket::new(0, 0, 0, 0, 0),
+        }
+    }
+
+    /// Parses a setup packet from its bytes.
+    ///
+    /// The multi-byte fields are little endian, as on the bus. Bytes after the first 8 are ignored.
+    ///
+    /// ## Errors
+    ///
+    /// * `Custom` if `bytes` is shorter than a setup packet.
+    pub fn from_bytes(bytes: &[u8]) -> ::Result<Self> {
+        if bytes.len() < Self::SIZE {
+            return Err(format!("setup packet needs {} bytes, got {}", Self::SIZE, bytes.len()).into());
+        }
+
+        Ok(SetupPacket {
+            request_type: bytes[0],
+            request: bytes[1],
+            value: bytes[2] as u16 | (bytes[3] as u16) << 8,
+            index: bytes[4] as u16 | (bytes[5] as u16) << 8,
+            length: bytes[6] as u16 | (bytes[7] as u16) << 8,
+        })
+    }
+
+    /// Returns the setup packet's bytes, as they are sent to the device.
+    pub fn to_bytes(&self) -> [u8; 8] {
+        [
+            self.request_type,
+            self.request,
+            self.value as u8,
+            (self.value >> 8) as u8,
+            self.index as u8,
+            (self.index >> 8) as u8,
+            self.length as u8,
+            (self.length >> 8) as u8,
+        ]
+    }
+
+    /// Returns the raw `bmRequestType` field.
+    pub fn raw_request_type(&self) -> u8 {
+        self.request_type
+    }
+
+    /// Returns the direction of the data stage.
+    pub fn direction(&self) -> Direction {
+        match self.request_type & LIBUSB_ENDPOINT_DIR_MASK {
+            LIBUSB_ENDPOINT_OUT    => Direction::Out,
+            LIBUSB_ENDPOINT_IN | _ => Direction::In,
+        }
+    }
+
+    /// Returns the type of the request.
+    pub fn request_type(&self) -> RequestType {
+        match self.request_type & 0x60 {
+            LIBUSB_REQUEST_TYPE_STANDARD     => RequestType::Standard,
+            LIBUSB_REQUEST_TYPE_CLASS        => RequestType::Class,
+            LIBUSB_REQUEST_TYPE_VENDOR       => RequestType::Vendor,
+            LIBUSB_REQUEST_TYPE_RESERVED | _ => RequestType::Reserved,
+        }
+    }
+
+    /// Returns the recipient of the request.
+    ///
+    /// Reserved recipient values are returned as `Recipient::Other`.
+    pub fn recipient(&self) -> Recipient {
+        match self.request_type & 0x1F {
+            LIBUSB_RECIPIENT_DEVICE    => Recipient::Device,
+            LIBUSB_RECIPIENT_INTERFACE => Recipient::Interface,
+            LIBUSB_RECIPIENT_ENDPOINT  => Recipient::Endpoint,
+            _                          => Recipient::Other,
+        }
+    }
+
+    /// Returns the `bRequest` field.
+    pub fn request(&self) -> u8 {
+        self.request
+    }
+
+    /// Returns the `wValue` field.
+    pub fn value(&self) -> u16 {
+        self.value
+    }
+
+    /// Returns the `wIndex` field.
+    pub fn index(&self) -> u16 {
+        self.index
+    }
+
+    /// Returns the `wLength` field, the length of the data stage.
+    pub fn length(&self) -> u16 {
+        self.length
+    }
+}
+
+/// Builds a [`SetupPacket`](struct.SetupPacket.html) field by field.
+#[derive(Debug,Clone,Copy)]
+pub struct SetupPacketBuilder {
+    direction: Direction,
+    request_type: RequestType,
+    recipient: Recipient,
+    packet: SetupPacket,
+}
+
+impl SetupPacketBuilder {
+    /// Sets the direction of the data stage.
+    pub fn direction(mut self, direction: Direction) -> Self {
+        self.direction = direction;
+        self
+    }
+
+    /// Sets the type of the request.
+    pub fn request_type(mut self, request_type: RequestType) -> Self {
+        self.request_type = request_type;
+        self
+    }
+
+    /// Sets the recipient of the request.
+    pub fn recipient(mut self, recipient: Recipient) -> Self {
+        self.recipient = recipient;
+        self
+    }
+
+    /// Sets the `bRequest` field.
+    pub fn request(mut self, request: u8) -> Self {
+        self.packet.request = request;
+        self
+    }
+
+    /// Sets the `wValue` field.
+    pub fn value(mut self, value: u16) -> Self {
+        self.packet.value = value;
+        self
+    }
+
+    /// Sets the `wIndex` field.
+    pub fn index(mut self, index: u16) -> Self {
+        self.packet.index = index;
+        self
+    }
+
+    /// Sets the `wLength` field, the length of the data stage.
+    pub fn length(mut self, length: u16) -> Self {
+        self.packet.length = length;
+        self
+    }
+
+    /// Builds the setup packet.
+    pub fn build(self) -> SetupPacket {
+        SetupPacket {
+            request_type: request_type(self.direction, self.request_type, self.recipient),
+            ..self.packet
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use fields::{Direction, RequestType, Recipient};
+    use super::SetupPacket;
+
+    #[test]
+    fn it_builds_request_type() {
+        let setup = SetupPacket::builder()
+            .direction(Direction::In)
+            .request_type(RequestType::Vendor)
+            .recipient(Recipient::Interface)
+            .build();
+
+        assert_eq!(0xC1, setup.raw_request_type());
+        assert_eq!(Direction::In, setup.direction());
+        assert_eq!(RequestType::Vendor, setup.request_type());
+        assert_eq!(Recipient::Interface, setup.recipient());
+    }
+
+    #[test]
+    fn it_defaults_to_standard_out_request_to_device() {
+        let setup = SetupPacket::builder().build();
+
+        assert_eq!(SetupPacket::new(0x00, 0, 0, 0, 0), setup);
+    }
+
+    #[test]
+    fn it_serializes_little_endian() {
+        let setup = SetupPacket::new(0x21, 0x09, 0x0200, 0x0003, 0x0040);
+
+        assert_eq!([0x21, 0x09, 0x00, 0x02, 0x03, 0x00, 0x40, 0x00], setup.to_bytes());
+    }
+
+    #[test]
+    fn it_parses_bytes() {
+        let setup = SetupPacket::from_bytes(&[0x82, 0x00, 0x00, 0x00, 0x81, 0x00, 0x02, 0x00]).unwrap();
+
+        assert_eq!(Direction::In, setup.direction());
+        assert_eq!(Recipient::Endpoint, setup.recipient());
+        assert_eq!(0x00, setup.request());
+        assert_eq!(0x0081, setup.index());
+        assert_eq!(2, setup.length());
+    }
+
+    #[test]
+    fn it_round_trips_bytes() {
+        let setup = SetupPacket::new(0xA1, 0xFE, 0x1234, 0xABCD, 0xFFFF);
+
+        assert_eq!(setup, SetupPacket::from_bytes(&setup.to_bytes()).unwrap());
+    }
+
+    #[test]
+    fn it_ignores_trailing_bytes() {
+        let setup = SetupPacket::from_bytes(&[0x80, 0x06, 0x00, 0x01, 0x00, 0x00, 0x12, 0x00, 0xFF]).unwrap();
+
+        assert_eq!(SetupPacket::new(0x80, 0x06, 0x0100, 0, 18), setup);
+    }
+
+    #[test]
+    fn it_rejects_short_bytes() {
+        assert!(SetupPacket::from_bytes(&[0x80, 0x06, 0x00, 0x01, 0x00, 0x00, 0x12]).is_err());
+    }
+
+    #[test]
+    fn it_maps_reserved_recipient_to_other() {
+        assert_eq!(Recipient::Other, SetupPacket::new(0x1F, 0, 0, 0, 0).recipient());
+    }
+}
@@ -0,0 +1,155 @@
+use fields::Recipient;
+
+
+/// The status of a device, as returned by a standard `GET_STATUS` request.
+#[derive(Debug,PartialEq,Eq,Clone,Copy,Hash)]
+pub struct DeviceStatus {
+    raw: u16,
+}
+
+impl DeviceStatus {
+    /// Indicates if the device is currently self-powered.
+    pub fn self_powered(&self) -> bool {
+        self.raw & 0x0001 != 0
+    }
+
+    /// Indicates if the device's remote wakeup feature is enabled.
+    pub fn remote_wakeup(&self) -> bool {
+        self.raw & 0x0002 != 0
+    }
+
+    /// Returns the raw status word.
+    ///
+    /// SuperSpeed devices report their link power management state in the upper bits.
+    pub fn raw(&self) -> u16 {
+        self.raw
+    }
+}
+
+/// A test mode selected with the `TEST_MODE` feature.
+///
+/// Test modes are used for electrical compliance testing of high-speed devices. A device leaves
+/// test mode only when it is power cycled.
+#[derive(Debug,PartialEq,Eq,Clone,Copy,Hash)]
+pub enum TestMode {
+    /// Transmit a continuous J state.
+    TestJ,
+
+    /// Transmit a continuous K state.
+    TestK,
+
+    /// Respond to IN tokens with NAK.
+    TestSe0Nak,
+
+    /// Transmit the test packet repeatedly.
+    TestPacket,
+
+    /// Enable the downstream port of a hub in forced mode.
+    TestForceEnable,
+}
+
+impl TestMode {
+    fn selector(&self) -> u8 {
+        match *self {
+            TestMode::TestJ           => 0x01,
+            TestMode::TestK           => 0x02,
+            TestMode::TestSe0Nak      => 0x03,
+            TestMode::TestPacket      => 0x04,
+            TestMode::TestForceEnable => 0x05,
+        }
+    }
+}
+
+/// A standard feature that can be set or cleared with `SET_FEATURE` and `CLEAR_FEATURE`.
+#[derive(Debug,PartialEq,Eq,Clone,Copy,Hash)]
+pub enum Feature {
+    /// The device's remote wakeup feature.
+    DeviceRemoteWakeup,
+
+    /// The halt feature of the endpoint with the given address.
+    EndpointHalt(u8),
+
+    /// The device's test mode. This feature can't be cleared.
+    TestMode(TestMode),
+}
+
+impl Feature {
+    /// Returns the feature's recipient.
+    pub fn recipient(&self) -> Recipient {
+        match *self {
+            Feature::DeviceRemoteWakeup => Recipient::Device,
+            Feature::EndpointHalt(_)    => Recipient::Endpoint,
+            Feature::TestMode(_)        => Recipient::Device,
+        }
+    }
+
+    /// Returns the `wValue` field of a request for this feature, the feature selector.
+    pub fn selector(&self) -> u16 {
+        match *self {
+            Feature::EndpointHalt(_)    => 0,
+            Feature::DeviceRemoteWakeup => 1,
+            Feature::TestMode(_)        => 2,
+        }
+    }
+
+    /// Returns the `wIndex` field of a request for this feature.
+    pub fn index(&self) -> u16 {
+        match *self {
+            Feature::DeviceRemoteWakeup => 0,
+            Feature::EndpointHalt(endpoint) => endpoint as u16,
+            Feature::TestMode(mode) => (mode.selector() as u16) << 8,
+        }
+    }
+}
+
+
+#[doc(hidden)]
+pub fn device_status_from_raw(raw: u16) -> DeviceStatus {
+    DeviceStatus { raw: raw }
+}
+
+
+#[cfg(test)]
+mod test {
+    use fields::Recipient;
+    use super::{Feature, TestMode, device_status_from_raw};
+
+    #[test]
+    fn it_has_self_powered_bit() {
+        assert!(device_status_from_raw(0x0001).self_powered());
+        assert!(!device_status_from_raw(0x0002).self_powered());
+    }
+
+    #[test]
+    fn it_has_remote_wakeup_bit() {
+        assert!(device_status_from_raw(0x0002).remote_wakeup());
+        assert!(!device_status_from_raw(0x0001).remote_wakeup());
+    }
+
+    #[test]
+    fn it_addresses_endpoint_halt_to_endpoint() {
+        let feature = Feature::EndpointHalt(0x81);
+
+        assert_eq!(Recipient::Endpoint, feature.recipient());
+        assert_eq!(0, feature.selector());
+        assert_eq!(0x0081, feature.index());
+    }
+
+    #[test]
+    fn it_addresses_remote_wakeup_to_device() {
+        let feature = Feature::DeviceRemoteWakeup;
+
+        assert_eq!(Recipient::Device, feature.recipient());
+        assert_eq!(1, feature.selector());
+        assert_eq!(0, feature.index());
+    }
+
+    #[test]
+    fn it_puts_test_selector_in_upper_index_byte() {
+        let feature = Feature::TestMode(TestMode::TestPacket);
+
+        assert_eq!(Recipient::Device, feature.recipient());
+        assert_eq!(2, feature.selector());
+        assert_eq!(0x0400, feature.index());
+    }
+}
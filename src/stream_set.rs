@@ -0,0 +1,148 @@
+use std::marker::PhantomData;
+
+use libc::c_int;
+use libusb::*;
+
+use io::IoType;
+use device_handle::{self, DeviceHandle};
+use device_handle_sync_api::DeviceHandleSyncApi;
+use transfer_options::TransferOptions;
+use error;
+
+
+/// A set of bulk streams allocated on one or more SuperSpeed endpoints.
+///
+/// Streams are allocated with [`DeviceHandle::alloc_streams`](struct.DeviceHandle.html#method.alloc_streams)
+/// and freed when the stream set goes out of scope. Every endpoint in the set has streams with IDs
+/// from `1` to [`num_streams()`](#method.num_streams).
+pub struct StreamSet<'dh, 'ctx: 'dh, Io>
+    where Io: IoType<'ctx>,
+{
+    handle: &'dh DeviceHandle<'ctx, Io>,
+    endpoints: Vec<u8>,
+    num_streams: u32,
+}
+
+impl<'dh, 'ctx, Io> Drop for StreamSet<'dh, 'ctx, Io>
+    where Io: IoType<'ctx>,
+{
+    /// Frees the streams.
+    fn drop(&mut self) {
+        if let Err(e) = self.free() {
+            warn!("Could not free streams on endpoints {:?}: {}", self.endpoints, e);
+        }
+    }
+}
+
+impl<'dh, 'ctx, Io> StreamSet<'dh, 'ctx, Io>
+    where Io: IoType<'ctx>,
+{
+    /// Returns the number of streams allocated on each endpoint.
+    ///
+    /// This can be less than the number of streams that was requested.
+    pub fn num_streams(&self) -> u32 {
+        self.num_streams
+    }
+
+    /// Returns the addresses of the endpoints that the streams were allocated on.
+    pub fn endpoints(&self) -> &[u8] {
+        &self.endpoints
+    }
+
+    /// Returns one of the allocated streams.
+    ///
+    /// ## Errors
+    ///
+    /// * `InvalidParam` if streams were not allocated on `endpoint` or if `stream_id` is not
+    ///   between `1` and `num_streams()`.
+    pub fn stream<'s>(&'s self, endpoint: u8, stream_id: u32) -> ::Result<Stream<'s, 'ctx, Io>> {
+        if !self.endpoints.contains(&endpoint) || stream_id == 0 || stream_id > self.num_streams {
+            return Err(::Error::InvalidParam);
+        }
+
+        Ok(Stream {
+            handle: self.handle,
+            endpoint: endpoint,
+            stream_id: stream_id,
+            _set: PhantomData,
+        })
+    }
+
+    /// Frees the streams, reporting any error.
+    ///
+    /// Streams are also freed when the stream set is dropped, but errors are only logged then.
+    pub fn free_streams(mut self) -> ::Result<()> {
+        self.free()
+    }
+
+    fn free(&mut self) -> ::Result<()> {
+        if self.endpoints.is_empty() {
+            return Ok(());
+        }
+
+        let raw = device_handle::as_raw(self.handle);
+        let res = unsafe { libusb_free_streams(raw, self.endpoints.as_mut_ptr(), self.endpoints.len() as c_int) };
+        self.endpoints.clear();
+
+        if res < 0 {
+            Err(error::from_libusb(res))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// A single bulk stream on an endpoint.
+pub struct Stream<'s, 'ctx: 's, Io>
+    where Io: IoType<'ctx>,
+{
+    handle: &'s DeviceHandle<'ctx, Io>,
+    endpoint: u8,
+    stream_id: u32,
+    _set: PhantomData<&'s ()>,
+}
+
+impl<'s, 'ctx, Io> Stream<'s, 'ctx, Io>
+    where Io: IoType<'ctx>,
+          DeviceHandle<'ctx, Io>: DeviceHandleSyncApi,
+{
+    /// Returns the address of the stream's endpoint.
+    pub fn endpoint(&self) -> u8 {
+        self.endpoint
+    }
+
+    /// Returns the stream ID.
+    pub fn stream_id(&self) -> u32 {
+        self.stream_id
+    }
+
+    /// Reads from the stream.
+    ///
+    /// This behaves like
+    /// [`read_bulk_with_options`](trait.DeviceHandleSyncApi.html#method.read_bulk_with_options) with
+    /// the stream's ID set in `options`.
+    pub fn read<O: Into<TransferOptions>>(&self, buf: &mut [u8], options: O) -> ::Result<usize> {
+        self.handle.read_bulk_with_options(self.endpoint, buf, &options.into().with_stream_id(self.stream_id))
+    }
+
+    /// Writes to the stream.
+    ///
+    /// This behaves like
+    /// [`write_bulk_with_options`](trait.DeviceHandleSyncApi.html#method.write_bulk_with_options) with
+    /// the stream's ID set in `options`.
+    pub fn write<O: Into<TransferOptions>>(&self, buf: &[u8], options: O) -> ::Result<usize> {
+        self.handle.write_bulk_with_options(self.endpoint, buf, &options.into().with_stream_id(self.stream_id))
+    }
+}
+
+
+#[doc(hidden)]
+pub fn from_libusb<'dh, 'ctx, Io>(handle: &'dh DeviceHandle<'ctx, Io>, endpoints: Vec<u8>, num_streams: u32) -> StreamSet<'dh, 'ctx, Io>
+    where Io: IoType<'ctx>,
+{
+    StreamSet {
+        handle: handle,
+        endpoints: endpoints,
+        num_streams: num_streams,
+    }
+}
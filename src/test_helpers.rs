@@ -162,3 +162,69 @@ macro_rules! device_descriptor {
         )
     }
 }
+
+/// Parses a built configuration descriptor like one that was read from a device.
+pub fn build_config(builder: ::ConfigDescriptorBuilder) -> ::ConfigDescriptor {
+    ::ConfigDescriptor::from_bytes(&builder.build()).unwrap()
+}
+
+/// A device that answers control requests from a closure and records them.
+///
+/// Inbound requests are answered with the returned bytes, truncated to the request's length.
+/// Outbound requests succeed with the full length if the closure returns `Ok`. Bulk and interrupt
+/// transfers are not supported.
+pub struct MockDevice {
+    pub requests: ::std::cell::RefCell<Vec<(::SetupPacket, Vec<u8>)>>,
+    handler: Box<Fn(&::SetupPacket, &[u8]) -> ::Result<Vec<u8>>>,
+}
+
+impl MockDevice {
+    pub fn new<F>(handler: F) -> Self
+        where F: Fn(&::SetupPacket, &[u8]) -> ::Result<Vec<u8>> + 'static,
+    {
+        MockDevice {
+            requests: ::std::cell::RefCell::new(Vec::new()),
+            handler: Box::new(handler),
+        }
+    }
+
+    /// Returns the setup packets of all requests made so far.
+    pub fn setups(&self) -> Vec<::SetupPacket> {
+        self.requests.borrow().iter().map(|&(setup, _)| setup).collect()
+    }
+
+    fn request(&self, setup: ::SetupPacket, data: &[u8]) -> ::Result<Vec<u8>> {
+        self.requests.borrow_mut().push((setup, data.to_vec()));
+        (self.handler)(&setup, data)
+    }
+}
+
+impl ::DeviceHandleSyncApi for MockDevice {
+    fn read_interrupt(&self, _: u8, _: &mut [u8], _: ::std::time::Duration) -> ::Result<usize> {
+        Err(::Error::NotSupported)
+    }
+
+    fn write_interrupt(&self, _: u8, _: &[u8], _: ::std::time::Duration) -> ::Result<usize> {
+        Err(::Error::NotSupported)
+    }
+
+    fn read_bulk(&self, _: u8, _: &mut [u8], _: ::std::time::Duration) -> ::Result<usize> {
+        Err(::Error::NotSupported)
+    }
+
+    fn write_bulk(&self, _: u8, _: &[u8], _: ::std::time::Duration) -> ::Result<usize> {
+        Err(::Error::NotSupported)
+    }
+
+    fn read_control(&self, request_type: u8, request: u8, value: u16, index: u16, buf: &mut [u8], _: ::std::time::Duration) -> ::Result<usize> {
+        let response = self.request(::SetupPacket::new(request_type, request, value, index, buf.len() as u16), &[])?;
+        let len = ::std::cmp::min(response.len(), buf.len());
+        buf[..len].copy_from_slice(&response[..len]);
+        Ok(len)
+    }
+
+    fn write_control(&self, request_type: u8, request: u8, value: u16, index: u16, buf: &[u8], _: ::std::time::Duration) -> ::Result<usize> {
+        self.request(::SetupPacket::new(request_type, request, value, index, buf.len() as u16), buf)?;
+        Ok(buf.len())
+    }
+}
@@ -0,0 +1,206 @@
+use std::time::Duration;
+
+use libc::c_uint;
+use libusb::*;
+
+/// Flags that change how `libusb` performs a transfer.
+///
+/// `LIBUSB_TRANSFER_FREE_BUFFER` and `LIBUSB_TRANSFER_FREE_TRANSFER` are not offered: transfer
+/// buffers are owned by Rust and transfers are always freed by this crate, so letting `libusb` free
+/// them would result in a double free.
+#[derive(Debug,PartialEq,Eq,Clone,Copy,Hash)]
+pub enum TransferFlag {
+    /// Report short frames as errors.
+    ///
+    /// A transfer that receives less data than requested fails with `Io` instead of completing
+    /// with a short length.
+    ShortNotOk,
+
+    /// Terminate the transfer with a zero-length packet.
+    ///
+    /// If the length of an outgoing transfer is a multiple of the endpoint's maximum packet size,
+    /// an additional zero-length packet is sent to mark the end of the transfer. This flag is only
+    /// supported on some platforms.
+    AddZeroPacket,
+}
+
+impl TransferFlag {
+    fn as_libusb(&self) -> u8 {
+        match *self {
+            TransferFlag::ShortNotOk    => LIBUSB_TRANSFER_SHORT_NOT_OK,
+            TransferFlag::AddZeroPacket => LIBUSB_TRANSFER_ADD_ZERO_PACKET,
+        }
+    }
+}
+
+/// Options for a single transfer.
+///
+/// Transfer options combine the timeout, the transfer flags and the bulk stream ID of a transfer.
+/// The asynchronous transfer methods accept anything that converts into `TransferOptions`, so a
+/// plain `Duration` can be given where only a timeout is needed. The synchronous API takes them in
+/// the `*_with_options` methods of [`DeviceHandleSyncApi`](trait.DeviceHandleSyncApi.html).
+///
+/// ## Examples
+///
+/// ```
+/// use std::time::Duration;
+/// use libusb::{TransferOptions, TransferFlag};
+///
+/// let options = TransferOptions::new(Duration::from_secs(1))
+///     .with_flag(TransferFlag::ShortNotOk);
+///
+/// assert!(options.has_flag(TransferFlag::ShortNotOk));
+/// assert!(!options.has_flag(TransferFlag::AddZeroPacket));
+/// ```
+#[derive(Debug,PartialEq,Eq,Clone,Copy,Hash,Default)]
+pub struct TransferOptions {
+    timeout: Duration,
+    flags: u8,
+    stream_id: Option<u32>,
+}
+
+impl TransferOptions {
+    /// Creates transfer options with the given timeout and no flags.
+    ///
+    /// A timeout of zero means that the transfer never times out.
+    pub fn new(timeout: Duration) -> Self {
+        TransferOptions {
+            timeout: timeout,
+            flags: 0,
+            stream_id: None,
+        }
+    }
+
+    /// Sets a transfer flag.
+    pub fn with_flag(mut self, flag: TransferFlag) -> Self {
+        self.flags |= flag.as_libusb();
+        self
+    }
+
+    /// Sets the bulk stream ID used for the transfer.
+    ///
+    /// Stream IDs are only meaningful for bulk endpoints that have streams allocated. They are used
+    /// by the synchronous bulk methods; all other transfers fail with `InvalidParam` when given
+    /// one. Asynchronous stream transfers take their stream ID as an argument of `bulk_stream`
+    /// instead.
+    pub fn with_stream_id(mut self, stream_id: u32) -> Self {
+        self.stream_id = Some(stream_id);
+        self
+    }
+
+    /// Returns the transfer's timeout.
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    /// Indicates if a transfer flag is set.
+    pub fn has_flag(&self, flag: TransferFlag) -> bool {
+        self.flags & flag.as_libusb() != 0
+    }
+
+    /// Returns the bulk stream ID used for the transfer.
+    pub fn stream_id(&self) -> Option<u32> {
+        self.stream_id
+    }
+
+    #[doc(hidden)]
+    pub fn timeout_ms(&self) -> c_uint {
+        (self.timeout.as_secs() * 1000 + self.timeout.subsec_nanos() as u64 / 1_000_000) as c_uint
+    }
+
+    #[doc(hidden)]
+    pub fn libusb_flags(&self) -> u8 {
+        self.flags
+    }
+}
+
+impl From<Duration> for TransferOptions {
+    fn from(timeout: Duration) -> Self {
+        TransferOptions::new(timeout)
+    }
+}
+
+/// Rejects options with a stream ID for transfers that can't use one.
+#[doc(hidden)]
+pub fn check_no_stream_id(options: &TransferOptions) -> ::Result<()> {
+    if options.stream_id.is_some() {
+        return Err(::Error::InvalidParam);
+    }
+
+    Ok(())
+}
+
+/// Returns the options without their stream ID.
+#[doc(hidden)]
+pub fn without_stream_id(mut options: TransferOptions) -> TransferOptions {
+    options.stream_id = None;
+    options
+}
+
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+    use libusb::*;
+    use error::Error;
+    use super::{TransferOptions, TransferFlag, check_no_stream_id, without_stream_id};
+
+    #[test]
+    fn it_converts_from_timeout() {
+        let options = TransferOptions::from(Duration::from_millis(1500));
+
+        assert_eq!(Duration::from_millis(1500), options.timeout());
+        assert_eq!(0, options.libusb_flags());
+        assert_eq!(None, options.stream_id());
+    }
+
+    #[test]
+    fn it_has_timeout_in_milliseconds() {
+        assert_eq!(0,    TransferOptions::default().timeout_ms());
+        assert_eq!(1500, TransferOptions::new(Duration::from_millis(1500)).timeout_ms());
+        assert_eq!(2000, TransferOptions::new(Duration::new(2, 999)).timeout_ms());
+    }
+
+    #[test]
+    fn it_sets_short_not_ok_flag() {
+        let options = TransferOptions::default().with_flag(TransferFlag::ShortNotOk);
+
+        assert!(options.has_flag(TransferFlag::ShortNotOk));
+        assert_eq!(LIBUSB_TRANSFER_SHORT_NOT_OK, options.libusb_flags());
+    }
+
+    #[test]
+    fn it_sets_add_zero_packet_flag() {
+        let options = TransferOptions::default().with_flag(TransferFlag::AddZeroPacket);
+
+        assert!(options.has_flag(TransferFlag::AddZeroPacket));
+        assert_eq!(LIBUSB_TRANSFER_ADD_ZERO_PACKET, options.libusb_flags());
+    }
+
+    #[test]
+    fn it_combines_flags() {
+        let options = TransferOptions::default()
+            .with_flag(TransferFlag::ShortNotOk)
+            .with_flag(TransferFlag::AddZeroPacket);
+
+        assert_eq!(LIBUSB_TRANSFER_SHORT_NOT_OK | LIBUSB_TRANSFER_ADD_ZERO_PACKET, options.libusb_flags());
+    }
+
+    #[test]
+    fn it_has_stream_id() {
+        assert_eq!(Some(3), TransferOptions::default().with_stream_id(3).stream_id());
+    }
+
+    #[test]
+    fn it_rejects_stream_id_where_none_is_used() {
+        assert!(check_no_stream_id(&TransferOptions::default()).is_ok());
+        assert!(match check_no_stream_id(&TransferOptions::default().with_stream_id(3)) { Err(Error::InvalidParam) => true, _ => false });
+    }
+
+    #[test]
+    fn it_removes_stream_id() {
+        let options = TransferOptions::new(Duration::from_secs(1)).with_flag(TransferFlag::ShortNotOk).with_stream_id(3);
+
+        assert_eq!(TransferOptions::new(Duration::from_secs(1)).with_flag(TransferFlag::ShortNotOk), without_stream_id(options));
+    }
+}
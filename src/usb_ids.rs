@@ -0,0 +1,409 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+
+use language::Language;
+use error::Error;
+
+/// Locations of the system copy of `usb.ids`, in the order they're tried.
+const SYSTEM_PATHS: &'static [&'static str] = &[
+    "/usr/share/hwdata/usb.ids",
+    "/var/lib/usbutils/usb.ids",
+];
+
+
+/// Names from the USB ID database, in the `usb.ids` format maintained at
+/// http://www.linux-usb.org/usb.ids.
+///
+/// The database names vendors and their products, device and interface classes with their sub
+/// classes and protocols, HID usage pages with their usages, and languages with their dialects.
+/// It gives human-readable names for devices without string descriptors, or that can't be opened
+/// to read them. Other sections of the file are ignored.
+///
+/// The database can be parsed from text, loaded from a file, or loaded from the system copy
+/// that's installed by `usbutils` or `hwdata`.
+///
+/// ## Examples
+///
+/// ```
+/// let ids = libusb::UsbIds::parse("1d6b  Linux Foundation\n\t0002  2.0 root hub\n");
+///
+/// assert_eq!(Some("Linux Foundation"), ids.vendor_name(0x1d6b));
+/// assert_eq!(Some("2.0 root hub"), ids.product_name(0x1d6b, 0x0002));
+/// ```
+#[derive(Debug,Default,Clone)]
+pub struct UsbIds {
+    vendors: HashMap<u16, Entry<u16>>,
+    classes: HashMap<u8, ClassEntry>,
+    usage_pages: HashMap<u16, Entry<u16>>,
+    languages: HashMap<u16, Entry<u8>>,
+}
+
+impl UsbIds {
+    /// Parses a database in the `usb.ids` format.
+    ///
+    /// Malformed entries are logged and skipped, along with the entries that are indented under
+    /// them, so that one bad line doesn't make the rest of the database unusable.
+    pub fn parse(text: &str) -> Self {
+        let mut ids = UsbIds::default();
+        let mut section = Section::Ignored;
+
+        for (index, line) in text.lines().enumerate() {
+            let line = line.trim_end();
+
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            if let Err(message) = ids.parse_line(&mut section, line) {
+                warn!("usb.ids line {}: {}", index + 1, message);
+
+                if !line.starts_with('\t') {
+                    section = Section::Ignored;
+                }
+            }
+        }
+
+        ids
+    }
+
+    /// Loads a database from a file in the `usb.ids` format.
+    ///
+    /// ## Errors
+    ///
+    /// * `NotFound` if the file doesn't exist.
+    /// * `Access` if the file can't be read because of its permissions.
+    /// * `Custom` if the file can't be read for other reasons.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> ::Result<Self> {
+        let path = path.as_ref();
+        let mut text = String::new();
+
+        let res = File::open(path).and_then(|mut file| file.read_to_string(&mut text));
+
+        match res {
+            Ok(_) => Ok(UsbIds::parse(&text)),
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => Err(Error::NotFound),
+            Err(ref e) if e.kind() == io::ErrorKind::PermissionDenied => Err(Error::Access),
+            Err(e) => Err(format!("could not read {}: {}", path.display(), e).into()),
+        }
+    }
+
+    /// Loads the system copy of the database.
+    ///
+    /// `/usr/share/hwdata/usb.ids` and `/var/lib/usbutils/usb.ids` are tried in turn.
+    ///
+    /// ## Errors
+    ///
+    /// * `NotFound` if there's no system copy.
+    /// * Any error of [`from_file`](#method.from_file) for the first copy that exists.
+    pub fn system() -> ::Result<Self> {
+        for path in SYSTEM_PATHS {
+            match UsbIds::from_file(path) {
+                Err(Error::NotFound) => continue,
+                res => return res,
+            }
+        }
+
+        Err(Error::NotFound)
+    }
+
+    /// Returns the name of a vendor.
+    pub fn vendor_name(&self, vendor_id: u16) -> Option<&str> {
+        self.vendors.get(&vendor_id).map(|vendor| &vendor.name[..])
+    }
+
+    /// Returns the name of one of a vendor's products.
+    pub fn product_name(&self, vendor_id: u16, product_id: u16) -> Option<&str> {
+        self.vendors.get(&vendor_id).and_then(|vendor| vendor.child(product_id))
+    }
+
+    /// Returns the name of a class.
+    pub fn class_name(&self, class_code: u8) -> Option<&str> {
+        self.classes.get(&class_code).map(|class| &class.name[..])
+    }
+
+    /// Returns the name of a class's sub class.
+    pub fn sub_class_name(&self, class_code: u8, sub_class_code: u8) -> Option<&str> {
+        self.sub_class(class_code, sub_class_code).map(|sub_class| &sub_class.name[..])
+    }
+
+    /// Returns the name of a sub class's protocol.
+    pub fn protocol_name(&self, class_code: u8, sub_class_code: u8, protocol_code: u8) -> Option<&str> {
+        self.sub_class(class_code, sub_class_code).and_then(|sub_class| sub_class.child(protocol_code))
+    }
+
+    /// Returns the name of a HID usage page.
+    pub fn usage_page_name(&self, usage_page: u16) -> Option<&str> {
+        self.usage_pages.get(&usage_page).map(|page| &page.name[..])
+    }
+
+    /// Returns the name of a HID usage within a usage page.
+    pub fn usage_name(&self, usage_page: u16, usage: u16) -> Option<&str> {
+        self.usage_pages.get(&usage_page).and_then(|page| page.child(usage))
+    }
+
+    /// Returns the name of a language's primary language.
+    pub fn language_name(&self, language: Language) -> Option<&str> {
+        self.languages.get(&(language.lang_id() & 0x03FF)).map(|entry| &entry.name[..])
+    }
+
+    /// Returns the name of a language's dialect.
+    pub fn dialect_name(&self, language: Language) -> Option<&str> {
+        self.languages.get(&(language.lang_id() & 0x03FF)).and_then(|entry| entry.child((language.lang_id() >> 10) as u8))
+    }
+
+    fn sub_class(&self, class_code: u8, sub_class_code: u8) -> Option<&Entry<u8>> {
+        self.classes.get(&class_code).and_then(|class| class.sub_classes.get(&sub_class_code))
+    }
+
+    fn parse_line(&mut self, section: &mut Section, line: &str) -> Result<(), String> {
+        if line.starts_with("\t\t") {
+            if let Section::Class(class, Some(sub_class)) = *section {
+                let (id, name) = entry(&line[2..], 2)?;
+
+                if let Some(sub_class) = self.classes.get_mut(&class).and_then(|c| c.sub_classes.get_mut(&sub_class)) {
+                    sub_class.insert(id as u8, name);
+                }
+            }
+        }
+        else if line.starts_with('\t') {
+            match *section {
+                Section::Vendor(vendor) => {
+                    let (id, name) = entry(&line[1..], 4)?;
+
+                    if let Some(vendor) = self.vendors.get_mut(&vendor) {
+                        vendor.insert(id, name);
+                    }
+                },
+                Section::Class(class, _) => {
+                    let (id, name) = entry(&line[1..], 2)?;
+
+                    if let Some(class) = self.classes.get_mut(&class) {
+                        class.sub_classes.insert(id as u8, Entry::new(name));
+                    }
+
+                    *section = Section::Class(class, Some(id as u8));
+                },
+                Section::UsagePage(page) => {
+                    let (id, name) = entry(&line[1..], 4)?;
+
+                    if let Some(page) = self.usage_pages.get_mut(&page) {
+                        page.insert(id, name);
+                    }
+                },
+                Section::Language(language) => {
+                    let (id, name) = entry(&line[1..], 2)?;
+
+                    if let Some(language) = self.languages.get_mut(&language) {
+                        language.insert(id as u8, name);
+                    }
+                },
+                Section::Ignored => (),
+            }
+        }
+        else if line.starts_with("C ") {
+            let (id, name) = entry(&line[2..], 2)?;
+            self.classes.insert(id as u8, ClassEntry { name: name, sub_classes: HashMap::new() });
+            *section = Section::Class(id as u8, None);
+        }
+        else if line.starts_with("HUT ") {
+            let (id, name) = entry(&line[4..], 2)?;
+            self.usage_pages.insert(id, Entry::new(name));
+            *section = Section::UsagePage(id);
+        }
+        else if line.starts_with("L ") {
+            let (id, name) = entry(&line[2..], 4)?;
+            self.languages.insert(id, Entry::new(name));
+            *section = Section::Language(id);
+        }
+        else if is_vendor(line) {
+            let (id, name) = entry(line, 4)?;
+            self.vendors.insert(id, Entry::new(name));
+            *section = Section::Vendor(id);
+        }
+        else {
+            *section = Section::Ignored;
+        }
+
+        Ok(())
+    }
+}
+
+
+/// The section that indented lines belong to.
+#[derive(Debug,Clone,Copy)]
+enum Section {
+    Vendor(u16),
+    Class(u8, Option<u8>),
+    UsagePage(u16),
+    Language(u16),
+    Ignored,
+}
+
+/// A named entry with named children, such as a vendor and its products.
+#[derive(Debug,Clone)]
+struct Entry<K> {
+    name: String,
+    children: HashMap<K, String>,
+}
+
+impl<K: ::std::hash::Hash + Eq> Entry<K> {
+    fn new(name: String) -> Self {
+        Entry {
+            name: name,
+            children: HashMap::new(),
+        }
+    }
+
+    fn insert(&mut self, id: K, name: String) {
+        self.children.insert(id, name);
+    }
+
+    fn child(&self, id: K) -> Option<&str> {
+        self.children.get(&id).map(|name| &name[..])
+    }
+}
+
+#[derive(Debug,Clone)]
+struct ClassEntry {
+    name: String,
+    sub_classes: HashMap<u8, Entry<u8>>,
+}
+
+/// Indicates if a line starts a vendor entry, whose ID is four hexadecimal digits without a
+/// section keyword.
+fn is_vendor(line: &str) -> bool {
+    let bytes = line.as_bytes();
+
+    bytes.len() > 4 && bytes[..4].iter().all(u8::is_ascii_hexdigit) && bytes[4] == b' '
+}
+
+/// Splits an entry into its hexadecimal ID of up to `digits` digits and its name, which are
+/// separated by two spaces.
+fn entry(line: &str, digits: usize) -> Result<(u16, String), String> {
+    let mut parts = line.splitn(2, "  ");
+    let id = parts.next().unwrap_or("");
+    let name = parts.next().map(str::trim);
+
+    match (u16::from_str_radix(id, 16), name) {
+        (Ok(n), Some(name)) if id.len() <= digits && !name.is_empty() => Ok((n, name.to_string())),
+        _ => Err(format!("malformed entry {:?}", line)),
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use language;
+    use error::Error;
+    use super::UsbIds;
+
+    const IDS: &'static str = concat!(
+        "#\n",
+        "#\tList of USB ID's\n",
+        "#\n",
+        "\n",
+        "1d6b  Linux Foundation\n",
+        "\t0001  1.1 root hub\n",
+        "\t0002  2.0 root hub\n",
+        "1209  Generic\n",
+        "\t0001  pid.codes Test PID\n",
+        "\t\t00  Interface names are ignored\n",
+        "\n",
+        "# List of known device classes, subclasses and protocols\n",
+        "C 03  Human Interface Device\n",
+        "\t00  No Subclass\n",
+        "\t01  Boot Interface Subclass\n",
+        "\t\t01  Keyboard\n",
+        "\t\t02  Mouse\n",
+        "C 08  Mass Storage\n",
+        "\t06  SCSI\n",
+        "\t\t50  Bulk-Only\n",
+        "\n",
+        "AT 0101  USB Streaming\n",
+        "HID 21  HID\n",
+        "BIAS 0  Not Applicable\n",
+        "R 04  Usage Page\n",
+        "\n",
+        "HUT 01  Generic Desktop Controls\n",
+        "\t002  Mouse\n",
+        "\t006  Keyboard\n",
+        "\n",
+        "L 0009  English\n",
+        "\t01  US\n",
+        "\t02  UK\n",
+        "\n",
+        "VT 0100  USB Vendor Specific\n",
+        "\t00  Ignored\n",
+    );
+
+    fn ids() -> UsbIds {
+        UsbIds::parse(IDS)
+    }
+
+    #[test]
+    fn it_parses_vendors_and_products() {
+        assert_eq!(Some("Linux Foundation"), ids().vendor_name(0x1d6b));
+        assert_eq!(Some("2.0 root hub"), ids().product_name(0x1d6b, 0x0002));
+        assert_eq!(Some("pid.codes Test PID"), ids().product_name(0x1209, 0x0001));
+        assert_eq!(None, ids().product_name(0x1209, 0x0002));
+        assert_eq!(None, ids().vendor_name(0x0000));
+    }
+
+    #[test]
+    fn it_parses_classes() {
+        assert_eq!(Some("Human Interface Device"), ids().class_name(0x03));
+        assert_eq!(Some("Boot Interface Subclass"), ids().sub_class_name(0x03, 0x01));
+        assert_eq!(Some("Mouse"), ids().protocol_name(0x03, 0x01, 0x02));
+        assert_eq!(Some("Bulk-Only"), ids().protocol_name(0x08, 0x06, 0x50));
+        assert_eq!(None, ids().protocol_name(0x03, 0x00, 0x01));
+    }
+
+    #[test]
+    fn it_parses_hid_usages() {
+        assert_eq!(Some("Generic Desktop Controls"), ids().usage_page_name(0x01));
+        assert_eq!(Some("Keyboard"), ids().usage_name(0x01, 0x06));
+    }
+
+    #[test]
+    fn it_parses_languages() {
+        let language = language::from_lang_id(0x0809);
+
+        assert_eq!(Some("English"), ids().language_name(language));
+        assert_eq!(Some("UK"), ids().dialect_name(language));
+    }
+
+    #[test]
+    fn it_ignores_other_sections() {
+        let ids = ids();
+
+        assert_eq!(None, ids.usage_name(0x0100, 0x00));
+        assert_eq!(None, ids.vendor_name(0x0101));
+    }
+
+    #[test]
+    fn it_skips_malformed_entries() {
+        let ids = UsbIds::parse("1d6b  Linux Foundation\n\tzzzz  Not hex\n\t0002  2.0 root hub\n");
+
+        assert_eq!(Some("Linux Foundation"), ids.vendor_name(0x1d6b));
+        assert_eq!(Some("2.0 root hub"), ids.product_name(0x1d6b, 0x0002));
+    }
+
+    #[test]
+    fn it_skips_entries_under_malformed_sections() {
+        let ids = UsbIds::parse("C 03\n\t01  Boot Interface Subclass\nC 08  Mass Storage\n\t06  SCSI\n");
+
+        assert_eq!(None, ids.class_name(0x03));
+        assert_eq!(None, ids.sub_class_name(0x03, 0x01));
+        assert_eq!(Some("SCSI"), ids.sub_class_name(0x08, 0x06));
+    }
+
+    #[test]
+    fn it_returns_not_found_for_missing_files() {
+        match UsbIds::from_file("/nonexistent/usb.ids") {
+            Err(Error::NotFound) => (),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+}